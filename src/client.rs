@@ -0,0 +1,406 @@
+//! # 客户端同步状态机
+//!
+//! 实现上参考了 [ot.js 的 Client](https://github.com/Operational-Transformation/ot.js/blob/master/lib/client.js)。
+//!
+//! 客户端同一时刻最多只有一个已发送但未被服务端确认的操作（outstanding），
+//! 在此期间产生的本地操作被合并（compose）到缓冲区（buffer）中，收到确认后再发送；
+//! 收到的远端操作则与 outstanding、buffer 进行转换（transform）后再应用到本地文档。
+//!
+//! 与 ot.js 通过回调（`sendOperation`、`applyOperation`）通知调用方不同，
+//! 这里的方法直接返回需要发送给服务端的操作（[`Outgoing`]）或需要应用到本地文档的操作，由调用方负责网络与文档。
+//!
+//! # Example
+//! ```
+//! use ot_rs::client::{Client, Outgoing};
+//! use ot_rs::core::TextOperation;
+//!
+//! let mut client: Client = Client::new(0);
+//! let mut doc = "ab".to_string();
+//!
+//! // 本地编辑：立即应用到本地文档，并发送给服务端
+//! let mut local = TextOperation::new();
+//! local.retain(2).insert("c");
+//! doc = local.apply(doc.as_str()).unwrap();
+//! let outgoing = client.apply_client(local).unwrap().unwrap();
+//! assert_eq!(0, outgoing.revision);
+//!
+//! // 在确认之前收到了其他客户端的操作：转换后应用到本地文档
+//! let mut remote = TextOperation::new();
+//! remote.insert("x").retain(2);
+//! let transformed = client.apply_server(remote).unwrap();
+//! doc = transformed.apply(doc.as_str()).unwrap();
+//! assert_eq!("xabc", doc);
+//!
+//! // 收到确认
+//! assert_eq!(None, client.server_ack().unwrap());
+//! assert_eq!(2, client.revision());
+//! ```
+
+use crate::core::{OperationError, Selection, Side, TextOperation};
+use crate::types::{OtType, UnitTypeNames};
+use std::fmt;
+
+/// 客户端的状态
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClientState<Op> {
+    /// 没有未被确认的操作
+    Synchronized,
+    /// 有一个已发送、等待确认的操作
+    AwaitingConfirm(Op),
+    /// 有一个已发送、等待确认的操作，以及一个尚未发送的缓冲操作：`(outstanding, buffer)`
+    AwaitingWithBuffer(Op, Op),
+}
+
+/// 需要发送给服务端的操作
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outgoing<Op> {
+    /// 操作所基于的版本
+    pub revision: usize,
+    /// 操作
+    pub operation: Op,
+}
+
+/// 客户端同步状态机，参见[模块文档](self)
+pub struct Client<T: OtType = TextOperation> {
+    revision: usize,
+    state: ClientState<T::Op>,
+}
+
+impl<T: OtType> Client<T> {
+    /// 以服务端的文档版本 `revision` 构造一个处于 `Synchronized` 状态的客户端
+    pub fn new(revision: usize) -> Client<T> {
+        return Client {
+            revision,
+            state: ClientState::Synchronized,
+        };
+    }
+
+    /// 客户端所知的最新的服务端版本
+    pub fn revision(&self) -> usize {
+        self.revision
+    }
+
+    /// 当前状态
+    pub fn state(&self) -> &ClientState<T::Op> {
+        &self.state
+    }
+
+    /// 本地产生了操作 `operation`（调用方应已将其应用到本地文档），
+    /// 返回需要立即发送给服务端的操作；正在等待确认时操作被合并到缓冲区中，返回 `None`
+    pub fn apply_client(
+        &mut self,
+        operation: T::Op,
+    ) -> Result<Option<Outgoing<T::Op>>, OperationError> {
+        let (state, outgoing) = match std::mem::replace(&mut self.state, ClientState::Synchronized)
+        {
+            ClientState::Synchronized => {
+                let outgoing = Outgoing {
+                    revision: self.revision,
+                    operation: operation.clone(),
+                };
+                (ClientState::AwaitingConfirm(operation), Some(outgoing))
+            }
+            ClientState::AwaitingConfirm(outstanding) => (
+                ClientState::AwaitingWithBuffer(outstanding, operation),
+                None,
+            ),
+            ClientState::AwaitingWithBuffer(outstanding, buffer) => {
+                let buffer = match T::compose(&buffer, &operation) {
+                    Ok(buffer) => buffer,
+                    Err(e) => {
+                        self.state = ClientState::AwaitingWithBuffer(outstanding, buffer);
+                        return Err(e);
+                    }
+                };
+                (ClientState::AwaitingWithBuffer(outstanding, buffer), None)
+            }
+        };
+        self.state = state;
+        return Ok(outgoing);
+    }
+
+    /// 收到了其他客户端的操作 `operation`，返回转换后需要应用到本地文档的操作
+    pub fn apply_server(&mut self, operation: T::Op) -> Result<T::Op, OperationError> {
+        let (state, transformed) = match &self.state {
+            ClientState::Synchronized => (ClientState::Synchronized, operation),
+            ClientState::AwaitingConfirm(outstanding) => {
+                let (outstanding, operation) =
+                    T::transform_pair(outstanding, &operation, Side::Left)?;
+                (ClientState::AwaitingConfirm(outstanding), operation)
+            }
+            ClientState::AwaitingWithBuffer(outstanding, buffer) => {
+                let (outstanding, operation) =
+                    T::transform_pair(outstanding, &operation, Side::Left)?;
+                let (buffer, operation) = T::transform_pair(buffer, &operation, Side::Left)?;
+                (
+                    ClientState::AwaitingWithBuffer(outstanding, buffer),
+                    operation,
+                )
+            }
+        };
+        self.revision += 1;
+        self.state = state;
+        return Ok(transformed);
+    }
+
+    /// 服务端确认了等待确认的操作，返回接下来需要发送的缓冲操作；
+    /// 没有等待确认的操作时返回 `NoPendingOperation`
+    pub fn server_ack(&mut self) -> Result<Option<Outgoing<T::Op>>, OperationError> {
+        let (state, outgoing) = match std::mem::replace(&mut self.state, ClientState::Synchronized)
+        {
+            ClientState::Synchronized => return Err(OperationError::NoPendingOperation),
+            ClientState::AwaitingConfirm(_) => (ClientState::Synchronized, None),
+            ClientState::AwaitingWithBuffer(_, buffer) => {
+                let outgoing = Outgoing {
+                    revision: self.revision + 1,
+                    operation: buffer.clone(),
+                };
+                (ClientState::AwaitingConfirm(buffer), Some(outgoing))
+            }
+        };
+        self.revision += 1;
+        self.state = state;
+        return Ok(outgoing);
+    }
+
+    /// 与服务端重新建立连接后调用，返回需要重新发送的等待确认的操作
+    pub fn server_reconnect(&self) -> Option<Outgoing<T::Op>> {
+        match &self.state {
+            ClientState::Synchronized => None,
+            ClientState::AwaitingConfirm(outstanding)
+            | ClientState::AwaitingWithBuffer(outstanding, _) => Some(Outgoing {
+                revision: self.revision,
+                operation: outstanding.clone(),
+            }),
+        }
+    }
+}
+
+impl<U: UnitTypeNames> Client<TextOperation<U>> {
+    /// 将一个基于服务端当前版本的选择（例如其他协作者的光标，参见 [`presence`](crate::presence)）
+    /// 依次通过尚未被确认的操作与缓冲操作进行转换，得到本地文档上的选择
+    /// # Example
+    /// ```
+    /// use ot_rs::client::Client;
+    /// use ot_rs::core::{Selection, TextOperation};
+    /// let mut client: Client = Client::new(0);
+    /// let mut local = TextOperation::new();
+    /// local.insert("xy").retain(3);
+    /// client.apply_client(local).unwrap();
+    /// assert_eq!(Selection::cursor(3), client.transform_selection(&Selection::cursor(1)));
+    /// ```
+    pub fn transform_selection(&self, selection: &Selection) -> Selection {
+        match &self.state {
+            ClientState::Synchronized => selection.clone(),
+            ClientState::AwaitingConfirm(outstanding) => {
+                selection.transform_with(outstanding, false)
+            }
+            ClientState::AwaitingWithBuffer(outstanding, buffer) => selection
+                .transform_with(outstanding, false)
+                .transform_with(buffer, false),
+        }
+    }
+}
+
+impl<T: OtType> Clone for Client<T> {
+    fn clone(&self) -> Self {
+        return Client {
+            revision: self.revision,
+            state: self.state.clone(),
+        };
+    }
+}
+
+impl<T: OtType> fmt::Debug for Client<T>
+where
+    T::Op: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Client")
+            .field("revision", &self.revision)
+            .field("state", &self.state)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Client, ClientState, Outgoing};
+    use crate::core::test_util::random_string;
+    use crate::core::{OperationError, TextOperation};
+    use crate::server::Server;
+    use rand::{self, Rng};
+    use std::collections::VecDeque;
+
+    fn random_operation(base: &str) -> TextOperation {
+        let mut rng = rand::thread_rng();
+        let mut target: String = base.chars().filter(|_| rng.gen_bool(0.8)).collect();
+        let index = rng.gen_range(0..=target.chars().count());
+        let offset = target
+            .char_indices()
+            .nth(index)
+            .map_or(target.len(), |(i, _)| i);
+        target.insert_str(offset, &random_string(1));
+        return TextOperation::diff(base, &target);
+    }
+
+    #[test]
+    fn test_state_transitions() {
+        let mut client: Client = Client::new(3);
+        assert_eq!(Err(OperationError::NoPendingOperation), client.server_ack());
+        assert_eq!(None, client.server_reconnect());
+
+        let mut a = TextOperation::new();
+        a.insert("a");
+        let mut b = TextOperation::new();
+        b.retain(1).insert("b");
+        let mut c = TextOperation::new();
+        c.retain(2).insert("c");
+        assert_eq!(
+            Some(Outgoing {
+                revision: 3,
+                operation: a.clone()
+            }),
+            client.apply_client(a.clone()).unwrap()
+        );
+        assert_eq!(None, client.apply_client(b).unwrap());
+        assert_eq!(None, client.apply_client(c).unwrap());
+        let mut bc = TextOperation::new();
+        bc.retain(1).insert("bc");
+        assert_eq!(
+            &ClientState::AwaitingWithBuffer(a.clone(), bc.clone()),
+            client.state()
+        );
+        assert_eq!(
+            Some(Outgoing {
+                revision: 3,
+                operation: a
+            }),
+            client.server_reconnect()
+        );
+        assert_eq!(
+            Some(Outgoing {
+                revision: 4,
+                operation: bc.clone()
+            }),
+            client.server_ack().unwrap()
+        );
+        assert_eq!(&ClientState::AwaitingConfirm(bc), client.state());
+        assert_eq!(None, client.server_ack().unwrap());
+        assert_eq!(&ClientState::Synchronized, client.state());
+        assert_eq!(5, client.revision());
+
+        // 缓冲的操作无法合并时，状态保持不变
+        let mut a = TextOperation::new();
+        a.insert("a");
+        client.apply_client(a.clone()).unwrap();
+        client.apply_client(a.clone()).unwrap();
+        assert_eq!(
+            Err(OperationError::SecondBaseLengthNotEqualFirstAfterLength),
+            client.apply_client(a.clone())
+        );
+        assert_eq!(
+            &ClientState::AwaitingWithBuffer(a.clone(), a),
+            client.state()
+        );
+    }
+
+    enum Message {
+        Ack,
+        Operation(TextOperation),
+    }
+
+    #[test]
+    fn test_random_convergence() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..50 {
+            let mut server: Server = Server::new(String::new());
+            let n = 3;
+            let mut clients: Vec<Client> = (0..n).map(|_| Client::new(0)).collect();
+            let mut docs = vec![String::new(); n];
+            let mut upstream: VecDeque<(usize, Outgoing<TextOperation>)> = VecDeque::new();
+            let mut downstream: Vec<VecDeque<Message>> = (0..n).map(|_| VecDeque::new()).collect();
+            for step in 0..200 {
+                let finishing = step >= 150;
+                match rng.gen_range(0..3) {
+                    // 客户端产生本地操作
+                    0 if !finishing => {
+                        let i = rng.gen_range(0..n);
+                        let operation = random_operation(&docs[i]);
+                        docs[i] = operation.apply(docs[i].as_str()).unwrap();
+                        if let Some(outgoing) = clients[i].apply_client(operation).unwrap() {
+                            upstream.push_back((i, outgoing));
+                        }
+                    }
+                    // 服务端处理一个操作，确认发送方并广播给其他客户端
+                    1 => {
+                        if let Some((from, outgoing)) = upstream.pop_front() {
+                            let operation = server
+                                .receive_operation(outgoing.revision, outgoing.operation)
+                                .unwrap();
+                            for (i, queue) in downstream.iter_mut().enumerate() {
+                                queue.push_back(if i == from {
+                                    Message::Ack
+                                } else {
+                                    Message::Operation(operation.clone())
+                                });
+                            }
+                        }
+                    }
+                    // 客户端处理一个来自服务端的消息
+                    _ => {
+                        let i = rng.gen_range(0..n);
+                        match downstream[i].pop_front() {
+                            Some(Message::Ack) => {
+                                if let Some(outgoing) = clients[i].server_ack().unwrap() {
+                                    upstream.push_back((i, outgoing));
+                                }
+                            }
+                            Some(Message::Operation(operation)) => {
+                                let operation = clients[i].apply_server(operation).unwrap();
+                                docs[i] = operation.apply(docs[i].as_str()).unwrap();
+                            }
+                            None => {}
+                        }
+                    }
+                }
+            }
+            // 处理完全部消息
+            while !upstream.is_empty() || downstream.iter().any(|q| !q.is_empty()) {
+                while let Some((from, outgoing)) = upstream.pop_front() {
+                    let operation = server
+                        .receive_operation(outgoing.revision, outgoing.operation)
+                        .unwrap();
+                    for (i, queue) in downstream.iter_mut().enumerate() {
+                        queue.push_back(if i == from {
+                            Message::Ack
+                        } else {
+                            Message::Operation(operation.clone())
+                        });
+                    }
+                }
+                for i in 0..n {
+                    while let Some(message) = downstream[i].pop_front() {
+                        match message {
+                            Message::Ack => {
+                                if let Some(outgoing) = clients[i].server_ack().unwrap() {
+                                    upstream.push_back((i, outgoing));
+                                }
+                            }
+                            Message::Operation(operation) => {
+                                let operation = clients[i].apply_server(operation).unwrap();
+                                docs[i] = operation.apply(docs[i].as_str()).unwrap();
+                            }
+                        }
+                    }
+                }
+            }
+            for (client, doc) in clients.iter().zip(&docs) {
+                assert_eq!(server.document(), doc);
+                assert_eq!(&ClientState::Synchronized, client.state());
+                assert_eq!(server.revision(), client.revision());
+            }
+        }
+    }
+}
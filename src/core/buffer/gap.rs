@@ -0,0 +1,154 @@
+use super::{check_char_range, TextBuffer};
+use crate::core::error::OperationError;
+use crate::core::unit::Chars;
+use std::fmt;
+use std::ops::Range;
+
+/// 以字符为单位的间隙缓冲区（gap buffer）：在光标附近的连续编辑只需要移动少量字符
+/// # Example
+/// ```
+/// use ot_rs::core::{GapBuffer, TextOperation};
+/// let mut buffer = GapBuffer::from("hello");
+/// let mut ops = TextOperation::new();
+/// ops.retain(5).insert(" world");
+/// ops.apply_to(&mut buffer).unwrap();
+/// assert_eq!("hello world", buffer.to_string());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct GapBuffer {
+    buf: Vec<char>,
+    /// 间隙的起始位置
+    gap_start: usize,
+    /// 间隙的结束位置（不包含）
+    gap_end: usize,
+}
+
+impl GapBuffer {
+    /// 构造一个空的缓冲区
+    pub fn new() -> GapBuffer {
+        return GapBuffer::default();
+    }
+
+    /// 缓冲区中的字符数
+    pub fn len(&self) -> usize {
+        self.buf.len() - (self.gap_end - self.gap_start)
+    }
+
+    /// 缓冲区是否为空
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 获取第 `index` 个字符
+    pub fn get(&self, index: usize) -> Option<char> {
+        if index >= self.len() {
+            return None;
+        }
+        if index < self.gap_start {
+            return Some(self.buf[index]);
+        }
+        return Some(self.buf[index + self.gap_end - self.gap_start]);
+    }
+
+    /// 将间隙移动到 `position` 处
+    fn move_gap(&mut self, position: usize) {
+        if position < self.gap_start {
+            let n = self.gap_start - position;
+            self.buf
+                .copy_within(position..self.gap_start, self.gap_end - n);
+            self.gap_start -= n;
+            self.gap_end -= n;
+        } else if position > self.gap_start {
+            let n = position - self.gap_start;
+            self.buf
+                .copy_within(self.gap_end..self.gap_end + n, self.gap_start);
+            self.gap_start += n;
+            self.gap_end += n;
+        }
+    }
+
+    /// 保证间隙至少能容纳 `n` 个字符
+    fn reserve_gap(&mut self, n: usize) {
+        let gap = self.gap_end - self.gap_start;
+        if gap >= n {
+            return;
+        }
+        let grow = (n - gap).max(self.buf.len()).max(16);
+        let tail = self.buf.len() - self.gap_end;
+        self.buf.resize(self.buf.len() + grow, '\0');
+        let new_gap_end = self.buf.len() - tail;
+        self.buf
+            .copy_within(self.gap_end..self.gap_end + tail, new_gap_end);
+        self.gap_end = new_gap_end;
+    }
+}
+
+impl From<&str> for GapBuffer {
+    fn from(s: &str) -> GapBuffer {
+        let buf: Vec<char> = s.chars().collect();
+        let len = buf.len();
+        return GapBuffer {
+            buf,
+            gap_start: len,
+            gap_end: len,
+        };
+    }
+}
+
+impl fmt::Display for GapBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in self.buf[..self.gap_start]
+            .iter()
+            .chain(&self.buf[self.gap_end..])
+        {
+            write!(f, "{}", c)?;
+        }
+        return Ok(());
+    }
+}
+
+impl TextBuffer<Chars> for GapBuffer {
+    fn unit_len(&self) -> usize {
+        self.len()
+    }
+
+    fn unit_slice(&self, range: Range<usize>) -> Result<String, OperationError> {
+        check_char_range(self.len(), &range)?;
+        return Ok(range.map(|i| self.get(i).unwrap()).collect());
+    }
+
+    fn unit_replace(&mut self, range: Range<usize>, text: &str) -> Result<(), OperationError> {
+        check_char_range(self.len(), &range)?;
+        self.move_gap(range.start);
+        // 删除：直接将间隙向后扩展
+        self.gap_end += range.end - range.start;
+        let chars: Vec<char> = text.chars().collect();
+        self.reserve_gap(chars.len());
+        self.buf[self.gap_start..self.gap_start + chars.len()].copy_from_slice(&chars);
+        self.gap_start += chars.len();
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::GapBuffer;
+    use crate::core::TextBuffer;
+
+    #[test]
+    fn test_gap_buffer() {
+        let mut buffer = GapBuffer::new();
+        assert!(buffer.is_empty());
+        buffer.unit_replace(0..0, "hello").unwrap();
+        buffer.unit_replace(5..5, " world").unwrap();
+        buffer.unit_replace(0..1, "H").unwrap();
+        buffer.unit_replace(6..11, "😄").unwrap();
+        assert_eq!("Hello 😄", buffer.to_string());
+        assert_eq!(7, buffer.len());
+        assert_eq!(Some('😄'), buffer.get(6));
+        assert_eq!(None, buffer.get(7));
+        assert_eq!("lo 😄", buffer.unit_slice(3..7).unwrap());
+        assert!(buffer.unit_slice(3..8).is_err());
+    }
+}
@@ -0,0 +1,229 @@
+//! 可插拔的文本缓冲区：`TextOperation::apply_to` 和 `TextOperation::invert_from` 可以直接作用于编辑器自己的缓冲区结构，
+//! 而无需每次编辑都将整个内容转换为 `String`。
+//!
+//! 本模块为 `String`、`Vec<char>`、[`GapBuffer`] 和 [`PieceTable`] 实现了 [`TextBuffer`]。
+
+mod gap;
+mod piece;
+
+pub use gap::GapBuffer;
+pub use piece::PieceTable;
+
+use super::error::OperationError;
+use super::operation::Component;
+use super::text::TextOperation;
+use super::unit::{Chars, LengthUnit};
+use std::ops::Range;
+
+/// 文本缓冲区，全部位置、长度都以 `U` 为单位
+pub trait TextBuffer<U: LengthUnit = Chars> {
+    /// 缓冲区的长度
+    fn unit_len(&self) -> usize;
+
+    /// 返回 `range` 区间内的文本
+    fn unit_slice(&self, range: Range<usize>) -> Result<String, OperationError>;
+
+    /// 将 `range` 区间内的文本替换为 `text`
+    fn unit_replace(&mut self, range: Range<usize>, text: &str) -> Result<(), OperationError>;
+}
+
+/// 将以 `U` 为单位的区间转换为字节区间
+fn byte_range<U: LengthUnit>(s: &str, range: Range<usize>) -> Result<Range<usize>, OperationError> {
+    if range.start > range.end {
+        return Err(OperationError::OperationMoreLeftString);
+    }
+    let start = U::byte_offset(s, range.start)?;
+    let end = start + U::byte_offset(&s[start..], range.end - range.start)?;
+    return Ok(start..end);
+}
+
+impl<U: LengthUnit> TextBuffer<U> for String {
+    fn unit_len(&self) -> usize {
+        U::len(self)
+    }
+
+    fn unit_slice(&self, range: Range<usize>) -> Result<String, OperationError> {
+        return Ok(self[byte_range::<U>(self, range)?].to_string());
+    }
+
+    fn unit_replace(&mut self, range: Range<usize>, text: &str) -> Result<(), OperationError> {
+        let range = byte_range::<U>(self, range)?;
+        self.replace_range(range, text);
+        return Ok(());
+    }
+}
+
+/// 检查以字符为单位的区间是否合法
+fn check_char_range(len: usize, range: &Range<usize>) -> Result<(), OperationError> {
+    if range.start > range.end || range.end > len {
+        return Err(OperationError::OperationMoreLeftString);
+    }
+    return Ok(());
+}
+
+impl TextBuffer<Chars> for Vec<char> {
+    fn unit_len(&self) -> usize {
+        self.len()
+    }
+
+    fn unit_slice(&self, range: Range<usize>) -> Result<String, OperationError> {
+        check_char_range(self.len(), &range)?;
+        return Ok(self[range].iter().collect());
+    }
+
+    fn unit_replace(&mut self, range: Range<usize>, text: &str) -> Result<(), OperationError> {
+        check_char_range(self.len(), &range)?;
+        self.splice(range, text.chars());
+        return Ok(());
+    }
+}
+
+impl<U: LengthUnit> TextOperation<U> {
+    /// 将该操作原地应用到一个文本缓冲区上。
+    /// 缓冲区的长度与 base length 不一致时返回错误，且缓冲区保持不变；
+    /// 其他错误（例如长度落在字符中间）发生时，缓冲区可能已经被部分修改
+    /// # Example
+    /// ```
+    /// use ot_rs::core::TextOperation;
+    /// let mut ops = TextOperation::new();
+    /// ops.retain(1).delete(1).retain(1).insert("d");
+    ///
+    /// let mut buffer = "abc".to_string();
+    /// ops.apply_to(&mut buffer).unwrap();
+    /// assert_eq!("acd", buffer);
+    ///
+    /// let mut buffer = vec!['a', 'b', 'c'];
+    /// ops.apply_to(&mut buffer).unwrap();
+    /// assert_eq!(vec!['a', 'c', 'd'], buffer);
+    /// ```
+    pub fn apply_to<B: TextBuffer<U> + ?Sized>(
+        &self,
+        buffer: &mut B,
+    ) -> Result<(), OperationError> {
+        if buffer.unit_len() != self.base_length() {
+            return Err(OperationError::OperationApplyStringNotCompatible);
+        }
+        let mut cursor = 0usize; // 在缓冲区中的游标，游标之前的部分已经应用完成
+        for op in self.ops() {
+            match op {
+                &Component::Retain(n) => cursor += n,
+                Component::Insert(str) => {
+                    buffer.unit_replace(cursor..cursor, str)?;
+                    cursor += U::len(str);
+                }
+                &Component::Delete(n) => buffer.unit_replace(cursor..cursor + n, "")?,
+            }
+        }
+        return Ok(());
+    }
+
+    /// 同 `invert`，被删除的内容从文本缓冲区中读取
+    /// # Example
+    /// ```
+    /// use ot_rs::core::{GapBuffer, TextOperation};
+    /// let mut ops = TextOperation::new();
+    /// ops.retain(1).delete(1).retain(1).insert("d");
+    /// let mut buffer = GapBuffer::from("abc");
+    /// let inverse = ops.invert_from(&buffer).unwrap();
+    /// ops.apply_to(&mut buffer).unwrap();
+    /// inverse.apply_to(&mut buffer).unwrap();
+    /// assert_eq!("abc", buffer.to_string());
+    /// ```
+    pub fn invert_from<B: TextBuffer<U> + ?Sized>(
+        &self,
+        buffer: &B,
+    ) -> Result<TextOperation<U>, OperationError> {
+        if buffer.unit_len() != self.base_length() {
+            return Err(OperationError::OperationApplyStringNotCompatible);
+        }
+        let mut cursor = 0usize; // 在 base 中的游标
+        let mut inverse = TextOperation::with_unit();
+        for op in self.ops() {
+            match op {
+                &Component::Retain(n) => {
+                    inverse.retain(n);
+                    cursor += n;
+                }
+                Component::Insert(str) => {
+                    inverse.delete(U::len(str));
+                }
+                &Component::Delete(n) => {
+                    inverse.insert(buffer.unit_slice(cursor..cursor + n)?);
+                    cursor += n;
+                }
+            }
+        }
+        return Ok(inverse);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{GapBuffer, PieceTable, TextBuffer};
+    use crate::core::test_util::random_string;
+    use crate::core::{Chars, OperationError, TextOperation, Utf16};
+
+    /// 连续地对同一个缓冲区应用多个操作，并与 `apply`、`invert` 的结果比较
+    fn check_buffer<U, B, F>(new_buffer: F)
+    where
+        U: crate::core::LengthUnit,
+        B: TextBuffer<U> + ToString,
+        F: Fn(&str) -> B,
+    {
+        let mut text = random_string(30);
+        let mut buffer = new_buffer(&text);
+        for _ in 0..20 {
+            let after = random_string(30);
+            let ops = TextOperation::<U>::diff(&text, &after);
+            assert_eq!(
+                ops.invert(&text).unwrap(),
+                ops.invert_from(&buffer).unwrap()
+            );
+            ops.apply_to(&mut buffer).unwrap();
+            assert_eq!(after, buffer.to_string());
+            text = after;
+        }
+    }
+
+    #[test]
+    fn test_buffers() {
+        for _ in 0..20 {
+            check_buffer::<Chars, _, _>(|s| s.to_string());
+            check_buffer::<Utf16, _, _>(|s| s.to_string());
+            check_buffer::<Chars, _, _>(|s| GapBuffer::from(s));
+            check_buffer::<Chars, _, _>(|s| PieceTable::from(s));
+            check_buffer::<Utf16, _, _>(|s| PieceTable::from(s));
+        }
+    }
+
+    #[test]
+    fn test_vec_char() {
+        let mut buffer = "a😄b".chars().collect::<Vec<_>>();
+        let ops: TextOperation = TextOperation::diff("a😄b", "中b!");
+        ops.apply_to(&mut buffer).unwrap();
+        assert_eq!("中b!", buffer.iter().collect::<String>());
+    }
+
+    #[test]
+    fn test_apply_error() {
+        let mut ops = TextOperation::new();
+        ops.retain(2);
+        let mut buffer = "abc".to_string();
+        assert_eq!(
+            OperationError::OperationApplyStringNotCompatible,
+            ops.apply_to(&mut buffer).unwrap_err()
+        );
+        let mut ops = TextOperation::<Utf16>::with_unit();
+        ops.retain(1).delete(1);
+        let mut buffer = "😄".to_string();
+        assert_eq!(
+            OperationError::NotOnUnitBoundary,
+            ops.apply_to(&mut buffer).unwrap_err()
+        );
+        assert_eq!(
+            OperationError::OperationMoreLeftString,
+            TextBuffer::<Chars>::unit_slice(&buffer, 0..2).unwrap_err()
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use super::{byte_range, TextBuffer};
+use crate::core::error::OperationError;
+use crate::core::unit::LengthUnit;
+use std::fmt;
+use std::ops::Range;
+
+/// 片段所引用的缓冲区
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    Original,
+    Added,
+}
+
+/// 一个片段，引用某个缓冲区中的一段字节
+#[derive(Debug, Clone, Copy)]
+struct Piece {
+    source: Source,
+    start: usize,
+    end: usize,
+}
+
+/// 片段表（piece table）：原始文本只读，插入的文本追加到另一个缓冲区中，文档由一系列片段组成。
+/// 编辑的代价只与片段数量有关，与被删除、插入的文本长度无关。
+///
+/// 注意：对于 `Graphemes` 单位，一个字素簇可能跨越多个片段，此时长度将按片段分别计算
+/// # Example
+/// ```
+/// use ot_rs::core::{PieceTable, TextOperation, Utf16};
+/// let mut buffer = PieceTable::from("😄b");
+/// let mut ops = TextOperation::<Utf16>::with_unit();
+/// ops.retain(2).delete(1).insert("a");
+/// ops.apply_to(&mut buffer).unwrap();
+/// assert_eq!("😄a", buffer.to_string());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PieceTable {
+    original: String,
+    added: String,
+    pieces: Vec<Piece>,
+}
+
+impl PieceTable {
+    /// 构造一个空的片段表
+    pub fn new() -> PieceTable {
+        return PieceTable::default();
+    }
+
+    /// 片段的文本
+    fn text(&self, piece: &Piece) -> &str {
+        let source = match piece.source {
+            Source::Original => &self.original,
+            Source::Added => &self.added,
+        };
+        return &source[piece.start..piece.end];
+    }
+
+    /// 在以 `U` 为单位的位置 `index` 处切分片段，返回从 `index` 开始的第一个片段的下标
+    fn split<U: LengthUnit>(&mut self, index: usize) -> Result<usize, OperationError> {
+        let mut cursor = 0usize;
+        for i in 0..self.pieces.len() {
+            if cursor == index {
+                return Ok(i);
+            }
+            let piece = self.pieces[i];
+            let text = self.text(&piece);
+            let len = U::len(text);
+            if index < cursor + len {
+                let offset = U::byte_offset(text, index - cursor)?;
+                self.pieces[i].end = piece.start + offset;
+                self.pieces.insert(
+                    i + 1,
+                    Piece {
+                        start: piece.start + offset,
+                        ..piece
+                    },
+                );
+                return Ok(i + 1);
+            }
+            cursor += len;
+        }
+        if cursor == index {
+            return Ok(self.pieces.len());
+        }
+        return Err(OperationError::OperationMoreLeftString);
+    }
+}
+
+impl From<&str> for PieceTable {
+    fn from(s: &str) -> PieceTable {
+        let mut table = PieceTable {
+            original: s.to_string(),
+            ..PieceTable::default()
+        };
+        if !s.is_empty() {
+            table.pieces.push(Piece {
+                source: Source::Original,
+                start: 0,
+                end: s.len(),
+            });
+        }
+        return table;
+    }
+}
+
+impl fmt::Display for PieceTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for piece in &self.pieces {
+            f.write_str(self.text(piece))?;
+        }
+        return Ok(());
+    }
+}
+
+impl<U: LengthUnit> TextBuffer<U> for PieceTable {
+    fn unit_len(&self) -> usize {
+        self.pieces.iter().map(|p| U::len(self.text(p))).sum()
+    }
+
+    fn unit_slice(&self, range: Range<usize>) -> Result<String, OperationError> {
+        if range.start > range.end {
+            return Err(OperationError::OperationMoreLeftString);
+        }
+        let mut result = String::new();
+        let mut cursor = 0usize;
+        for piece in &self.pieces {
+            if cursor >= range.end {
+                break;
+            }
+            let text = self.text(piece);
+            let len = U::len(text);
+            if cursor + len > range.start {
+                let start = range.start.max(cursor) - cursor;
+                let end = range.end.min(cursor + len) - cursor;
+                result.push_str(&text[byte_range::<U>(text, start..end)?]);
+            }
+            cursor += len;
+        }
+        if cursor < range.end {
+            return Err(OperationError::OperationMoreLeftString);
+        }
+        return Ok(result);
+    }
+
+    fn unit_replace(&mut self, range: Range<usize>, text: &str) -> Result<(), OperationError> {
+        if range.start > range.end {
+            return Err(OperationError::OperationMoreLeftString);
+        }
+        let start = self.split::<U>(range.start)?;
+        let end = self.split::<U>(range.end)?;
+        let mut inserted = vec![];
+        if !text.is_empty() {
+            inserted.push(Piece {
+                source: Source::Added,
+                start: self.added.len(),
+                end: self.added.len() + text.len(),
+            });
+            self.added.push_str(text);
+        }
+        self.pieces.splice(start..end, inserted);
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::PieceTable;
+    use crate::core::{Chars, TextBuffer, Utf16};
+
+    #[test]
+    fn test_piece_table() {
+        let mut buffer = PieceTable::new();
+        TextBuffer::<Chars>::unit_replace(&mut buffer, 0..0, "hello world").unwrap();
+        TextBuffer::<Chars>::unit_replace(&mut buffer, 5..6, "😄").unwrap();
+        TextBuffer::<Utf16>::unit_replace(&mut buffer, 7..7, "!").unwrap();
+        assert_eq!("hello😄!world", buffer.to_string());
+        assert_eq!(12, TextBuffer::<Chars>::unit_len(&buffer));
+        assert_eq!(13, TextBuffer::<Utf16>::unit_len(&buffer));
+        assert_eq!(
+            "o😄!w",
+            TextBuffer::<Chars>::unit_slice(&buffer, 4..8).unwrap()
+        );
+        assert!(TextBuffer::<Utf16>::unit_slice(&buffer, 6..8).is_err());
+        assert!(TextBuffer::<Chars>::unit_slice(&buffer, 4..13).is_err());
+    }
+}
@@ -0,0 +1,371 @@
+//! 通过比较两个字符串生成 `TextOperation`。
+//!
+//! 采用线性空间的 Myers 差分算法（[An O(ND) Difference Algorithm and Its Variations](http://www.xmailserver.org/diff2.pdf)），
+//! 得到的操作插入、删除的内容总量最少。
+
+use super::text::TextOperation;
+use super::unit::LengthUnit;
+use std::ops::{Index, IndexMut, Range};
+
+/// 差分的结果片段，下标均为元素（而不是长度单位）的下标
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Run {
+    /// `old[old_start..old_start + len] == new[new_start..new_start + len]`
+    Equal(usize, usize, usize),
+    /// 删除 `old[start..start + len]`
+    Delete(usize, usize),
+    /// 插入 `new[start..start + len]`
+    Insert(usize, usize),
+}
+
+/// 以 `k`（可以为负数）为下标的数组，即论文中的 `V`
+struct V {
+    offset: isize,
+    v: Vec<usize>,
+}
+
+impl V {
+    fn new(max_d: usize) -> V {
+        return V {
+            offset: max_d as isize,
+            v: vec![0; 2 * max_d],
+        };
+    }
+}
+
+impl Index<isize> for V {
+    type Output = usize;
+
+    fn index(&self, k: isize) -> &usize {
+        &self.v[(k + self.offset) as usize]
+    }
+}
+
+impl IndexMut<isize> for V {
+    fn index_mut(&mut self, k: isize) -> &mut usize {
+        &mut self.v[(k + self.offset) as usize]
+    }
+}
+
+fn max_d(n: usize, m: usize) -> usize {
+    (n + m).div_ceil(2) + 1
+}
+
+fn common_prefix_len<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    a.iter().zip(b).take_while(|(x, y)| x == y).count()
+}
+
+fn common_suffix_len<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    a.iter()
+        .rev()
+        .zip(b.iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count()
+}
+
+/// 寻找 middle snake，返回其在 old、new 中的起点
+fn find_middle_snake<T: PartialEq>(
+    old: &[T],
+    old_range: Range<usize>,
+    new: &[T],
+    new_range: Range<usize>,
+    vf: &mut V,
+    vb: &mut V,
+) -> Option<(usize, usize)> {
+    let n = old_range.len();
+    let m = new_range.len();
+    let delta = n as isize - m as isize;
+    let odd = delta & 1 == 1;
+    vf[1] = 0;
+    vb[1] = 0;
+    for d in 0..max_d(n, m) as isize {
+        // 正向搜索
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = if k == -d || (k != d && vf[k - 1] < vf[k + 1]) {
+                vf[k + 1]
+            } else {
+                vf[k - 1] + 1
+            };
+            let y = (x as isize - k) as usize;
+            let (x0, y0) = (x, y);
+            if x < n && y < m {
+                x += common_prefix_len(
+                    &old[old_range.start + x..old_range.end],
+                    &new[new_range.start + y..new_range.end],
+                );
+            }
+            vf[k] = x;
+            if odd && (k - delta).abs() < d && vf[k] + vb[-(k - delta)] >= n {
+                return Some((x0 + old_range.start, y0 + new_range.start));
+            }
+        }
+        // 反向搜索
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = if k == -d || (k != d && vb[k - 1] < vb[k + 1]) {
+                vb[k + 1]
+            } else {
+                vb[k - 1] + 1
+            };
+            let mut y = (x as isize - k) as usize;
+            if x < n && y < m {
+                let advance = common_suffix_len(
+                    &old[old_range.start..old_range.start + n - x],
+                    &new[new_range.start..new_range.start + m - y],
+                );
+                x += advance;
+                y += advance;
+            }
+            vb[k] = x;
+            if !odd && (k - delta).abs() <= d && vb[k] + vf[-(k - delta)] >= n {
+                return Some((n - x + old_range.start, m - y + new_range.start));
+            }
+        }
+    }
+    return None;
+}
+
+/// 分治：去掉公共前后缀后，以 middle snake 为界递归处理两侧
+fn conquer<T: PartialEq>(
+    old: &[T],
+    mut old_range: Range<usize>,
+    new: &[T],
+    mut new_range: Range<usize>,
+    vf: &mut V,
+    vb: &mut V,
+    runs: &mut Vec<Run>,
+) {
+    let prefix = common_prefix_len(&old[old_range.clone()], &new[new_range.clone()]);
+    if prefix > 0 {
+        runs.push(Run::Equal(old_range.start, new_range.start, prefix));
+    }
+    old_range.start += prefix;
+    new_range.start += prefix;
+
+    let suffix = common_suffix_len(&old[old_range.clone()], &new[new_range.clone()]);
+    old_range.end -= suffix;
+    new_range.end -= suffix;
+
+    if old_range.is_empty() && new_range.is_empty() {
+        // 无需处理
+    } else if new_range.is_empty() {
+        runs.push(Run::Delete(old_range.start, old_range.len()));
+    } else if old_range.is_empty() {
+        runs.push(Run::Insert(new_range.start, new_range.len()));
+    } else if let Some((x, y)) =
+        find_middle_snake(old, old_range.clone(), new, new_range.clone(), vf, vb)
+    {
+        conquer(
+            old,
+            old_range.start..x,
+            new,
+            new_range.start..y,
+            vf,
+            vb,
+            runs,
+        );
+        conquer(old, x..old_range.end, new, y..new_range.end, vf, vb, runs);
+    } else {
+        runs.push(Run::Delete(old_range.start, old_range.len()));
+        runs.push(Run::Insert(new_range.start, new_range.len()));
+    }
+
+    if suffix > 0 {
+        runs.push(Run::Equal(old_range.end, new_range.end, suffix));
+    }
+}
+
+/// 计算将 old 转换为 new 的最短编辑脚本
+fn myers<T: PartialEq>(old: &[T], new: &[T]) -> Vec<Run> {
+    let max_d = max_d(old.len(), new.len());
+    let mut vf = V::new(max_d);
+    let mut vb = V::new(max_d);
+    let mut runs = vec![];
+    conquer(
+        old,
+        0..old.len(),
+        new,
+        0..new.len(),
+        &mut vf,
+        &mut vb,
+        &mut runs,
+    );
+    return runs;
+}
+
+impl<U: LengthUnit> TextOperation<U> {
+    /// 比较两个字符串，生成一个满足 `diff(old, new).apply(old) == new` 的操作，
+    /// 且插入、删除的内容（以 `U` 的最小不可分割片段计）总量最少
+    /// # Example
+    /// ```
+    /// use ot_rs::core::TextOperation;
+    /// let ops: TextOperation = TextOperation::diff("hello world", "hello, brave world!");
+    /// assert_eq!(
+    ///     "(11->19){retain(5).insert(\", brave\").retain(6).insert(\"!\")}",
+    ///     ops.to_string()
+    /// );
+    /// assert_eq!("hello, brave world!", ops.apply("hello world").unwrap());
+    /// ```
+    pub fn diff(old: &str, new: &str) -> TextOperation<U> {
+        let mut operation = TextOperation::with_unit();
+        operation.push_diff(old, new);
+        return operation;
+    }
+
+    /// 按行比较两个字符串，适用于较大的文本：先以行为单位计算差分，
+    /// 再在发生变化的连续行内部以字符为单位计算差分。结果满足 `diff_lines(old, new).apply(old) == new`，
+    /// 但不保证插入、删除的内容总量最少
+    /// # Example
+    /// ```
+    /// use ot_rs::core::TextOperation;
+    /// let old = "fn main() {\n    println!(\"hello\");\n}\n";
+    /// let new = "fn main() {\n    println!(\"hello, world\");\n}\n";
+    /// let ops: TextOperation = TextOperation::diff_lines(old, new);
+    /// assert_eq!(new, ops.apply(old).unwrap());
+    /// assert_eq!(
+    ///     "(37->44){retain(31).insert(\", world\").retain(6)}",
+    ///     ops.to_string()
+    /// );
+    /// ```
+    pub fn diff_lines(old: &str, new: &str) -> TextOperation<U> {
+        let old_lines = old.split_inclusive('\n').collect::<Vec<_>>();
+        let new_lines = new.split_inclusive('\n').collect::<Vec<_>>();
+        let mut operation = TextOperation::with_unit();
+        // 被修改的连续行（相邻的 Delete、Insert）合并后再以字符为单位计算差分
+        let (mut old_changed, mut new_changed) = (String::new(), String::new());
+        for run in myers(&old_lines, &new_lines) {
+            match run {
+                Run::Equal(start, _, len) => {
+                    operation.push_diff(&old_changed, &new_changed);
+                    old_changed.clear();
+                    new_changed.clear();
+                    for line in &old_lines[start..start + len] {
+                        operation.retain(U::len(line));
+                    }
+                }
+                Run::Delete(start, len) => {
+                    old_changed.extend(old_lines[start..start + len].iter().copied())
+                }
+                Run::Insert(start, len) => {
+                    new_changed.extend(new_lines[start..start + len].iter().copied())
+                }
+            }
+        }
+        operation.push_diff(&old_changed, &new_changed);
+        return operation;
+    }
+
+    /// 将 old 到 new 的字符差分追加到当前操作的尾部
+    fn push_diff(&mut self, old: &str, new: &str) {
+        let old_atoms = U::atoms(old);
+        let new_atoms = U::atoms(new);
+        for run in myers(&old_atoms, &new_atoms) {
+            match run {
+                Run::Equal(start, _, len) => {
+                    self.retain(U::len(&old_atoms[start..start + len].concat()));
+                }
+                Run::Delete(start, len) => {
+                    self.delete(U::len(&old_atoms[start..start + len].concat()));
+                }
+                Run::Insert(start, len) => {
+                    self.insert(new_atoms[start..start + len].concat());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{myers, Run};
+    use crate::core::{test_util, Chars, Component, TextOperation, Utf16};
+    use rand::{self, Rng};
+
+    const RAND_TEST_COUNT: usize = 200;
+
+    /// 长度在 `0..=max_len` 之间的随机字符串
+    fn random_string(max_len: usize) -> String {
+        return test_util::random_string(rand::thread_rng().gen_range(0..=max_len));
+    }
+
+    /// 通过动态规划计算最长公共子序列的长度
+    fn lcs_len(a: &[char], b: &[char]) -> usize {
+        let mut dp = vec![vec![0; b.len() + 1]; a.len() + 1];
+        for i in 1..=a.len() {
+            for j in 1..=b.len() {
+                dp[i][j] = if a[i - 1] == b[j - 1] {
+                    dp[i - 1][j - 1] + 1
+                } else {
+                    dp[i - 1][j].max(dp[i][j - 1])
+                };
+            }
+        }
+        dp[a.len()][b.len()]
+    }
+
+    #[test]
+    fn test_myers() {
+        let old = "abcabba".chars().collect::<Vec<_>>();
+        let new = "cbabac".chars().collect::<Vec<_>>();
+        let runs = myers(&old, &new);
+        let edits: usize = runs
+            .iter()
+            .map(|run| match run {
+                Run::Equal(..) => 0,
+                &Run::Delete(_, len) | &Run::Insert(_, len) => len,
+            })
+            .sum();
+        // 论文中的例子，D = 5
+        assert_eq!(5, edits);
+    }
+
+    #[test]
+    fn test_diff_minimal() {
+        for _ in 0..RAND_TEST_COUNT {
+            let old = random_string(30);
+            let new = random_string(30);
+            let ops: TextOperation = TextOperation::diff(&old, &new);
+            assert_eq!(new, ops.apply(&old).unwrap());
+            let edits: usize = ops
+                .ops()
+                .filter(|op| !matches!(op, Component::Retain(_)))
+                .map(|op| op.len::<Chars>())
+                .sum();
+            let (a, b) = (
+                old.chars().collect::<Vec<_>>(),
+                new.chars().collect::<Vec<_>>(),
+            );
+            assert_eq!(a.len() + b.len() - 2 * lcs_len(&a, &b), edits);
+        }
+    }
+
+    #[test]
+    fn test_diff_lines() {
+        for _ in 0..RAND_TEST_COUNT {
+            let old = random_string(60);
+            let new = random_string(60);
+            let ops: TextOperation = TextOperation::diff_lines(&old, &new);
+            assert_eq!(new, ops.apply(&old).unwrap());
+            let ops = TextOperation::<Utf16>::diff_lines(&old, &new);
+            assert_eq!(new, ops.apply(&old).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_diff_edge_cases() {
+        let ops: TextOperation = TextOperation::diff("", "");
+        assert!(ops.is_noop());
+        let ops: TextOperation = TextOperation::diff("abc", "abc");
+        assert_eq!("(3->3){retain(3)}", ops.to_string());
+        let ops: TextOperation = TextOperation::diff("", "abc");
+        assert_eq!("(0->3){insert(\"abc\")}", ops.to_string());
+        let ops: TextOperation = TextOperation::diff("abc", "");
+        assert_eq!("(3->0){delete(3)}", ops.to_string());
+        // 以 UTF-16 为单位时，emoji 不会被拆分
+        let ops = TextOperation::<Utf16>::diff("a😄b", "a😂b");
+        assert_eq!(
+            "(4->4){retain(1).insert(\"😂\").delete(2).retain(1)}",
+            ops.to_string()
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! 以绝对位置描述的编辑（`(区间, 替换文本)`）与 `TextOperation` 之间的相互转换，
+//! 适用于多光标编辑、编辑器的 change 事件等场景。
+
+use super::error::OperationError;
+use super::operation::Component;
+use super::text::TextOperation;
+use super::unit::LengthUnit;
+use std::ops::Range;
+use std::slice;
+
+impl<U: LengthUnit> TextOperation<U> {
+    /// 由一组编辑构造一个 TextOperation，每个编辑将 base 中 `range` 区间（以 `U` 为单位）的内容替换为给定的文本。
+    /// 编辑可以以任意顺序给出；区间相互重叠（包括在同一位置的两个插入）时返回 `EditsOverlap`，
+    /// 区间超出 `base_length` 或起点大于终点时返回 `OperationMoreLeftString`
+    /// # Example
+    /// ```
+    /// use ot_rs::core::{OperationError, TextOperation};
+    /// let ops: TextOperation = TextOperation::from_edits(
+    ///     11,
+    ///     vec![(6..11, "rust"), (0..0, "> "), (5..6, ", ")],
+    /// )
+    /// .unwrap();
+    /// assert_eq!("> hello, rust", ops.apply("hello world").unwrap());
+    /// assert_eq!(
+    ///     OperationError::EditsOverlap,
+    ///     TextOperation::<ot_rs::core::Chars>::from_edits(3, vec![(0..2, "a"), (1..3, "b")])
+    ///         .unwrap_err()
+    /// );
+    /// ```
+    pub fn from_edits<I, S>(
+        base_length: usize,
+        edits: I,
+    ) -> Result<TextOperation<U>, OperationError>
+    where
+        I: IntoIterator<Item = (Range<usize>, S)>,
+        S: Into<String>,
+    {
+        let mut edits: Vec<(Range<usize>, S)> = edits.into_iter().collect();
+        edits.sort_by_key(|(range, _)| (range.start, range.end));
+
+        let mut operation = TextOperation::with_unit();
+        let mut cursor = 0usize; // 在 base 中的游标
+        let mut last_insert_at = None; // 上一个纯插入的位置
+        for (range, text) in edits {
+            if range.start > range.end || range.end > base_length {
+                return Err(OperationError::OperationMoreLeftString);
+            }
+            if range.start < cursor || (range.is_empty() && last_insert_at == Some(range.start)) {
+                return Err(OperationError::EditsOverlap);
+            }
+            if range.is_empty() {
+                last_insert_at = Some(range.start);
+            }
+            operation
+                .retain(range.start - cursor)
+                .insert(text)
+                .delete(range.end - range.start);
+            cursor = range.end;
+        }
+        operation.retain(base_length - cursor);
+        return Ok(operation);
+    }
+
+    /// 返回该操作包含的全部编辑：base 中的区间（以 `U` 为单位）及其替换文本，按位置从前往后排列。
+    /// 相邻的插入与删除将合并为一个编辑
+    /// # Example
+    /// ```
+    /// use ot_rs::core::TextOperation;
+    /// let mut ops = TextOperation::new();
+    /// ops.insert("> ").retain(5).delete(1).insert(", ").retain(1).delete(4);
+    /// assert_eq!(
+    ///     vec![(0..0, "> "), (5..6, ", "), (7..11, "")],
+    ///     ops.edits().collect::<Vec<_>>()
+    /// );
+    /// ```
+    pub fn edits(&self) -> Edits<'_> {
+        return Edits {
+            ops: self.ops(),
+            cursor: 0,
+        };
+    }
+}
+
+/// 遍历一个操作的全部编辑的迭代器，参见 [`TextOperation::edits`]
+#[derive(Debug, Clone)]
+pub struct Edits<'a> {
+    ops: slice::Iter<'a, Component>,
+    /// 在 base 中的游标
+    cursor: usize,
+}
+
+impl<'a> Iterator for Edits<'a> {
+    type Item = (Range<usize>, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.ops.next()? {
+                &Component::Retain(n) => self.cursor += n,
+                Component::Insert(str) => {
+                    let start = self.cursor;
+                    // insert 始终在 delete 之前，因此只需检查下一个原子操作
+                    if let Some(&Component::Delete(n)) = self.ops.clone().next() {
+                        self.ops.next();
+                        self.cursor += n;
+                    }
+                    return Some((start..self.cursor, str.as_str()));
+                }
+                &Component::Delete(n) => {
+                    let start = self.cursor;
+                    self.cursor += n;
+                    return Some((start..self.cursor, ""));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::core::test_util::random_string;
+    use crate::core::{OperationError, TextOperation};
+    use rand::{self, seq::SliceRandom};
+
+    #[test]
+    fn test_edits_round_trip() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let base = random_string(20);
+            let after = random_string(20);
+            let ops: TextOperation = TextOperation::diff(&base, &after);
+            let mut edits: Vec<_> = ops.edits().collect();
+            // 编辑之间互不重叠，且按位置排列
+            for pair in edits.windows(2) {
+                assert!(pair[0].0.end <= pair[1].0.start);
+            }
+            edits.shuffle(&mut rng);
+            assert_eq!(ops, TextOperation::from_edits(20, edits).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_from_edits_error() {
+        let from_edits = |edits: Vec<(std::ops::Range<usize>, &str)>| {
+            TextOperation::<crate::core::Chars>::from_edits(5, edits)
+        };
+        assert_eq!(
+            Err(OperationError::EditsOverlap),
+            from_edits(vec![(2..2, "a"), (2..2, "b")])
+        );
+        assert_eq!(
+            Err(OperationError::EditsOverlap),
+            from_edits(vec![(1..4, "a"), (2..2, "b")])
+        );
+        assert_eq!(
+            Err(OperationError::OperationMoreLeftString),
+            from_edits(vec![(4..6, "a")])
+        );
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 3..1;
+        assert_eq!(
+            Err(OperationError::OperationMoreLeftString),
+            from_edits(vec![(reversed, "a")])
+        );
+        // 相邻的编辑不算重叠
+        let ops = from_edits(vec![(2..2, "a"), (0..2, "b"), (2..5, "")]).unwrap();
+        assert_eq!("ba", ops.apply("12345").unwrap());
+    }
+}
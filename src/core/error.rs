@@ -1,3 +1,6 @@
+use std::error::Error;
+use std::fmt;
+
 /// 定义 OT 算法的一些异常
 #[derive(Debug, PartialEq, Eq)]
 pub enum OperationError {
@@ -28,4 +31,115 @@ pub enum OperationError {
     /// The two operations aren't compatible
     /// 两个操作并不兼容
     TransformNotCompatible,
+    /// A component must not be empty, e.g. `Retain(0)`, `Delete(0)` or `Insert("")`.
+    /// 原子操作不能为空，例如 `Retain(0)`、`Delete(0)` 或 `Insert("")`
+    ComponentEmpty,
+    /// A component's length doesn't fit in `usize`.
+    /// 原子操作的长度超出了 `usize` 的范围
+    ComponentLengthOverflow,
+    /// Syntax error at the given byte offset when parsing the string representation of an operation.
+    /// 解析操作的字符串表示时，在给定的字节偏移量处出现语法错误
+    ParseSyntaxError(usize),
+    /// The declared `(base->after)` lengths don't match the parsed components.
+    /// 声明的 `(base->after)` 长度与解析出的原子操作不一致
+    ParseLengthMismatch,
+    /// A length falls in the middle of a character (or grapheme cluster) in the operation's length unit.
+    /// 长度落在了一个字符（或字素簇）的中间，无法以该长度单位切分字符串
+    NotOnUnitBoundary,
+    /// A position falls between the `\r` and `\n` of a `\r\n` line break, which has no line/character position.
+    /// 位置落在了 `\r\n` 换行符的中间，无法表示为行列位置
+    InsideLineBreak,
+    /// The text carried by a delete component doesn't match the text it deletes.
+    /// 删除的原子操作所携带的文本与实际被删除的文本不一致
+    DeletedTextMismatch,
+    /// Two edits overlap, or two insertions are at the same position.
+    /// 两个编辑的区间相互重叠，或者两个插入位于同一位置
+    EditsOverlap,
+    /// Syntax error at the given line (starting from 1) when parsing a unified diff.
+    /// 解析统一 diff 时，在给定的行（从 1 开始）出现语法错误
+    PatchSyntaxError(usize),
+    /// The hunk at the given index (starting from 0) of a unified diff can't be located in the base text.
+    /// 统一 diff 中给定下标（从 0 开始）的 hunk 无法在 base 中定位
+    PatchHunkNotFound(usize),
+    /// A snapshot or an operation passed to a type-erased OT type has an unexpected type.
+    /// 传递给类型擦除的 OT 类型的快照或操作的实际类型与该类型不一致
+    TypeMismatch,
+    /// The path of a JSON component doesn't exist in the document, or doesn't fit the component.
+    /// JSON 原子操作的路径在文档中不存在，或与原子操作的种类不符
+    JsonPathInvalid,
+    /// The value at the path of a JSON component has an unexpected type, e.g. adding to a string.
+    /// JSON 原子操作路径上的值的类型不符合要求，例如对字符串执行数字加法
+    JsonValueMismatch,
+    /// A JSON component in the json0 wire format is malformed or unsupported.
+    /// json0 格式的 JSON 原子操作格式错误或不受支持
+    JsonComponentInvalid,
+    /// A Quill Delta is malformed, or isn't a document (containing only inserts) where one is required.
+    /// Quill Delta 格式错误，或者在要求文档（只包含插入）的地方不是一个文档
+    DeltaInvalid,
+    /// The server acknowledged an operation, but the client has no pending operation.
+    /// 服务端确认了一个操作，但客户端并没有等待确认的操作
+    NoPendingOperation,
+    /// The operation is based on the given revision, which the server hasn't reached yet.
+    /// 操作所基于的版本大于服务端当前的版本
+    RevisionInFuture(usize),
+    /// The operation is based on the given revision, which is older than the history retained by the server.
+    /// 操作所基于的版本早于服务端所保留的历史
+    RevisionTooOld(usize),
 }
+
+impl fmt::Display for OperationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            &Self::ParseSyntaxError(offset) => {
+                return write!(f, "syntax error at byte offset {}", offset);
+            }
+            &Self::PatchSyntaxError(line) => {
+                return write!(f, "syntax error in patch at line {}", line);
+            }
+            &Self::PatchHunkNotFound(index) => {
+                return write!(f, "hunk {} of the patch can't be located in the base text", index);
+            }
+            &Self::RevisionInFuture(revision) => {
+                return write!(f, "revision {} is in the future", revision);
+            }
+            &Self::RevisionTooOld(revision) => {
+                return write!(f, "revision {} is older than the retained history", revision);
+            }
+            Self::OperationApplyStringNotCompatible => {
+                "the operation's base length must be equal to the string's length"
+            }
+            Self::OperationMoreLeftString => {
+                "operation can't retain more characters than are left in the string"
+            }
+            Self::SecondBaseLengthNotEqualFirstAfterLength => {
+                "the base length of the second operation has to be the target length of the first operation"
+            }
+            Self::ComposeFirstTooShort => "compose operations: first operation is too short",
+            Self::ComposeFirstTooLong => "compose operations: first operation is too long",
+            Self::TransformBaseDifferent => "both operations have to have the same base length",
+            Self::TransformNotCompatible => "the two operations aren't compatible",
+            Self::ComponentEmpty => "a component must not be empty",
+            Self::ComponentLengthOverflow => "a component's length doesn't fit in usize",
+            Self::ParseLengthMismatch => {
+                "the declared lengths don't match the parsed components"
+            }
+            Self::NotOnUnitBoundary => {
+                "a length falls in the middle of a character in the operation's length unit"
+            }
+            Self::InsideLineBreak => "a position falls between the \\r and \\n of a line break",
+            Self::DeletedTextMismatch => {
+                "the text carried by a delete component doesn't match the deleted text"
+            }
+            Self::EditsOverlap => "two edits overlap",
+            Self::TypeMismatch => "the snapshot or operation doesn't belong to this OT type",
+            Self::JsonPathInvalid => "the path doesn't exist in the document or doesn't fit the component",
+            Self::JsonValueMismatch => "the value at the path has an unexpected type",
+            Self::JsonComponentInvalid => "malformed or unsupported json0 component",
+            Self::DeltaInvalid => "malformed delta, or the delta isn't a document",
+            Self::NoPendingOperation => "there is no pending operation",
+        };
+        f.write_str(message)
+    }
+}
+
+impl Error for OperationError {}
@@ -2,9 +2,39 @@
 //! # OT 算法（Operational Transform）实现
 //! > 实现上参考了 [Operational-Transformation/ot.js](https://github.com/Operational-Transformation/ot.js/blob/master/lib/text-operation.js)
 
+mod buffer;
+mod diff;
+mod edit;
 mod error;
 mod operation;
+mod parse;
+mod patch;
+mod reversible;
+#[cfg(feature = "ropey")]
+mod rope;
+mod selection;
+mod sequence;
+mod side;
+#[cfg(test)]
+pub(crate) mod test_util;
 mod text;
+mod unit;
+#[cfg(feature = "serde")]
+mod wire;
+mod wrapped;
 
+pub use buffer::{GapBuffer, PieceTable, TextBuffer};
+pub use edit::Edits;
 pub use error::OperationError;
+pub use operation::Component;
+pub use reversible::{ReversibleComponent, ReversibleOperation};
+#[cfg(feature = "ropey")]
+pub use rope::RopeUnit;
+pub use selection::{Selection, SelectionRange};
+pub use sequence::{Deletion, Items, Operation, SequenceOperation, SequenceUnit};
+pub use side::Side;
 pub use text::TextOperation;
+#[cfg(feature = "graphemes")]
+pub use unit::Graphemes;
+pub use unit::{convert_index, Bytes, Chars, LengthUnit, Utf16};
+pub use wrapped::{Meta, SelectionMeta, WrappedOperation};
@@ -1,21 +1,62 @@
+use super::unit::LengthUnit;
+use std::fmt;
+
 /// `op`
-/// 定义了如何将一个字符串转化为另一个字符串的的三种原子操作
+/// 定义了如何将一个字符串转化为另一个字符串的的三种原子操作；
+/// 类型参数 `I` 为插入内容的类型，对于 [`SequenceOperation<T>`](super::SequenceOperation) 为 `Vec<T>`；
+/// 类型参数 `D` 为删除记录的内容，默认只记录长度，对于 [`ReversibleOperation`](super::ReversibleOperation) 为被删除的文本
+///
+/// 可以通过 [`TextOperation::ops`](super::TextOperation::ops) 遍历一个操作的全部原子操作，
+/// 或者通过 [`TextOperation::from_components`](super::TextOperation::from_components) 由原子操作构造 `TextOperation`。
 #[derive(Debug, PartialEq, Eq, Clone)]
-pub(super) enum Operation {
+pub enum Component<I = String, D = usize> {
     /// 保持 - 将 base 字符串游标位置后侧的字符串拷贝到 buffer 中，并将 base 字符串游标向右移动相应长度
     Retain(usize),
     /// 插入 - 向 buffer 中插入字符串，且 base 字符串的游标保持不变
-    Insert(String),
+    Insert(I),
     /// 删除 - 移动游标在 base 字符串中，向右移动相应长度，不操作 buffer
-    Delete(usize),
+    Delete(D),
+}
+
+impl Component {
+    /// 原子操作以 `U` 为单位的长度：`Retain`、`Delete` 为其数值，`Insert` 为插入字符串的长度
+    /// # Example
+    /// ```
+    /// use ot_rs::core::{Chars, Component, Utf16};
+    /// assert_eq!(2, Component::Retain(2).len::<Chars>());
+    /// assert_eq!(3, Component::Insert("ab😄".to_string()).len::<Chars>());
+    /// assert_eq!(4, Component::Insert("ab😄".to_string()).len::<Utf16>());
+    /// assert_eq!(0, Component::Delete(0).len::<Chars>());
+    /// ```
+    pub fn len<U: LengthUnit>(&self) -> usize {
+        match self {
+            &Self::Retain(n) => n,
+            Self::Insert(str) => U::len(str),
+            &Self::Delete(n) => n,
+        }
+    }
+
+    /// 原子操作是否为空（长度为 0），空的原子操作是无意义的
+    pub fn is_empty(&self) -> bool {
+        match self {
+            &Self::Retain(n) => n == 0,
+            Self::Insert(str) => str.is_empty(),
+            &Self::Delete(n) => n == 0,
+        }
+    }
 }
 
-impl ToString for Operation {
-    fn to_string(&self) -> String {
+/// 转义 `\` 和 `"`，以便可以通过 `FromStr` 解析回来（早期版本只转义 `"`，参见 `parse` 模块）
+pub(super) fn escape(str: &str) -> String {
+    return str.replace('\\', "\\\\").replace('"', "\\\"");
+}
+
+impl fmt::Display for Component {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            &Self::Retain(n) => format!("retain({})", n),
-            Self::Insert(str) => format!("insert(\"{}\")", str.replace('"', "\\\"")),
-            &Self::Delete(n) => format!("delete({})", n),
+            &Self::Retain(n) => write!(f, "retain({})", n),
+            Self::Insert(str) => write!(f, "insert(\"{}\")", escape(str)),
+            &Self::Delete(n) => write!(f, "delete({})", n),
         }
     }
 }
@@ -23,18 +64,22 @@ impl ToString for Operation {
 #[cfg(test)]
 mod tests {
 
-    use super::Operation;
+    use super::Component;
 
     #[test]
     fn it_works() {
-        assert_eq!("retain(1)", Operation::Retain(1).to_string());
+        assert_eq!("retain(1)", Component::Retain(1).to_string());
         assert_eq!(
             "insert(\"abc\")",
-            Operation::Insert("abc".to_string()).to_string()
+            Component::Insert("abc".to_string()).to_string()
         );
         assert_eq!(
             "insert(\"abc\\\"\")",
-            Operation::Insert("abc\"".to_string()).to_string()
+            Component::Insert("abc\"".to_string()).to_string()
+        );
+        assert_eq!(
+            "insert(\"a\\\\\\\"\")",
+            Component::Insert("a\\\"".to_string()).to_string()
         );
     }
 }
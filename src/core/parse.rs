@@ -0,0 +1,272 @@
+//! 解析 `TextOperation` 和 `Component` 的字符串表示（即 `to_string()` 的输出），
+//! 例如 `(3->5){retain(1).delete(1).retain(1).insert("de\"")}`
+//!
+//! 插入的字符串中 `"` 被转义为 `\"`，`\` 被转义为 `\\`。
+//! 早期版本的 `to_string()` 只转义 `"`，为了能继续解析这些已记录的输出，
+//! 其后不是 `"` 或 `\` 的 `\` 被视为一个字面的 `\`，例如 `insert("C:\tmp")` 解析为 `C:\tmp`。
+//! 注意：早期输出中连续的两个 `\`（或紧跟在 `\` 后的 `"`）存在歧义，将按新的转义规则解析。
+
+use super::error::OperationError;
+use super::operation::Component;
+use super::text::TextOperation;
+use super::unit::LengthUnit;
+use std::str::FromStr;
+
+/// 一个简单的递归下降解析器，`pos` 为当前解析到的字节偏移量
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Parser<'a> {
+        return Parser { input, pos: 0 };
+    }
+
+    fn error(&self) -> OperationError {
+        OperationError::ParseSyntaxError(self.pos)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    /// 消费一个给定的字符串，不匹配时返回语法错误
+    fn expect(&mut self, s: &str) -> Result<(), OperationError> {
+        if !self.input[self.pos..].starts_with(s) {
+            return Err(self.error());
+        }
+        self.pos += s.len();
+        return Ok(());
+    }
+
+    fn number(&mut self) -> Result<usize, OperationError> {
+        let digits = self.input[self.pos..]
+            .bytes()
+            .take_while(u8::is_ascii_digit)
+            .count();
+        if digits == 0 {
+            return Err(self.error());
+        }
+        let n = self.input[self.pos..self.pos + digits]
+            .parse()
+            .map_err(|_| OperationError::ComponentLengthOverflow)?;
+        self.pos += digits;
+        return Ok(n);
+    }
+
+    /// 解析一个带引号的字符串，支持 `\"` 和 `\\` 两种转义，其他位置的 `\` 为字面的 `\`（兼容早期的输出）
+    fn string(&mut self) -> Result<String, OperationError> {
+        self.expect("\"")?;
+        let mut str = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error()),
+                Some('"') => {
+                    self.pos += 1;
+                    return Ok(str);
+                }
+                Some('\\') => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some(c @ '"') | Some(c @ '\\') => {
+                            str.push(c);
+                            self.pos += 1;
+                        }
+                        _ => str.push('\\'),
+                    }
+                }
+                Some(c) => {
+                    str.push(c);
+                    self.pos += c.len_utf8();
+                }
+            }
+        }
+    }
+
+    fn component(&mut self) -> Result<Component, OperationError> {
+        let rest = &self.input[self.pos..];
+        if rest.starts_with("retain(") {
+            self.pos += "retain(".len();
+            let n = self.number()?;
+            self.expect(")")?;
+            return Ok(Component::Retain(n));
+        } else if rest.starts_with("delete(") {
+            self.pos += "delete(".len();
+            let n = self.number()?;
+            self.expect(")")?;
+            return Ok(Component::Delete(n));
+        } else if rest.starts_with("insert(") {
+            self.pos += "insert(".len();
+            let str = self.string()?;
+            self.expect(")")?;
+            return Ok(Component::Insert(str));
+        }
+        return Err(self.error());
+    }
+
+    fn text_operation<U: LengthUnit>(&mut self) -> Result<TextOperation<U>, OperationError> {
+        self.expect("(")?;
+        let base_length = self.number()?;
+        self.expect("->")?;
+        let after_length = self.number()?;
+        self.expect("){")?;
+        let mut components = vec![];
+        if self.peek() != Some('}') {
+            components.push(self.component()?);
+            while self.peek() == Some('.') {
+                self.pos += 1;
+                components.push(self.component()?);
+            }
+        }
+        self.expect("}")?;
+        let operation = TextOperation::from_components(components)?;
+        if operation.base_length() != base_length || operation.target_length() != after_length {
+            return Err(OperationError::ParseLengthMismatch);
+        }
+        return Ok(operation);
+    }
+
+    /// 要求输入已经被全部消费
+    fn finish<T>(&self, value: T) -> Result<T, OperationError> {
+        if self.pos != self.input.len() {
+            return Err(self.error());
+        }
+        return Ok(value);
+    }
+}
+
+impl FromStr for Component {
+    type Err = OperationError;
+
+    /// # Example
+    /// ```
+    /// use ot_rs::core::{Component, OperationError};
+    /// assert_eq!(Component::Retain(1), "retain(1)".parse().unwrap());
+    /// assert_eq!(
+    ///     Component::Insert("a\"b".to_string()),
+    ///     "insert(\"a\\\"b\")".parse().unwrap()
+    /// );
+    /// assert_eq!(
+    ///     OperationError::ParseSyntaxError(7),
+    ///     "delete(x)".parse::<Component>().unwrap_err()
+    /// );
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::new(s);
+        let component = parser.component()?;
+        if component.is_empty() {
+            return Err(OperationError::ComponentEmpty);
+        }
+        parser.finish(component)
+    }
+}
+
+impl<U: LengthUnit> FromStr for TextOperation<U> {
+    type Err = OperationError;
+
+    /// 解析 `to_string()` 的输出，并校验声明的 `(base->after)` 长度
+    /// # Example
+    /// ```
+    /// use ot_rs::core::{OperationError, TextOperation};
+    /// let mut ops = TextOperation::new();
+    /// ops.retain(1).delete(1).retain(1).insert("de\"");
+    /// assert_eq!(ops, ops.to_string().parse().unwrap());
+    /// // 声明的长度与实际不一致
+    /// assert_eq!(
+    ///     OperationError::ParseLengthMismatch,
+    ///     "(2->1){retain(1)}".parse::<TextOperation>().unwrap_err()
+    /// );
+    /// // 语法错误将返回出错位置的字节偏移量
+    /// assert_eq!(
+    ///     OperationError::ParseSyntaxError(16),
+    ///     "(1->1){retain(1)]".parse::<TextOperation>().unwrap_err()
+    /// );
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::new(s);
+        let operation = parser.text_operation()?;
+        parser.finish(operation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::core::{Component, OperationError, TextOperation};
+
+    #[test]
+    fn test_round_trip() {
+        let mut ops = TextOperation::new();
+        ops.insert("\\\"中\\\\文😄\"\"")
+            .retain(3)
+            .delete(2)
+            .insert(")}.")
+            .retain(1);
+        let str = ops.to_string();
+        assert_eq!(ops, str.parse().unwrap());
+        assert_eq!(str, str.parse::<TextOperation>().unwrap().to_string());
+        assert_eq!(
+            TextOperation::new(),
+            "(0->0){}".parse::<TextOperation>().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_syntax_error() {
+        let cases = [
+            ("", 0),
+            ("(1->1)", 5),
+            ("(1->1){retain(1)", 16),
+            ("(1->1){retain(1).}", 17),
+            ("(1->1){retain(1)} ", 17),
+            ("(1->2){retain(1).insert(\"中)}", 30),
+            ("(0->1){insert(\"a\\\")}", 20),
+            ("(a->1){}", 1),
+        ];
+        for &(input, offset) in cases.iter() {
+            assert_eq!(
+                OperationError::ParseSyntaxError(offset),
+                input.parse::<TextOperation>().unwrap_err(),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_legacy_backslash() {
+        // 早期版本的输出不转义 `\`
+        let ops = r#"(0->6){insert("C:\tmp")}"#.parse::<TextOperation>().unwrap();
+        let mut expected = TextOperation::new();
+        expected.insert("C:\\tmp");
+        assert_eq!(expected, ops);
+        assert_eq!(ops, ops.to_string().parse().unwrap());
+        assert_eq!(
+            Component::Insert("a\\b\"".to_string()),
+            r#"insert("a\b\"")"#.parse().unwrap()
+        );
+        assert_eq!(
+            OperationError::ParseLengthMismatch,
+            r#"(0->1){insert("\n")}"#.parse::<TextOperation>().unwrap_err()
+        );
+    }
+
+    #[test]
+    fn test_invalid_component() {
+        assert_eq!(
+            OperationError::ComponentEmpty,
+            "(0->0){retain(0)}".parse::<TextOperation>().unwrap_err()
+        );
+        assert_eq!(
+            OperationError::ComponentEmpty,
+            "insert(\"\")".parse::<Component>().unwrap_err()
+        );
+        assert_eq!(
+            OperationError::ComponentLengthOverflow,
+            "retain(99999999999999999999999)"
+                .parse::<Component>()
+                .unwrap_err()
+        );
+    }
+}
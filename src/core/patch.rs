@@ -0,0 +1,429 @@
+//! 统一 diff 格式（unified diff，即 `diff -u`、`git diff` 的输出格式）的导入与导出。
+//!
+//! 导出时，被操作完整保留、且在 after 中仍是一个完整行的行作为上下文，其余的行作为删除、插入的行；
+//! 导入时，若 hunk 的上下文在 base 中发生了移动，将在附近搜索匹配的位置，并报告每个 hunk 的行偏移量。
+//!
+//! # Example
+//! ```
+//! use ot_rs::core::TextOperation;
+//! let base = "a\nb\nc\n";
+//! let ops: TextOperation = TextOperation::diff(base, "a\nB\nc\n");
+//! let patch = ops.to_unified_diff(base, 1).unwrap();
+//! assert_eq!("--- a\n+++ b\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n", patch);
+//!
+//! // 在 base 的开头插入两行后，patch 仍然可以应用，hunk 的偏移量为 2
+//! let moved = "x\ny\na\nb\nc\n";
+//! let (patched, offsets): (TextOperation, _) = TextOperation::from_unified_diff(moved, &patch).unwrap();
+//! assert_eq!("x\ny\na\nB\nc\n", patched.apply(moved).unwrap());
+//! assert_eq!(vec![2], offsets);
+//! ```
+
+use super::error::OperationError;
+use super::operation::Component;
+use super::text::TextOperation;
+use super::unit::LengthUnit;
+
+/// 统一 diff 中的一行
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Line<'a> {
+    Context(&'a str),
+    Delete(&'a str),
+    Insert(&'a str),
+}
+
+/// 解析得到的 hunk
+struct Hunk {
+    /// base 中的起始行（从 0 开始）
+    old_start: usize,
+    /// hunk 中的全部行，`(类型, 内容)`，类型为 ` `、`-`、`+` 之一
+    lines: Vec<(u8, String)>,
+}
+
+/// 每一行在字符串中的起始字节偏移量，最后追加字符串的长度
+fn line_starts(lines: &[&str]) -> Vec<usize> {
+    let mut starts = Vec::with_capacity(lines.len() + 1);
+    let mut offset = 0;
+    starts.push(0);
+    for line in lines {
+        offset += line.len();
+        starts.push(offset);
+    }
+    return starts;
+}
+
+/// 格式化 hunk 头中的区间
+fn format_range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, len),
+    }
+}
+
+/// 解析 hunk 头中的区间，如 `3,4` 或 `3`
+fn parse_range(s: &str) -> Option<(usize, usize)> {
+    let mut parts = s.splitn(2, ',');
+    let start = parts.next()?.parse().ok()?;
+    let len = match parts.next() {
+        Some(len) => len.parse().ok()?,
+        None => 1,
+    };
+    return Some((start, len));
+}
+
+/// 解析 hunk 头，如 `@@ -1,3 +1,4 @@ fn main()`
+fn parse_hunk_header(line: &str) -> Option<((usize, usize), (usize, usize))> {
+    let mut parts = line.strip_prefix("@@ -")?.split(' ');
+    let old = parse_range(parts.next()?)?;
+    let new = parse_range(parts.next()?.strip_prefix('+')?)?;
+    if !parts.next()?.starts_with("@@") {
+        return None;
+    }
+    return Some((old, new));
+}
+
+/// 解析 patch 中的全部 hunk
+fn parse_patch(patch: &str) -> Result<Vec<Hunk>, OperationError> {
+    let lines: Vec<&str> = patch.split_inclusive('\n').collect();
+    let mut hunks = vec![];
+    let mut i = 0;
+    while i < lines.len() {
+        // hunk 之前的文件头（`diff --git`、`index`、`---`、`+++` 等）全部忽略
+        if !lines[i].starts_with("@@") {
+            i += 1;
+            continue;
+        }
+        let ((old_start, mut old_rem), (_, mut new_rem)) =
+            parse_hunk_header(lines[i]).ok_or(OperationError::PatchSyntaxError(i + 1))?;
+        let mut hunk = Hunk {
+            old_start: if old_rem == 0 {
+                old_start
+            } else {
+                old_start.saturating_sub(1)
+            },
+            lines: vec![],
+        };
+        i += 1;
+        loop {
+            // `\ No newline at end of file` 作用于上一行
+            if lines.get(i).is_some_and(|l| l.starts_with('\\')) {
+                match hunk.lines.last_mut() {
+                    Some((_, last)) if last.ends_with('\n') => {
+                        last.pop();
+                    }
+                    _ => return Err(OperationError::PatchSyntaxError(i + 1)),
+                }
+                i += 1;
+                continue;
+            }
+            if old_rem == 0 && new_rem == 0 {
+                break;
+            }
+            let line = lines
+                .get(i)
+                .ok_or(OperationError::PatchSyntaxError(i + 1))?;
+            // 部分工具会删除空的上下文行行首的空格
+            let (kind, content) = if line.starts_with(['\n', '\r']) {
+                (b' ', *line)
+            } else if let Some(content) = line.strip_prefix(' ') {
+                (b' ', content)
+            } else if let Some(content) = line.strip_prefix('-') {
+                (b'-', content)
+            } else if let Some(content) = line.strip_prefix('+') {
+                (b'+', content)
+            } else {
+                return Err(OperationError::PatchSyntaxError(i + 1));
+            };
+            let rem = match kind {
+                b' ' => old_rem.min(new_rem),
+                b'-' => old_rem,
+                _ => new_rem,
+            };
+            if rem == 0 {
+                return Err(OperationError::PatchSyntaxError(i + 1));
+            }
+            if kind != b'+' {
+                old_rem -= 1;
+            }
+            if kind != b'-' {
+                new_rem -= 1;
+            }
+            let mut content = content.to_string();
+            if !content.ends_with('\n') {
+                content.push('\n');
+            }
+            hunk.lines.push((kind, content));
+            i += 1;
+        }
+        hunks.push(hunk);
+    }
+    return Ok(hunks);
+}
+
+impl<U: LengthUnit> TextOperation<U> {
+    /// 将作用于 `base` 的操作导出为统一 diff 格式，每个 hunk 包含 `context_lines` 行上下文。
+    /// 操作不产生任何修改时返回空字符串
+    pub fn to_unified_diff(
+        &self,
+        base: &str,
+        context_lines: usize,
+    ) -> Result<String, OperationError> {
+        let after = self.apply(base)?;
+        let base_lines: Vec<&str> = base.split_inclusive('\n').collect();
+        let after_lines: Vec<&str> = after.split_inclusive('\n').collect();
+        let base_starts = line_starts(&base_lines);
+        let after_starts = line_starts(&after_lines);
+
+        // 找到全部保持不变的行：整行位于同一个 Retain 中，且在 after 中仍然是一个完整的行
+        let mut matched = vec![];
+        let (mut base_pos, mut after_pos) = (0usize, 0usize); // 字节偏移量
+        for op in self.ops() {
+            match op {
+                &Component::Retain(n) => {
+                    let len = U::byte_offset(&base[base_pos..], n)?;
+                    let first = base_starts.partition_point(|&s| s < base_pos);
+                    for bi in first..base_lines.len() {
+                        let (start, end) = (base_starts[bi], base_starts[bi + 1]);
+                        if end > base_pos + len {
+                            break;
+                        }
+                        let a_start = after_pos + (start - base_pos);
+                        let a_end = a_start + (end - start);
+                        let is_line_start = a_start == 0 || after.as_bytes()[a_start - 1] == b'\n';
+                        let is_line_end = base_lines[bi].ends_with('\n') || a_end == after.len();
+                        if is_line_start && is_line_end {
+                            let ai = after_starts.partition_point(|&s| s < a_start);
+                            matched.push((bi, ai));
+                        }
+                    }
+                    base_pos += len;
+                    after_pos += len;
+                }
+                Component::Insert(str) => after_pos += str.len(),
+                &Component::Delete(n) => base_pos += U::byte_offset(&base[base_pos..], n)?,
+            }
+        }
+
+        // 按顺序生成全部行，相邻的两个不变行之间先删除后插入
+        let mut lines = vec![];
+        let (mut bi, mut ai) = (0usize, 0usize);
+        for &(next_bi, next_ai) in matched
+            .iter()
+            .chain(Some(&(base_lines.len(), after_lines.len())))
+        {
+            lines.extend(base_lines[bi..next_bi].iter().map(|l| Line::Delete(l)));
+            lines.extend(after_lines[ai..next_ai].iter().map(|l| Line::Insert(l)));
+            if next_bi < base_lines.len() {
+                lines.push(Line::Context(base_lines[next_bi]));
+            }
+            bi = next_bi + 1;
+            ai = next_ai + 1;
+        }
+
+        // 将修改及其上下文分组为 hunk，两个修改之间的上下文不超过 2 * context_lines 行时合并为一个 hunk
+        let changes: Vec<usize> = (0..lines.len())
+            .filter(|&i| !matches!(lines[i], Line::Context(_)))
+            .collect();
+        let mut groups: Vec<(usize, usize)> = vec![];
+        for &i in &changes {
+            match groups.last_mut() {
+                Some((_, last)) if i - *last <= 2 * context_lines + 1 => *last = i,
+                _ => groups.push((i, i)),
+            }
+        }
+        if groups.is_empty() {
+            return Ok(String::new());
+        }
+
+        let mut diff = String::from("--- a\n+++ b\n");
+        for (first, last) in groups {
+            let start = first.saturating_sub(context_lines);
+            let end = (last + 1 + context_lines).min(lines.len());
+            let old_start = lines[..start]
+                .iter()
+                .filter(|l| !matches!(l, Line::Insert(_)))
+                .count();
+            let new_start = lines[..start]
+                .iter()
+                .filter(|l| !matches!(l, Line::Delete(_)))
+                .count();
+            let hunk = &lines[start..end];
+            let old_len = hunk
+                .iter()
+                .filter(|l| !matches!(l, Line::Insert(_)))
+                .count();
+            let new_len = hunk
+                .iter()
+                .filter(|l| !matches!(l, Line::Delete(_)))
+                .count();
+            diff.push_str(&format!(
+                "@@ -{} +{} @@\n",
+                format_range(old_start, old_len),
+                format_range(new_start, new_len)
+            ));
+            for line in hunk {
+                let (prefix, content) = match line {
+                    Line::Context(l) => (' ', l),
+                    Line::Delete(l) => ('-', l),
+                    Line::Insert(l) => ('+', l),
+                };
+                diff.push(prefix);
+                diff.push_str(content);
+                if !content.ends_with('\n') {
+                    diff.push_str("\n\\ No newline at end of file\n");
+                }
+            }
+        }
+        return Ok(diff);
+    }
+
+    /// 将统一 diff 格式的 patch 导入为作用于 `base` 的操作，同时返回每个 hunk 相对于 hunk 头中声明位置的行偏移量。
+    ///
+    /// 若 hunk 的上下文与删除的行在声明的位置无法匹配，将在不与前一个 hunk 重叠的范围内，
+    /// 由近及远搜索可以匹配的位置；找不到时返回 `PatchHunkNotFound`。patch 格式错误时返回 `PatchSyntaxError`
+    /// # Example
+    /// ```
+    /// use ot_rs::core::{OperationError, TextOperation};
+    /// let patch = "@@ -1 +1 @@\n-a\n+b\n";
+    /// let (ops, offsets): (TextOperation, _) = TextOperation::from_unified_diff("a\n", patch).unwrap();
+    /// assert_eq!("b\n", ops.apply("a\n").unwrap());
+    /// assert_eq!(vec![0], offsets);
+    /// assert_eq!(
+    ///     OperationError::PatchHunkNotFound(0),
+    ///     TextOperation::<ot_rs::core::Chars>::from_unified_diff("c\n", patch).unwrap_err()
+    /// );
+    /// ```
+    pub fn from_unified_diff(
+        base: &str,
+        patch: &str,
+    ) -> Result<(TextOperation<U>, Vec<isize>), OperationError> {
+        let hunks = parse_patch(patch)?;
+        let base_lines: Vec<&str> = base.split_inclusive('\n').collect();
+        let base_starts = line_starts(&base_lines);
+
+        let mut operation = TextOperation::with_unit();
+        let mut offsets = vec![];
+        let mut cursor = 0usize; // 已处理的 base 的行数
+        let mut last_offset = 0isize;
+        for (index, hunk) in hunks.iter().enumerate() {
+            let old: Vec<&str> = hunk
+                .lines
+                .iter()
+                .filter(|(kind, _)| *kind != b'+')
+                .map(|(_, l)| l.as_str())
+                .collect();
+            if old.len() > base_lines.len() - cursor {
+                return Err(OperationError::PatchHunkNotFound(index));
+            }
+            let (min, max) = (cursor, base_lines.len() - old.len());
+            let matches = |p: usize| base_lines[p..p + old.len()] == old[..];
+            // 以前一个 hunk 的偏移量修正声明的位置，再由近及远搜索
+            let guess =
+                (hunk.old_start as isize + last_offset).clamp(min as isize, max as isize) as usize;
+            let position = (0..=(max - min))
+                .flat_map(|d| vec![guess.checked_add(d), guess.checked_sub(d)])
+                .flatten()
+                .filter(|&p| p >= min && p <= max)
+                .find(|&p| matches(p))
+                .ok_or(OperationError::PatchHunkNotFound(index))?;
+            last_offset = position as isize - hunk.old_start as isize;
+            offsets.push(last_offset);
+
+            operation.retain(U::len(&base[base_starts[cursor]..base_starts[position]]));
+            for (kind, line) in &hunk.lines {
+                match kind {
+                    b' ' => operation.retain(U::len(line)),
+                    b'-' => operation.delete(U::len(line)),
+                    _ => operation.insert(line.clone()),
+                };
+            }
+            cursor = position + old.len();
+        }
+        operation.retain(U::len(&base[base_starts[cursor]..]));
+        return Ok((operation, offsets));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::core::test_util::random_string_from;
+    use crate::core::{OperationError, TextOperation, Utf16};
+
+    /// 以整行为主的片段
+    const PIECES: [&str; 6] = ["a\n", "b\n", "c", "\n", "中", "😄\n"];
+
+    fn random_string(n: usize) -> String {
+        return random_string_from(&PIECES, n);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for _ in 0..100 {
+            let base = random_string(30);
+            let after = random_string(30);
+            for &context in &[0, 1, 3] {
+                let ops = TextOperation::<Utf16>::diff_lines(&base, &after);
+                let patch = ops.to_unified_diff(&base, context).unwrap();
+                let (patched, offsets) =
+                    TextOperation::<Utf16>::from_unified_diff(&base, &patch).unwrap();
+                assert_eq!(after, patched.apply(&base).unwrap(), "{}", patch);
+                assert!(offsets.iter().all(|&o| o == 0));
+            }
+        }
+    }
+
+    #[test]
+    fn test_no_newline_at_end_of_file() {
+        let base = "a\nb";
+        let ops: TextOperation = TextOperation::diff(base, "a\nb\nc");
+        let patch = ops.to_unified_diff(base, 3).unwrap();
+        assert_eq!(
+            "--- a\n+++ b\n@@ -1,2 +1,3 @@\n a\n-b\n\\ No newline at end of file\n+b\n+c\n\\ No newline at end of file\n",
+            patch
+        );
+        let (patched, _): (TextOperation, _) =
+            TextOperation::from_unified_diff(base, &patch).unwrap();
+        assert_eq!("a\nb\nc", patched.apply(base).unwrap());
+        assert_eq!("", TextOperation::new().to_unified_diff("", 3).unwrap());
+    }
+
+    #[test]
+    fn test_fuzzy_offset() {
+        let patch = "diff --git a/f b/f\n--- a/f\n+++ b/f\n@@ -2,2 +2,2 @@\n x\n-y\n+Y\n@@ -6,0 +7 @@\n+new\n";
+        let base = "0\n1\n2\nx\ny\n3\n";
+        let (ops, offsets): (TextOperation, _) =
+            TextOperation::from_unified_diff(base, patch).unwrap();
+        assert_eq!("0\n1\n2\nx\nY\n3\nnew\n", ops.apply(base).unwrap());
+        assert_eq!(vec![2, 0], offsets);
+    }
+
+    #[test]
+    fn test_patch_error() {
+        let from = |base: &str, patch: &str| {
+            TextOperation::<crate::core::Chars>::from_unified_diff(base, patch).map(|(ops, _)| ops)
+        };
+        assert_eq!(
+            Err(OperationError::PatchSyntaxError(1)),
+            from("a\n", "@@ -1 +1 \n")
+        );
+        assert_eq!(
+            Err(OperationError::PatchSyntaxError(3)),
+            from("a\n", "@@ -1 +1 @@\n-a\n")
+        );
+        assert_eq!(
+            Err(OperationError::PatchSyntaxError(2)),
+            from("a\n", "@@ -1 +1 @@\n?a\n+b\n")
+        );
+        // 行首为多字节字符
+        assert_eq!(
+            Err(OperationError::PatchSyntaxError(2)),
+            from("a\n", "@@ -1 +1 @@\n中\n+b\n")
+        );
+        // 两个 hunk 不能匹配到同一行
+        assert_eq!(
+            Err(OperationError::PatchHunkNotFound(1)),
+            from("a\n", "@@ -1 +1 @@\n-a\n+b\n@@ -1 +1 @@\n-a\n+c\n")
+        );
+    }
+}
@@ -0,0 +1,272 @@
+//! 自包含的可逆操作：删除的原子操作携带被删除的文本（类似 ShareDB 的 text 类型），
+//! 因此求逆时不再需要 base 字符串，适用于撤销历史、审计日志等 base 早已丢失的场景。
+//!
+//! [`ReversibleOperation<U>`] 即 [`Operation<U, String>`]，与 [`TextOperation<U>`] 共用同一套
+//! retain/insert/delete、compose、transform 的实现；被删除的文本不一致时返回 `DeletedTextMismatch`。
+//!
+//! # Example
+//! ```
+//! use ot_rs::core::{ReversibleOperation, TextOperation};
+//! let base = "abc";
+//! let mut ops = TextOperation::new();
+//! ops.retain(1).delete(1).retain(1).insert("d");
+//! let reversible = ReversibleOperation::from_operation(&ops, base).unwrap();
+//! assert_eq!("(3->3){retain(1).delete(\"b\").retain(1).insert(\"d\")}", reversible.to_string());
+//!
+//! // 之后不需要 base 也可以求逆
+//! let after = reversible.apply("abc").unwrap();
+//! assert_eq!("abc", reversible.invert().apply(&after).unwrap());
+//! assert_eq!(ops, reversible.to_operation());
+//! ```
+//!
+//! compose 与 transform 会检查双方记录的被删除文本：
+//! ```
+//! use ot_rs::core::{OperationError, ReversibleOperation, Side};
+//! let mut ops1 = ReversibleOperation::new();
+//! ops1.retain(1).insert("xy").delete("b");
+//! let mut ops2 = ReversibleOperation::new();
+//! ops2.delete("ax").retain(1);
+//! let composed = ops1.compose(&ops2).unwrap();
+//! assert_eq!("(2->1){insert(\"y\").delete(\"ab\")}", composed.to_string());
+//! assert_eq!("ab", composed.invert().apply("y").unwrap());
+//!
+//! let mut a = ReversibleOperation::new();
+//! a.delete("ab").retain(1);
+//! let mut b = ReversibleOperation::new();
+//! b.retain(1).delete("b").insert("x").retain(1);
+//! let (a_prime, _) = a.transform_with_side(&b, Side::Left).unwrap();
+//! assert_eq!("(3->2){delete(\"a\").retain(2)}", a_prime.to_string());
+//!
+//! let mut c = ReversibleOperation::new();
+//! c.delete("xb").retain(1);
+//! assert_eq!(OperationError::DeletedTextMismatch, a.transform(&c).unwrap_err());
+//! ```
+
+use super::error::OperationError;
+use super::operation::{escape, Component};
+use super::sequence::{Deletion, Operation};
+use super::text::TextOperation;
+use super::unit::{Chars, LengthUnit};
+use std::fmt;
+
+/// 可逆操作的原子操作，与 [`Component`] 的区别在于 `Delete` 携带了被删除的文本
+pub type ReversibleComponent = Component<String, String>;
+
+/// 删除的原子操作携带被删除文本的 [`TextOperation`]，全部长度以 `U` 为单位计算
+pub type ReversibleOperation<U = Chars> = Operation<U, String>;
+
+/// 记录被删除的文本
+impl<U: LengthUnit> Deletion<U> for String {
+    fn len(&self) -> usize {
+        U::len(self)
+    }
+
+    fn split_at(&self, n: usize) -> Result<(String, String), OperationError> {
+        let (head, tail) = U::split_at(self, n)?;
+        return Ok((head.to_string(), tail.to_string()));
+    }
+
+    fn extend(&mut self, other: String) {
+        self.push_str(&other)
+    }
+
+    fn matches(&self, s: &str) -> bool {
+        self == s
+    }
+}
+
+impl<U: LengthUnit> fmt::Display for ReversibleOperation<U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}->{}){{", self.base_length(), self.target_length())?;
+        for (i, op) in self.ops().enumerate() {
+            if i != 0 {
+                f.write_str(".")?;
+            }
+            match op {
+                &Component::Retain(n) => write!(f, "retain({})", n)?,
+                Component::Insert(str) => write!(f, "insert(\"{}\")", escape(str))?,
+                Component::Delete(str) => write!(f, "delete(\"{}\")", escape(str))?,
+            }
+        }
+        f.write_str("}")
+    }
+}
+
+impl ReversibleOperation {
+    /// 构造函数，创建一个无操作的、以 [`Chars`] 为长度单位的可逆操作
+    pub fn new() -> ReversibleOperation {
+        return ReversibleOperation::with_unit();
+    }
+}
+
+impl<U: LengthUnit> ReversibleOperation<U> {
+    /// 给定 base 字符串，由 `TextOperation` 构造可逆操作
+    pub fn from_operation(
+        operation: &TextOperation<U>,
+        base: &str,
+    ) -> Result<ReversibleOperation<U>, OperationError> {
+        if U::len(base) != operation.base_length() {
+            return Err(OperationError::OperationApplyStringNotCompatible);
+        }
+        let mut rest = base; // base 中游标之后的部分
+        let mut reversible = ReversibleOperation::with_unit();
+        for op in operation.ops() {
+            match op {
+                &Component::Retain(n) => {
+                    reversible.retain(n);
+                    rest = U::split_at(rest, n)?.1;
+                }
+                Component::Insert(str) => {
+                    reversible.insert(str.clone());
+                }
+                &Component::Delete(n) => {
+                    let (deleted, tail) = U::split_at(rest, n)?;
+                    reversible.delete(deleted);
+                    rest = tail;
+                }
+            }
+        }
+        return Ok(reversible);
+    }
+
+    /// 丢弃被删除的文本，转换为 `TextOperation`
+    pub fn to_operation(&self) -> TextOperation<U> {
+        let mut operation = TextOperation::with_unit();
+        for op in self.ops() {
+            match op {
+                &Component::Retain(n) => operation.retain(n),
+                Component::Insert(str) => operation.insert(str.clone()),
+                Component::Delete(str) => operation.delete(U::len(str)),
+            };
+        }
+        return operation;
+    }
+
+    /// 删除当前位置的字符串，`str` 为被删除的文本
+    pub fn delete<T: Into<String>>(&mut self, str: T) -> &mut ReversibleOperation<U> {
+        return self.push_delete(str.into());
+    }
+
+    /// 将该操作应用到字符串上，被删除的文本与 base 中对应的内容不一致时返回 `DeletedTextMismatch`
+    /// # Example
+    /// ```
+    /// use ot_rs::core::{OperationError, ReversibleOperation};
+    /// let mut ops = ReversibleOperation::new();
+    /// ops.retain(1).delete("b");
+    /// assert_eq!("a", ops.apply("ab").unwrap());
+    /// assert_eq!(OperationError::DeletedTextMismatch, ops.apply("ac").unwrap_err());
+    /// ```
+    pub fn apply(&self, base: &str) -> Result<String, OperationError> {
+        return self.apply_slice(base);
+    }
+
+    /// 生成该操作的逆操作，无需 base 字符串：插入变为删除，删除变为插入
+    pub fn invert(&self) -> ReversibleOperation<U> {
+        let mut inverse = ReversibleOperation::with_unit();
+        for op in self.ops() {
+            match op {
+                &Component::Retain(n) => inverse.retain(n),
+                Component::Insert(str) => inverse.delete(str.clone()),
+                Component::Delete(str) => inverse.insert(str.clone()),
+            };
+        }
+        return inverse;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::ReversibleOperation;
+    use crate::core::test_util::random_string;
+    use crate::core::{OperationError, Side, TextOperation, Utf16};
+    use rand::{self, Rng};
+
+    fn random_reversible(base: &str) -> ReversibleOperation {
+        let after = random_string(rand::thread_rng().gen_range(0..30));
+        let ops: TextOperation = TextOperation::diff(base, &after);
+        return ReversibleOperation::from_operation(&ops, base).unwrap();
+    }
+
+    #[test]
+    fn test_invert() {
+        for _ in 0..100 {
+            let base = random_string(20);
+            let ops = random_reversible(&base);
+            let after = ops.apply(&base).unwrap();
+            assert_eq!(after, ops.to_operation().apply(&base).unwrap());
+            assert_eq!(base, ops.invert().apply(&after).unwrap());
+            assert_eq!(ops, ops.invert().invert());
+            assert_eq!(
+                ops.to_operation().invert(&base).unwrap(),
+                ops.invert().to_operation()
+            );
+        }
+    }
+
+    #[test]
+    fn test_compose() {
+        for _ in 0..100 {
+            let base = random_string(20);
+            let a = random_reversible(&base);
+            let after_a = a.apply(&base).unwrap();
+            let b = random_reversible(&after_a);
+            let after_b = b.apply(&after_a).unwrap();
+            let ab = a.compose(&b).unwrap();
+            assert_eq!(after_b, ab.apply(&base).unwrap());
+            assert_eq!(base, ab.invert().apply(&after_b).unwrap());
+            assert_eq!(
+                a.to_operation().compose(&b.to_operation()).unwrap(),
+                ab.to_operation()
+            );
+        }
+    }
+
+    #[test]
+    fn test_transform() {
+        for _ in 0..100 {
+            let base = random_string(20);
+            let a = random_reversible(&base);
+            let b = random_reversible(&base);
+            for &side in &[Side::Left, Side::Right] {
+                let (a_prime, b_prime) = a.transform_with_side(&b, side).unwrap();
+                let after = b_prime.apply(&a.apply(&base).unwrap()).unwrap();
+                assert_eq!(after, a_prime.apply(&b.apply(&base).unwrap()).unwrap());
+                let (text_a_prime, text_b_prime) = a
+                    .to_operation()
+                    .transform_with_side(&b.to_operation(), side)
+                    .unwrap();
+                assert_eq!(text_a_prime, a_prime.to_operation());
+                assert_eq!(text_b_prime, b_prime.to_operation());
+            }
+        }
+    }
+
+    #[test]
+    fn test_utf16() {
+        let base = "😄ab";
+        let ops = TextOperation::<Utf16>::diff(base, "a😂");
+        let reversible = ReversibleOperation::from_operation(&ops, base).unwrap();
+        let after = reversible.apply(base).unwrap();
+        assert_eq!("a😂", after);
+        assert_eq!(base, reversible.invert().apply(&after).unwrap());
+    }
+
+    #[test]
+    fn test_mismatch() {
+        let mut a = ReversibleOperation::new();
+        a.delete("a");
+        let mut b = ReversibleOperation::new();
+        b.delete("b");
+        assert_eq!(
+            OperationError::DeletedTextMismatch,
+            a.transform(&b).unwrap_err()
+        );
+        let mut a = ReversibleOperation::new();
+        a.insert("a");
+        assert_eq!(
+            OperationError::DeletedTextMismatch,
+            a.compose(&b).unwrap_err()
+        );
+    }
+}
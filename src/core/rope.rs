@@ -0,0 +1,256 @@
+//! 将 `TextOperation` 应用到 [`ropey::Rope`] 上（需要开启 `ropey` feature）。
+//!
+//! `TextOperation::apply` 每次都会重建整个字符串，对于较大的文档，每次按键的代价都是 O(文档长度)。
+//! 而 Rope 的定位、插入、删除都是 O(log n) 的，因此将一个小的操作应用到 Rope 上的代价为 O(操作的原子操作数量 × log n)。
+//!
+//! # Example
+//! ```
+//! use ot_rs::core::TextOperation;
+//! use ropey::Rope;
+//! let mut rope = Rope::from_str("abc");
+//! let mut ops = TextOperation::new();
+//! ops.retain(1).delete(1).retain(1).insert("d");
+//! let inverse = ops.invert_from_rope(&rope).unwrap();
+//! ops.apply_to_rope(&mut rope).unwrap();
+//! assert_eq!("acd", rope.to_string());
+//! inverse.apply_to_rope(&mut rope).unwrap();
+//! assert_eq!("abc", rope.to_string());
+//! ```
+
+use super::buffer::TextBuffer;
+use super::error::OperationError;
+use super::operation::Component;
+use super::text::TextOperation;
+use super::unit::{Bytes, Chars, LengthUnit, Utf16};
+use ropey::Rope;
+use std::ops::Range;
+
+/// 可以在 Rope 上以 O(log n) 定位的长度单位
+pub trait RopeUnit: LengthUnit {
+    /// Rope 在该单位下的长度
+    fn rope_len(rope: &Rope) -> usize;
+
+    /// 将以该单位计算的位置转换为 Rope 的字符下标，该位置不是字符边界时返回 `NotOnUnitBoundary`
+    fn to_char_idx(rope: &Rope, index: usize) -> Result<usize, OperationError>;
+}
+
+impl RopeUnit for Chars {
+    fn rope_len(rope: &Rope) -> usize {
+        rope.len_chars()
+    }
+
+    fn to_char_idx(_rope: &Rope, index: usize) -> Result<usize, OperationError> {
+        Ok(index)
+    }
+}
+
+impl RopeUnit for Utf16 {
+    fn rope_len(rope: &Rope) -> usize {
+        rope.len_utf16_cu()
+    }
+
+    fn to_char_idx(rope: &Rope, index: usize) -> Result<usize, OperationError> {
+        let char_idx = rope.utf16_cu_to_char(index);
+        if rope.char_to_utf16_cu(char_idx) != index {
+            return Err(OperationError::NotOnUnitBoundary);
+        }
+        return Ok(char_idx);
+    }
+}
+
+impl RopeUnit for Bytes {
+    fn rope_len(rope: &Rope) -> usize {
+        rope.len_bytes()
+    }
+
+    fn to_char_idx(rope: &Rope, index: usize) -> Result<usize, OperationError> {
+        let char_idx = rope.byte_to_char(index);
+        if rope.char_to_byte(char_idx) != index {
+            return Err(OperationError::NotOnUnitBoundary);
+        }
+        return Ok(char_idx);
+    }
+}
+
+impl<U: RopeUnit> TextBuffer<U> for Rope {
+    fn unit_len(&self) -> usize {
+        U::rope_len(self)
+    }
+
+    fn unit_slice(&self, range: Range<usize>) -> Result<String, OperationError> {
+        if range.start > range.end || range.end > U::rope_len(self) {
+            return Err(OperationError::OperationMoreLeftString);
+        }
+        let start = U::to_char_idx(self, range.start)?;
+        let end = U::to_char_idx(self, range.end)?;
+        return Ok(self.slice(start..end).to_string());
+    }
+
+    fn unit_replace(&mut self, range: Range<usize>, text: &str) -> Result<(), OperationError> {
+        if range.start > range.end || range.end > U::rope_len(self) {
+            return Err(OperationError::OperationMoreLeftString);
+        }
+        let start = U::to_char_idx(self, range.start)?;
+        let end = U::to_char_idx(self, range.end)?;
+        self.remove(start..end);
+        self.insert(start, text);
+        return Ok(());
+    }
+}
+
+/// 操作在 Rope 上对应的一次修改
+struct RopeEdit<'a> {
+    /// 在 base 中以 `U` 为单位的起始位置
+    start: usize,
+    /// 在 base 中以字符为单位的起始位置
+    char_start: usize,
+    /// 删除的长度（以 `U` 为单位）、字符数，或插入的字符串
+    kind: RopeEditKind<'a>,
+}
+
+enum RopeEditKind<'a> {
+    Delete(usize, usize),
+    Insert(&'a str),
+}
+
+impl<U: RopeUnit> TextOperation<U> {
+    /// 将该操作在 base 中的每个删除区间、插入位置转换为 Rope 的字符下标
+    fn rope_edits(&self, rope: &Rope) -> Result<Vec<RopeEdit<'_>>, OperationError> {
+        if U::rope_len(rope) != self.base_length() {
+            return Err(OperationError::OperationApplyStringNotCompatible);
+        }
+        let mut edits = vec![];
+        let mut cursor = 0usize; // 以 U 为单位的游标
+        for op in self.ops() {
+            match op {
+                &Component::Retain(n) => cursor += n,
+                Component::Insert(str) => edits.push(RopeEdit {
+                    start: cursor,
+                    char_start: U::to_char_idx(rope, cursor)?,
+                    kind: RopeEditKind::Insert(str),
+                }),
+                &Component::Delete(n) => {
+                    let char_start = U::to_char_idx(rope, cursor)?;
+                    let char_end = U::to_char_idx(rope, cursor + n)?;
+                    edits.push(RopeEdit {
+                        start: cursor,
+                        char_start,
+                        kind: RopeEditKind::Delete(n, char_end - char_start),
+                    });
+                    cursor += n;
+                }
+            }
+        }
+        return Ok(edits);
+    }
+
+    /// 将该操作应用到 Rope 上。出错时 Rope 保持不变
+    pub fn apply_to_rope(&self, rope: &mut Rope) -> Result<(), OperationError> {
+        let edits = self.rope_edits(rope)?;
+        // 从后往前修改，保证前面的下标不受影响；同一位置的 Insert 总是在 Delete 之前，因此倒序时先删除后插入
+        for edit in edits.iter().rev() {
+            match edit.kind {
+                RopeEditKind::Insert(str) => rope.insert(edit.char_start, str),
+                RopeEditKind::Delete(_, chars) => {
+                    rope.remove(edit.char_start..edit.char_start + chars)
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    /// 同 `invert`，被删除的内容从 Rope 中读取
+    pub fn invert_from_rope(&self, rope: &Rope) -> Result<TextOperation<U>, OperationError> {
+        let edits = self.rope_edits(rope)?;
+        let mut inverse = TextOperation::with_unit();
+        let mut cursor = 0usize; // 以 U 为单位的游标
+        for edit in edits {
+            inverse.retain(edit.start - cursor);
+            cursor = edit.start;
+            match edit.kind {
+                RopeEditKind::Insert(str) => {
+                    inverse.delete(U::len(str));
+                }
+                RopeEditKind::Delete(n, chars) => {
+                    let deleted = rope.slice(edit.char_start..edit.char_start + chars);
+                    inverse.insert(deleted.to_string());
+                    cursor += n;
+                }
+            }
+        }
+        inverse.retain(self.base_length() - cursor);
+        return Ok(inverse);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::core::test_util::random_string;
+    use crate::core::{OperationError, TextOperation, Utf16};
+    use ropey::Rope;
+
+    #[test]
+    fn test_apply_to_rope() {
+        for _ in 0..100 {
+            let base = random_string(40);
+            let after = random_string(40);
+            let ops: TextOperation = TextOperation::diff(&base, &after);
+            let mut rope = Rope::from_str(&base);
+            assert_eq!(
+                ops.invert(&base).unwrap(),
+                ops.invert_from_rope(&rope).unwrap()
+            );
+            ops.apply_to_rope(&mut rope).unwrap();
+            assert_eq!(after, rope.to_string());
+
+            let ops = TextOperation::<Utf16>::diff(&base, &after);
+            let mut rope = Rope::from_str(&base);
+            assert_eq!(
+                ops.invert(&base).unwrap(),
+                ops.invert_from_rope(&rope).unwrap()
+            );
+            ops.apply_to_rope(&mut rope).unwrap();
+            assert_eq!(after, rope.to_string());
+
+            // 通过 TextBuffer 应用
+            let mut rope = Rope::from_str(&base);
+            assert_eq!(ops.invert(&base).unwrap(), ops.invert_from(&rope).unwrap());
+            ops.apply_to(&mut rope).unwrap();
+            assert_eq!(after, rope.to_string());
+        }
+    }
+
+    #[test]
+    fn test_rope_error() {
+        let mut rope = Rope::from_str("😄");
+        let mut ops = TextOperation::<Utf16>::with_unit();
+        ops.retain(1).insert("a").retain(1);
+        assert_eq!(
+            OperationError::NotOnUnitBoundary,
+            ops.apply_to_rope(&mut rope).unwrap_err()
+        );
+        assert_eq!("😄", rope.to_string());
+        let mut ops = TextOperation::new();
+        ops.retain(2);
+        assert_eq!(
+            OperationError::OperationApplyStringNotCompatible,
+            ops.apply_to_rope(&mut rope).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn test_large_document() {
+        // 在一个较大的文档中间进行一次小的修改
+        let base = "0123456789\n".repeat(100_000);
+        let mut rope = Rope::from_str(&base);
+        let mut ops = TextOperation::new();
+        ops.retain(500_000)
+            .delete(3)
+            .insert("abc")
+            .retain(base.len() - 500_003);
+        ops.apply_to_rope(&mut rope).unwrap();
+        assert_eq!(base.len(), rope.len_chars());
+        assert_eq!("abc", rope.slice(500_000..500_003).to_string());
+    }
+}
@@ -0,0 +1,201 @@
+//! 光标与选区，以及如何将其通过 `TextOperation` 进行转换。
+//! > 实现上参考了 [Operational-Transformation/ot.js](https://github.com/Operational-Transformation/ot.js/blob/master/lib/selection.js)
+
+use super::text::TextOperation;
+use super::unit::LengthUnit;
+
+/// 一个选区：`anchor` 为选区开始选择的位置，`head` 为选区的另一端（即光标所在的位置），
+/// `anchor == head` 时表示一个光标
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SelectionRange {
+    pub anchor: usize,
+    pub head: usize,
+}
+
+impl SelectionRange {
+    /// 构造函数
+    pub fn new(anchor: usize, head: usize) -> SelectionRange {
+        return SelectionRange { anchor, head };
+    }
+
+    /// 构造一个光标
+    pub fn cursor(position: usize) -> SelectionRange {
+        return SelectionRange::new(position, position);
+    }
+
+    /// 是否为一个光标（即没有选中任何内容）
+    pub fn is_empty(&self) -> bool {
+        self.anchor == self.head
+    }
+
+    /// 选区的起始位置
+    pub fn start(&self) -> usize {
+        self.anchor.min(self.head)
+    }
+
+    /// 选区的结束位置
+    pub fn end(&self) -> usize {
+        self.anchor.max(self.head)
+    }
+
+    /// 将选区通过一个操作进行转换，与 ot.js 的 `Range.transform` 一致：
+    /// 在选区端点处的插入将把该端点推到插入内容的后方，等价于 `transform_with(operation, true)`
+    /// # Example
+    /// ```
+    /// use ot_rs::core::{SelectionRange, TextOperation};
+    /// let mut ops = TextOperation::new();
+    /// ops.retain(1).insert("ab").retain(3);
+    /// assert_eq!(SelectionRange::new(3, 5), SelectionRange::new(1, 3).transform(&ops));
+    /// assert_eq!(SelectionRange::cursor(0), SelectionRange::cursor(0).transform(&ops));
+    /// ```
+    pub fn transform<U: LengthUnit>(&self, operation: &TextOperation<U>) -> SelectionRange {
+        return self.transform_with(operation, true);
+    }
+
+    /// 将选区通过一个操作进行转换，`is_own_op` 表示该操作是否是选区所属用户自己的操作，
+    /// 决定在选区端点处的插入是否推动该端点，参见 [`TextOperation::transform_index`]
+    /// # Example
+    /// ```
+    /// use ot_rs::core::{SelectionRange, TextOperation};
+    /// let mut ops = TextOperation::new();
+    /// ops.retain(1).insert("ab").retain(3);
+    /// assert_eq!(
+    ///     SelectionRange::cursor(1),
+    ///     SelectionRange::cursor(1).transform_with(&ops, false)
+    /// );
+    /// assert_eq!(
+    ///     SelectionRange::cursor(3),
+    ///     SelectionRange::cursor(1).transform_with(&ops, true)
+    /// );
+    /// ```
+    pub fn transform_with<U: LengthUnit>(
+        &self,
+        operation: &TextOperation<U>,
+        is_own_op: bool,
+    ) -> SelectionRange {
+        let anchor = operation.transform_index(self.anchor, is_own_op);
+        if self.is_empty() {
+            return SelectionRange::cursor(anchor);
+        }
+        let head = operation.transform_index(self.head, is_own_op);
+        return SelectionRange::new(anchor, head);
+    }
+}
+
+/// 一个选择，由多个选区组成（例如多光标编辑）
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Selection {
+    pub ranges: Vec<SelectionRange>,
+}
+
+impl Selection {
+    /// 构造函数
+    pub fn new(ranges: Vec<SelectionRange>) -> Selection {
+        return Selection { ranges };
+    }
+
+    /// 构造一个只包含一个光标的选择
+    pub fn cursor(position: usize) -> Selection {
+        return Selection::new(vec![SelectionRange::cursor(position)]);
+    }
+
+    /// 是否选中了内容
+    pub fn something_selected(&self) -> bool {
+        self.ranges.iter().any(|r| !r.is_empty())
+    }
+
+    /// 组合两个选择，与 ot.js 一致：后一个选择将覆盖前一个选择
+    pub fn compose(&self, other: &Selection) -> Selection {
+        return other.clone();
+    }
+
+    /// 将选择中的全部选区通过一个操作进行转换，参见 [`SelectionRange::transform`]
+    /// # Example
+    /// ```
+    /// use ot_rs::core::{Selection, SelectionRange, TextOperation};
+    /// let base = "hello world";
+    /// let selection = Selection::new(vec![SelectionRange::new(0, 5), SelectionRange::cursor(11)]);
+    /// let mut ops = TextOperation::new();
+    /// ops.delete(6).retain(5).insert("!");
+    /// assert_eq!("world!", ops.apply(base).unwrap());
+    /// assert_eq!(
+    ///     Selection::new(vec![SelectionRange::cursor(0), SelectionRange::cursor(6)]),
+    ///     selection.transform(&ops)
+    /// );
+    /// ```
+    pub fn transform<U: LengthUnit>(&self, operation: &TextOperation<U>) -> Selection {
+        return self.transform_with(operation, true);
+    }
+
+    /// 将选择中的全部选区通过一个操作进行转换，参见 [`SelectionRange::transform_with`]
+    pub fn transform_with<U: LengthUnit>(
+        &self,
+        operation: &TextOperation<U>,
+        is_own_op: bool,
+    ) -> Selection {
+        return Selection::new(
+            self.ranges
+                .iter()
+                .map(|r| r.transform_with(operation, is_own_op))
+                .collect(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Selection, SelectionRange};
+    use crate::core::TextOperation;
+
+    #[test]
+    fn test_transform_index_gravity() {
+        // 远端在光标处插入，光标保持不动；本地在光标处插入，光标跟随
+        let mut ops = TextOperation::new();
+        ops.retain(3).insert("abc").retain(3);
+        assert_eq!(3, ops.transform_index(3, false));
+        assert_eq!(6, ops.transform_index(3, true));
+        assert_eq!(7, ops.transform_index(4, false));
+        assert_eq!(9, ops.transform_index(6, false));
+        // 在文本末尾插入
+        let mut ops = TextOperation::new();
+        ops.retain(3).insert("abc");
+        assert_eq!(3, ops.transform_index(3, false));
+        assert_eq!(6, ops.transform_index(3, true));
+    }
+
+    #[test]
+    fn test_transform_index_delete() {
+        let mut ops = TextOperation::new();
+        ops.retain(2).delete(3).retain(2);
+        let expected = [0, 1, 2, 2, 2, 2, 3, 4];
+        for (index, &new_index) in expected.iter().enumerate() {
+            assert_eq!(new_index, ops.transform_index(index, false));
+            assert_eq!(new_index, ops.transform_index(index, true));
+        }
+    }
+
+    #[test]
+    fn test_selection_transform() {
+        let mut ops = TextOperation::new();
+        ops.retain(2).delete(3).insert("xy").retain(2);
+        // 选区被删除的部分收缩
+        assert_eq!(
+            SelectionRange::new(1, 5),
+            SelectionRange::new(1, 6).transform_with(&ops, false)
+        );
+        // 反向的选区保持方向
+        assert_eq!(
+            SelectionRange::new(5, 1),
+            SelectionRange::new(6, 1).transform_with(&ops, false)
+        );
+        let selection = Selection::new(vec![SelectionRange::new(0, 1), SelectionRange::cursor(3)]);
+        assert!(selection.something_selected());
+        let transformed = selection.transform(&ops);
+        assert_eq!(
+            Selection::new(vec![SelectionRange::new(0, 1), SelectionRange::cursor(4)]),
+            transformed
+        );
+        assert!(!Selection::cursor(1).something_selected());
+    }
+}
@@ -0,0 +1,1122 @@
+//! 通用的序列 OT：retain/insert/delete 的代数（规范化、apply、invert、compose、transform）与元素类型无关。
+//!
+//! [`Operation<M>`] 是其通用实现，由 [`SequenceUnit`] `M` 决定插入内容的类型以及长度的计算方式：
+//! - [`TextOperation<U>`](super::TextOperation) 即 `Operation<U>`，插入内容为 `String`，长度以 [`LengthUnit`] `U` 计算
+//! - [`SequenceOperation<T>`] 即 `Operation<Items<T>>`，插入内容为 `Vec<T>`，长度以元素个数计算
+//!
+//! 删除的原子操作记录的内容由 [`Deletion`] `D` 决定：默认的 `usize` 只记录被删除的长度，
+//! [`ReversibleOperation<U>`](super::ReversibleOperation) 即 `Operation<U, String>`，记录被删除的文本。
+//!
+//! # Example
+//! ```
+//! use ot_rs::core::SequenceOperation;
+//! let base = vec!["buy milk", "walk dog"];
+//! let mut ops = SequenceOperation::default();
+//! ops.retain(1).insert(vec!["call mom"]).delete(1);
+//! assert_eq!(vec!["buy milk", "call mom"], ops.apply(&base).unwrap());
+//! ```
+
+use super::error::OperationError;
+use super::operation::Component;
+use super::side::Side;
+use super::unit::LengthUnit;
+use std::borrow::Borrow;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::slice;
+
+/// 序列的单位：决定插入内容的类型，以及如何计算其长度、如何切分
+pub trait SequenceUnit {
+    /// 插入内容的借用形式，如 `str`、`[T]`
+    type Slice: ?Sized + PartialEq + ToOwned<Owned = Self::Owned>;
+    /// 插入内容的类型，如 `String`、`Vec<T>`
+    type Owned: Debug + Clone + Default + Borrow<Self::Slice>;
+
+    /// 序列在该单位下的长度
+    fn len(s: &Self::Slice) -> usize;
+
+    /// 在第 `n` 个单位处将序列分为两部分
+    fn split_at(s: &Self::Slice, n: usize) -> Result<(&Self::Slice, &Self::Slice), OperationError>;
+
+    /// 将 `s` 追加到 `owned` 的末尾
+    fn extend(owned: &mut Self::Owned, s: &Self::Slice);
+}
+
+impl<U: LengthUnit> SequenceUnit for U {
+    type Slice = str;
+    type Owned = String;
+
+    fn len(s: &str) -> usize {
+        U::len(s)
+    }
+
+    fn split_at(s: &str, n: usize) -> Result<(&str, &str), OperationError> {
+        U::split_at(s, n)
+    }
+
+    fn extend(owned: &mut String, s: &str) {
+        owned.push_str(s)
+    }
+}
+
+/// 以元素个数计算长度、插入内容为 `Vec<T>` 的单位
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Items<T>(PhantomData<T>);
+
+impl<T: Debug + Clone + PartialEq> SequenceUnit for Items<T> {
+    type Slice = [T];
+    type Owned = Vec<T>;
+
+    fn len(s: &[T]) -> usize {
+        s.len()
+    }
+
+    fn split_at(s: &[T], n: usize) -> Result<(&[T], &[T]), OperationError> {
+        if n > s.len() {
+            return Err(OperationError::OperationMoreLeftString);
+        }
+        return Ok(s.split_at(n));
+    }
+
+    fn extend(owned: &mut Vec<T>, s: &[T]) {
+        owned.extend_from_slice(s)
+    }
+}
+
+/// 删除的原子操作记录的内容，需要能够像插入内容一样计算长度、切分与合并
+pub trait Deletion<M: SequenceUnit>: Debug + Clone + PartialEq {
+    /// 被删除的内容以 `M` 为单位的长度
+    fn len(&self) -> usize;
+
+    /// 被删除的内容是否为空
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 在第 `n` 个单位处将被删除的内容分为两部分
+    fn split_at(&self, n: usize) -> Result<(Self, Self), OperationError>;
+
+    /// 将 `other` 追加到末尾
+    fn extend(&mut self, other: Self);
+
+    /// 被删除的内容是否就是与之等长的序列 `s`
+    fn matches(&self, s: &M::Slice) -> bool;
+}
+
+/// 只记录被删除的长度
+impl<M: SequenceUnit> Deletion<M> for usize {
+    fn len(&self) -> usize {
+        *self
+    }
+
+    fn split_at(&self, n: usize) -> Result<(usize, usize), OperationError> {
+        if n > *self {
+            return Err(OperationError::OperationMoreLeftString);
+        }
+        return Ok((n, *self - n));
+    }
+
+    fn extend(&mut self, other: usize) {
+        *self += other
+    }
+
+    fn matches(&self, _s: &M::Slice) -> bool {
+        true
+    }
+}
+
+/// 以元素个数计算长度的序列操作，插入内容为 `Vec<T>`，适用于待办事项列表、字节数组、行记录列表等
+pub type SequenceOperation<T> = Operation<Items<T>>;
+
+/// 原子操作以 `M` 为单位的长度
+fn component_len<M: SequenceUnit, D: Deletion<M>>(op: &Component<M::Owned, D>) -> usize {
+    match op {
+        &Component::Retain(n) => n,
+        Component::Insert(s) => M::len(s.borrow()),
+        Component::Delete(d) => d.len(),
+    }
+}
+
+/// 通用的序列操作，定义了如何将一个序列转换为另一个序列的原子操作序列，全部长度以 `M` 为单位计算，
+/// 删除的原子操作记录的内容为 `D`。
+/// 一般通过别名 [`TextOperation`](super::TextOperation)、[`SequenceOperation`] 或
+/// [`ReversibleOperation`](super::ReversibleOperation) 使用
+#[derive(Debug, Clone)]
+pub struct Operation<M: SequenceUnit, D: Deletion<M> = usize> {
+    /// 原子操作
+    ops: Vec<Component<M::Owned, D>>,
+    /// Retain、 Delete 的长度
+    /// 在 apply(base) -> after 时，等于 len(base)
+    base_length: usize,
+    /// Retain、Insert 的长度
+    /// 在 apply(base) -> after 时，等于 len(after)
+    after_length: usize,
+    /// 长度单位
+    unit: PhantomData<M>,
+}
+
+impl<M: SequenceUnit, D: Deletion<M>> PartialEq for Operation<M, D>
+where
+    M::Owned: PartialEq,
+{
+    /// # Example
+    /// ```
+    /// use ot_rs::core::TextOperation;
+    /// let mut ops1 = TextOperation::new();
+    /// let mut ops2 = TextOperation::new();
+    /// assert!(ops1 == ops2);
+    /// ops1.retain(1).delete(1).retain(1).insert("d");
+    /// assert!(ops1 != ops2);
+    /// ops2.retain(1).delete(1).retain(1).insert("d");
+    /// assert!(ops1 == ops2);
+    /// ops2.insert("1");
+    /// assert!(ops1 != ops2);
+    /// ```
+    fn eq(&self, other: &Self) -> bool {
+        if self.base_length != other.base_length {
+            return false;
+        }
+        if self.after_length != other.after_length {
+            return false;
+        }
+        if self.ops.len() != other.ops.len() {
+            return false;
+        }
+        return self
+            .ops
+            .iter()
+            .zip(&other.ops)
+            .filter(|&(a, b)| *a != *b)
+            .count()
+            == 0;
+    }
+}
+
+impl<M: SequenceUnit, D: Deletion<M>> Operation<M, D> {
+    /// 构造函数，创建一个无操作的、以 `M` 为单位的操作
+    /// # Example
+    /// ```
+    /// use ot_rs::core::{TextOperation, Utf16};
+    /// let mut ops = TextOperation::<Utf16>::with_unit();
+    /// ops.retain(1).insert("😄");
+    /// assert_eq!("(1->3){retain(1).insert(\"😄\")}", ops.to_string());
+    /// ```
+    pub fn with_unit() -> Operation<M, D> {
+        return Operation {
+            ops: vec![],
+            base_length: 0,
+            after_length: 0,
+            unit: PhantomData,
+        };
+    }
+
+    /// 由原子操作序列构造一个 TextOperation，相邻的原子操作将按照 `retain`/`insert`/`delete` 的规则合并（规范化）；
+    /// 如果存在空的原子操作（如 `Retain(0)`、`Insert("")`），返回错误
+    /// # Example
+    /// ```
+    /// use ot_rs::core::{Chars, Component, OperationError, TextOperation};
+    /// let ops: TextOperation = TextOperation::from_components(vec![
+    ///     Component::Retain(1),
+    ///     Component::Retain(1),
+    ///     Component::Delete(1),
+    ///     Component::Insert("a".to_string()),
+    /// ])
+    /// .unwrap();
+    /// assert_eq!("(3->3){retain(2).insert(\"a\").delete(1)}", ops.to_string());
+    /// assert_eq!(
+    ///     OperationError::ComponentEmpty,
+    ///     TextOperation::<Chars>::from_components(vec![Component::Delete(0)]).unwrap_err()
+    /// );
+    /// ```
+    pub fn from_components<I: IntoIterator<Item = Component<M::Owned, D>>>(
+        components: I,
+    ) -> Result<Operation<M, D>, OperationError> {
+        let mut operation = Operation::with_unit();
+        for op in components {
+            if component_len::<M, D>(&op) == 0 {
+                return Err(OperationError::ComponentEmpty);
+            }
+            match op {
+                Component::Retain(n) => operation.retain(n),
+                Component::Insert(str) => operation.insert(str),
+                Component::Delete(d) => operation.push_delete(d),
+            };
+        }
+        return Ok(operation);
+    }
+
+    // === 访问器 ===
+
+    /// 返回遍历全部原子操作的迭代器
+    /// # Example
+    /// ```
+    /// use ot_rs::core::{Component, TextOperation};
+    /// let mut ops = TextOperation::new();
+    /// ops.retain(1).insert("a");
+    /// assert_eq!(
+    ///     vec![&Component::Retain(1), &Component::Insert("a".to_string())],
+    ///     ops.ops().collect::<Vec<_>>()
+    /// );
+    /// ```
+    pub fn ops(&self) -> slice::Iter<'_, Component<M::Owned, D>> {
+        self.ops.iter()
+    }
+
+    /// 该操作可以 apply 的字符串的长度（Retain、Delete 的长度之和）
+    /// # Example
+    /// ```
+    /// use ot_rs::core::TextOperation;
+    /// let mut ops = TextOperation::new();
+    /// ops.retain(1).delete(2).insert("abc");
+    /// assert_eq!(3, ops.base_length());
+    /// assert_eq!(4, ops.target_length());
+    /// ```
+    pub fn base_length(&self) -> usize {
+        self.base_length
+    }
+
+    /// 该操作 apply 后得到的字符串的长度（Retain、Insert 的长度之和）
+    pub fn target_length(&self) -> usize {
+        self.after_length
+    }
+
+    // === 3 个 操作函数（接收 `&mut self`） ===
+
+    /// 跳过给定数量的字符
+    /// ```
+    /// use ot_rs::core::TextOperation;
+    /// let mut ops = TextOperation::new();
+    /// ops.retain(1);
+    /// assert_eq!("(1->1){retain(1)}", ops.to_string());
+    /// ops.retain(1);
+    /// assert_eq!("(2->2){retain(2)}", ops.to_string());
+    ///
+    pub fn retain(&mut self, n: usize) -> &mut Operation<M, D> {
+        if n == 0 {
+            return self;
+        }
+        self.base_length += n;
+        self.after_length += n;
+
+        // R(x),R(y) -> R(x+y)
+        if let Some(Component::Retain(last_n)) = self.ops.last_mut() {
+            *last_n += n;
+        } else {
+            self.ops.push(Component::Retain(n))
+        }
+        return self;
+    }
+
+    /// 在当前位置插入一个字符串
+    /// # Example
+    /// ```
+    /// use ot_rs::core::TextOperation;
+    /// let mut ops = TextOperation::new();
+    /// ops.insert("a");
+    /// assert_eq!("(0->1){insert(\"a\")}", ops.to_string());
+    /// ops.insert("b");
+    /// // 两次连续的插入将合并
+    /// assert_eq!("(0->2){insert(\"ab\")}", ops.to_string());
+    /// ops.delete(1);
+    /// assert_eq!("(1->2){insert(\"ab\").delete(1)}", ops.to_string());
+    /// // I,D + I 将加入的 I 合并到前面的插入
+    /// ops.insert("c");
+    /// assert_eq!("(1->3){insert(\"abc\").delete(1)}", ops.to_string());
+    /// ops.retain(1).delete(1);
+    /// assert_eq!(
+    ///     "(3->4){insert(\"abc\").delete(1).retain(1).delete(1)}",
+    ///     ops.to_string()
+    /// );
+    /// // D + I 将变为 I,D
+    /// ops.insert("d");
+    /// assert_eq!(
+    ///     "(3->5){insert(\"abc\").delete(1).retain(1).insert(\"d\").delete(1)}",
+    ///     ops.to_string()
+    /// );
+    /// ```
+    pub fn insert<T: Into<M::Owned>>(&mut self, str: T) -> &mut Operation<M, D> {
+        let str = str.into();
+        if M::len(str.borrow()) == 0 {
+            return self;
+        }
+        self.after_length += M::len(str.borrow());
+        match self.ops.split_last_mut() {
+            // 合并 I(x),I(y) -> I(x+y)
+            Some((Component::Insert(last_str), _)) => M::extend(last_str, str.borrow()),
+            Some((Component::Delete(_), op_heads)) => {
+                // 始终保持 insert 在 delete 前面
+                match op_heads.last_mut() {
+                    // 合并 I(s),D(x),I(y) -> I(s+y),D(x)
+                    Some(Component::Insert(last_str)) => M::extend(last_str, str.borrow()),
+                    // D(x),I(y) -> I(y),D(x)
+                    // 参考实现没有 bug，第一步 `ops[ops.length] = ops[ops.length-1]` 相当于插入了一个元素 😂，本质上就是上面的说明
+                    // https://github.com/Operational-Transformation/ot.js/blob/e9a3a0e214dd6c001e25515274bae0842a8415f2/lib/text-operation.js#L102
+                    _ => {
+                        let last_delete = self.ops.last().unwrap().clone();
+                        *self.ops.last_mut().unwrap() = Component::Insert(str);
+                        self.ops.push(last_delete);
+                    }
+                }
+            }
+            _ => self.ops.push(Component::Insert(str)),
+        }
+        return self;
+    }
+
+    /// 删除当前位置的内容，`deleted` 为删除的原子操作记录的内容
+    pub(super) fn push_delete(&mut self, deleted: D) -> &mut Operation<M, D> {
+        if deleted.is_empty() {
+            return self;
+        }
+        self.base_length += deleted.len();
+
+        // D(x),D(y) -> D(x+y)
+        if let Some(Component::Delete(last)) = self.ops.last_mut() {
+            last.extend(deleted);
+        } else {
+            self.ops.push(Component::Delete(deleted))
+        }
+        return self;
+    }
+
+    /// 测试该操作 apply 后是否不产生影响
+    /// # Example
+    /// ```
+    /// use ot_rs::core::TextOperation;
+    /// let mut ops = TextOperation::new();
+    /// assert!(ops.is_noop());
+    /// ops.retain(10);
+    /// assert!(ops.is_noop());
+    /// ```
+    pub fn is_noop(&self) -> bool {
+        match self.ops.len() {
+            0 => true,
+            1 => matches!(self.ops.first(), Some(&Component::Retain(_))),
+            _ => false,
+        }
+    }
+
+    /// 将 base 字符串中的一个位置（例如光标）转换为该操作 apply 后的字符串中对应的位置。
+    /// 当插入恰好发生在该位置时：
+    /// - `is_own_op = true`（本地用户自己的操作）：位置移动到插入内容的后方，即光标跟随输入
+    /// - `is_own_op = false`（远端用户的操作）：位置保持在插入内容的前方
+    ///
+    /// 位置在被删除的区间内时，移动到删除的起始位置。
+    /// # Example
+    /// ```
+    /// use ot_rs::core::TextOperation;
+    /// let mut ops = TextOperation::new();
+    /// ops.retain(2).insert("xyz").delete(2).retain(2);
+    /// assert_eq!(1, ops.transform_index(1, false));
+    /// assert_eq!(2, ops.transform_index(2, false));
+    /// assert_eq!(5, ops.transform_index(2, true));
+    /// assert_eq!(5, ops.transform_index(3, false));
+    /// assert_eq!(5, ops.transform_index(4, false));
+    /// assert_eq!(6, ops.transform_index(5, false));
+    /// ```
+    pub fn transform_index(&self, index: usize, is_own_op: bool) -> usize {
+        let mut new_index = index;
+        let mut cursor = 0usize; // 在 base 中的游标
+        for op in &self.ops {
+            if cursor > index {
+                break;
+            }
+            match op {
+                &Component::Retain(n) => cursor += n,
+                Component::Insert(str) => {
+                    if cursor < index || is_own_op {
+                        new_index += M::len(str.borrow());
+                    }
+                }
+                Component::Delete(d) => {
+                    new_index -= d.len().min(index - cursor);
+                    cursor += d.len();
+                }
+            }
+        }
+        return new_index;
+    }
+
+    /// 将操作应用到 base 序列上，返回一个新序列；被删除的内容与 base 中对应的内容不一致时返回 `DeletedTextMismatch`
+    pub(super) fn apply_slice(&self, base: &M::Slice) -> Result<M::Owned, OperationError> {
+        if M::len(base) != self.base_length {
+            return Err(OperationError::OperationApplyStringNotCompatible);
+        }
+
+        let mut rest = base; // base 中游标之后的部分
+        let mut buffer = M::Owned::default();
+        for op in &self.ops {
+            match op {
+                &Component::Retain(n) => {
+                    // 将 base 游标之后的 n 个单位拷贝到 buffer 中
+                    let (retained, tail) = M::split_at(rest, n)?;
+                    M::extend(&mut buffer, retained);
+                    rest = tail; // 游标移动
+                }
+                Component::Insert(v) => M::extend(&mut buffer, v.borrow()),
+                Component::Delete(d) => {
+                    // 跳过被删除的部分
+                    let (deleted, tail) = M::split_at(rest, d.len())?;
+                    if !d.matches(deleted) {
+                        return Err(OperationError::DeletedTextMismatch);
+                    }
+                    rest = tail;
+                }
+            }
+        }
+        return Ok(buffer);
+    }
+
+    /// 合并连续的两个 文本操作，满足 `apply(apply(S, A), B) = apply(S, compose(A, B))`；
+    /// 第二个操作删除第一个操作插入的内容时，被删除的内容必须与插入的内容一致，否则返回 `DeletedTextMismatch`
+    /// # Example
+    /// ```
+    /// use ot_rs::core::TextOperation;
+    /// let base = "abc";
+    /// let mut ops1 = TextOperation::new();
+    /// ops1.retain(1).insert("123").delete(1).retain(1);
+    /// let after1 = ops1.apply(base).unwrap();
+    /// assert_eq!("a123c", after1);
+    ///
+    /// let mut ops2 = TextOperation::new();
+    /// ops2.retain(2)
+    ///     .insert("$$$")
+    ///     .delete(1)
+    ///     .retain(1)
+    ///     .insert("###")
+    ///     .retain(1);
+    /// let after2 = ops2.apply(&after1).unwrap();
+    ///
+    /// assert_eq!("a1$$$3###c", after2);
+    /// let compose_ops = ops1.compose(&ops2).unwrap();
+    /// assert_eq!(after2, compose_ops.apply(base).unwrap());
+    /// ```
+    pub fn compose(&self, operation2: &Operation<M, D>) -> Result<Operation<M, D>, OperationError> {
+        if self.after_length != operation2.base_length {
+            return Err(OperationError::SecondBaseLengthNotEqualFirstAfterLength);
+        }
+
+        let mut ops1 = self.ops.split_first();
+        let mut ops2 = operation2.ops.split_first();
+        let mut tmp: Box<Component<M::Owned, D>>; // 修复 rust 生命周期检测
+
+        let mut composed = Operation::with_unit();
+        // 思路大概是：
+        // 设置两个游标，同时遍历 ops1，ops2；
+        // 每一轮迭代，都相当于重新调用了 compose，是一个递归过程；
+        // 定义递归函数 compose(ops1, ops2, ops3) 将 ops1、ops2 合并成 ops3
+        // 因此我们只需按照递归的思路，思考初始的状态的9种组合即可
+        // 1. compose([R(x), ..ops1], [R(y), ..ops2], [])
+        //          x == y: compose(ops1, ops2, [R(x)])
+        //          x > y : compose([R(x-y), ..ops1], ops2, [R(y)])
+        //          x < y : compose(ops1, [R(y-x), ..ops2], [R(y)])
+        // 2. compose([R(x), ..ops1], [I(y), ..ops2], [])
+        //                : compose([R(x), ..ops1], ops2, [I(y)])
+        // 3. compose([R(x), ..ops1], [D(y), ..ops2], [])
+        //          y < x : compose([R(x-y), ..ops1], ops2, [D(y)])
+        //           y = x: compose(ops1, ops2, [D(y)])
+        //          y > x : compose(ops1, [D(y-x) ,..ops2], [D(x)])
+        // 4. compose([I(x), ..ops1], [R(y), ..ops2], [])
+        //          ...
+        // 在此就不全部枚举了，本质上就 op1、op2 将范围大的那个拆一部分出来，然后继续递归
+        // 将以上全部枚举出来后，进行剪枝，并转化为迭代的形式，就可以得到如下的算法
+        loop {
+            match (ops1, ops2) {
+                // None, None
+                (None, None) => break,
+                // D, _
+                (Some((Component::Delete(d1), ops_tail1)), _) => {
+                    composed.push_delete(d1.clone());
+                    ops1 = ops_tail1.split_first();
+                    continue;
+                }
+                // _, I
+                (_, Some((Component::Insert(s), ops_tail))) => {
+                    composed.insert(s.clone());
+                    ops2 = ops_tail.split_first();
+                    continue;
+                }
+                // None, _
+                (None, _) => return Err(OperationError::ComposeFirstTooShort),
+                // _, None
+                (_, None) => return Err(OperationError::ComposeFirstTooLong),
+                (
+                    Some((&Component::Retain(n1), ops_tail1)),
+                    Some((&Component::Retain(n2), ops_tail2)),
+                ) => {
+                    if n1 > n2 {
+                        composed.retain(n2);
+                        tmp = Box::new(Component::Retain(n1 - n2));
+                        ops1 = Some((&tmp, ops_tail1));
+                        ops2 = ops_tail2.split_first();
+                    } else if n1 == n2 {
+                        composed.retain(n1);
+                        ops1 = ops_tail1.split_first();
+                        ops2 = ops_tail2.split_first();
+                    } else {
+                        composed.retain(n1);
+                        tmp = Box::new(Component::Retain(n2 - n1));
+                        ops2 = Some((&tmp, ops_tail2));
+                        ops1 = ops_tail1.split_first();
+                    }
+                }
+                // I, D
+                // 插入后又被删除，两者相互抵消
+                (
+                    Some((Component::Insert(s1), ops_tail1)),
+                    Some((Component::Delete(d2), ops_tail2)),
+                ) => {
+                    let (l1, n2) = (M::len(s1.borrow()), d2.len());
+                    if l1 > n2 {
+                        let (head, tail) = M::split_at(s1.borrow(), n2)?;
+                        if !d2.matches(head) {
+                            return Err(OperationError::DeletedTextMismatch);
+                        }
+                        tmp = Box::new(Component::Insert(tail.to_owned()));
+                        ops1 = Some((&tmp, ops_tail1));
+                        ops2 = ops_tail2.split_first();
+                    } else if l1 == n2 {
+                        if !d2.matches(s1.borrow()) {
+                            return Err(OperationError::DeletedTextMismatch);
+                        }
+                        ops1 = ops_tail1.split_first();
+                        ops2 = ops_tail2.split_first();
+                    } else {
+                        let (head, tail) = d2.split_at(l1)?;
+                        if !head.matches(s1.borrow()) {
+                            return Err(OperationError::DeletedTextMismatch);
+                        }
+                        tmp = Box::new(Component::Delete(tail));
+                        ops1 = ops_tail1.split_first();
+                        ops2 = Some((&tmp, ops_tail2));
+                    }
+                }
+                // I,R
+                (
+                    Some((Component::Insert(s1), ops_tail1)),
+                    Some((&Component::Retain(n2), ops_tail2)),
+                ) => {
+                    let l1 = M::len(s1.borrow());
+                    if l1 > n2 {
+                        let (head, tail) = M::split_at(s1.borrow(), n2)?;
+                        composed.insert(head.to_owned());
+                        tmp = Box::new(Component::Insert(tail.to_owned()));
+                        ops1 = Some((&tmp, ops_tail1));
+                        ops2 = ops_tail2.split_first();
+                    } else if l1 == n2 {
+                        composed.insert(s1.clone());
+                        ops1 = ops_tail1.split_first();
+                        ops2 = ops_tail2.split_first();
+                    } else {
+                        composed.insert(s1.clone());
+                        tmp = Box::new(Component::Retain(n2 - l1));
+                        ops2 = Some((&tmp, ops_tail2));
+                        ops1 = ops_tail1.split_first();
+                    }
+                }
+                // R,D
+                (
+                    Some((&Component::Retain(n1), ops_tail1)),
+                    Some((Component::Delete(d2), ops_tail2)),
+                ) => {
+                    let n2 = d2.len();
+                    if n1 > n2 {
+                        composed.push_delete(d2.clone());
+                        tmp = Box::new(Component::Retain(n1 - n2));
+                        ops1 = Some((&tmp, ops_tail1));
+                        ops2 = ops_tail2.split_first();
+                    } else if n1 == n2 {
+                        composed.push_delete(d2.clone());
+                        ops1 = ops_tail1.split_first();
+                        ops2 = ops_tail2.split_first();
+                    } else {
+                        let (head, tail) = d2.split_at(n1)?;
+                        composed.push_delete(head);
+                        tmp = Box::new(Component::Delete(tail));
+                        ops2 = Some((&tmp, ops_tail2));
+                        ops1 = ops_tail1.split_first();
+                    }
+                }
+            }
+        }
+        Ok(composed)
+    }
+
+    /// 获取起始游标
+    fn first_cursor(&self) -> usize {
+        if let Some(&Component::Retain(n)) = self.ops.first() {
+            return n;
+        }
+        return 0;
+    }
+
+    /// 如果当前操作是简单操作，则返回这个简单操作的内容，否者返回 None。
+    /// 简单操作指的是：只进行了一次或零次 Insert/Delete 操作
+    fn get_simple_operation(&self) -> Option<&Component<M::Owned, D>> {
+        match self.ops.as_slice() {
+            // [_] => [0]
+            [first] => Some(first),
+            // [R, _] => [1]
+            [Component::Retain(_), second] => Some(second),
+            // [I|D, R] => [0]
+            [first, Component::Retain(_)] => Some(first),
+            // [R, _, R] => [1]
+            [Component::Retain(_), second, Component::Retain(_)] => Some(second),
+            _ => None,
+        }
+    }
+
+    /// 当使用 ctrl-z 撤消最近的更改时，希望程序不会撤消每一次击键，而是撤消一口气写下的最后一句话或通过按住退格键所做的删除。
+    /// 这可以通过在将撤消栈上的操作进行 compose 来实现。 这个方法可以帮助决定是否应该组合两个操作。
+    /// 如果操作是 `连续的插入操作` 或 `连续的删除操作`，则返回 true。
+    /// 可能希望包括其他因素，例如自上次更改决定以来的时间，参见 [`GroupingPolicy`](crate::undo::GroupingPolicy)。
+    /// # Example
+    /// ```
+    /// use ot_rs::core::TextOperation;
+    /// let mut ops1: TextOperation;
+    /// let mut ops2: TextOperation;
+    /// // noop;I / I;noop
+    /// ops1 = TextOperation::new();
+    /// ops1.retain(3);
+    /// ops2 = TextOperation::new();
+    /// ops2.retain(1).insert("xxx").retain(2);
+    /// assert!(ops1.should_be_composed_with(&ops2));
+    /// assert!(ops2.should_be_composed_with(&ops1));
+    /// // I;I 正常输入
+    /// ops1 = TextOperation::new();
+    /// ops1.retain(1).insert("a").retain(2);
+    /// ops2 = TextOperation::new();
+    /// ops2.retain(2).insert("b").retain(2);
+    /// assert!(ops1.should_be_composed_with(&ops2));
+    /// ops1.delete(3);
+    /// assert!(!ops1.should_be_composed_with(&ops2));
+    /// // I;I 插入后光标发生变化
+    /// ops1 = TextOperation::new();
+    /// ops1.retain(1).insert("b").retain(2);
+    /// ops2 = TextOperation::new();
+    /// ops2.retain(1).insert("a").retain(3);
+    /// assert!(!ops1.should_be_composed_with(&ops2));
+    /// // D;D 退格键方式
+    /// ops1 = TextOperation::new();
+    /// ops1.retain(4).delete(3).retain(10);
+    /// ops2 = TextOperation::new();
+    /// ops2.retain(2).delete(2).retain(10);
+    /// assert!(ops1.should_be_composed_with(&ops2));
+    /// // D;D delete键方式
+    /// ops2 = TextOperation::new();
+    /// ops2.retain(4).delete(7).retain(3);
+    /// assert!(ops1.should_be_composed_with(&ops2));
+    /// // D;D 不连续的删除
+    /// ops2 = TextOperation::new();
+    /// ops2.retain(2).delete(9).retain(3);
+    /// assert!(!ops1.should_be_composed_with(&ops2));
+    /// ```
+    pub fn should_be_composed_with(&self, other: &Operation<M, D>) -> bool {
+        // 无影响的操作，可以合并
+        if self.is_noop() || other.is_noop() {
+            return true;
+        }
+        let (a_first_cursor, b_first_cursor) = (self.first_cursor(), other.first_cursor());
+        let (a_sample, b_sample) = (self.get_simple_operation(), other.get_simple_operation());
+        // 只要一个是非简单操作，则不可以合并
+        if a_sample.is_none() || b_sample.is_none() {
+            return false;
+        }
+        match (a_sample, b_sample, a_first_cursor, b_first_cursor) {
+            // I, I - 保证后插入的在之前插入的后方进行插入
+            (Some(Component::Insert(str)), Some(Component::Insert(_)), _, _) => {
+                return M::len(str.borrow()) + a_first_cursor == b_first_cursor; // 连续输入两个字符
+            }
+            // D, D
+            (Some(Component::Delete(_)), Some(Component::Delete(d2)), _, _) => {
+                return b_first_cursor as i64 + d2.len() as i64 == a_first_cursor as i64 // 按两下退格的场景
+                    || a_first_cursor == b_first_cursor; // 按两下 delete 键的场景
+            }
+            // 其他情况
+            _ => false,
+        }
+    }
+
+    /// 决定两个操作如果被 invert 是否应该相互组合，即 `should_be_composed_with_inverted(a, b) = should_be_composed_with_inverted(b^{-1}, a^{-1})`
+    pub fn should_be_composed_with_inverted(&self, other: &Operation<M, D>) -> bool {
+        // 无影响的操作，可以合并
+        if self.is_noop() || other.is_noop() {
+            return true;
+        }
+        let (a_first_cursor, b_first_cursor) = (self.first_cursor(), other.first_cursor());
+        let (a_sample, b_sample) = (self.get_simple_operation(), other.get_simple_operation());
+        // 只要一个是非简单操作，则不可以合并
+        if a_sample.is_none() || b_sample.is_none() {
+            return false;
+        }
+        match (a_sample, b_sample, a_first_cursor, b_first_cursor) {
+            // I, I - 因为是逆，所以原操作是 Delete
+            (Some(Component::Insert(str)), Some(Component::Insert(_)), _, _) => {
+                return a_first_cursor + M::len(str.borrow()) == b_first_cursor
+                    || a_first_cursor == b_first_cursor;
+            }
+            // D, D - 因为是逆，所以原操作是 Insert
+            (Some(Component::Delete(_)), Some(Component::Delete(d2)), _, _) => {
+                return b_first_cursor + d2.len() == a_first_cursor;
+            }
+            // 其他情况
+            _ => false,
+        }
+    }
+
+    /// 这个函数是 OT 算法的核心。
+    /// 转换两个基于同一版本 S 的操作 A 和 B，返回 A' 和 B'，使其满足
+    /// `apply(apply(S, A), B') = apply(apply(S, B), A')`。
+    /// 双方在同一位置插入时，`self` 的插入在前，等价于 `transform_with_side(operation2, Side::Left)`。
+    pub fn transform(&self, operation2: &Operation<M, D>) -> Result<(Self, Self), OperationError> {
+        return self.transform_with_side(operation2, Side::Left);
+    }
+
+    /// 同 `transform`，但由 `side` 决定双方在同一位置插入时谁在前。
+    /// 满足 `a.transform_with_side(b, side) = swap(b.transform_with_side(a, side.opposite()))`，
+    /// 因此无论哪一方调用 transform，只要双方对 side 的选择一致，结果就一致。
+    /// 双方删除同一段内容时，被删除的内容必须一致，否则返回 `DeletedTextMismatch`
+    /// # Example
+    /// ```
+    /// use ot_rs::core::{Side, TextOperation};
+    /// let base = "ab";
+    /// let mut a = TextOperation::new();
+    /// a.retain(1).insert("x").retain(1);
+    /// let mut b = TextOperation::new();
+    /// b.retain(1).insert("y").retain(1);
+    ///
+    /// let (a_prime, b_prime) = a.transform_with_side(&b, Side::Left).unwrap();
+    /// assert_eq!("axyb", a.compose(&b_prime).unwrap().apply(base).unwrap());
+    /// assert_eq!("axyb", b.compose(&a_prime).unwrap().apply(base).unwrap());
+    ///
+    /// let (a_prime, b_prime) = a.transform_with_side(&b, Side::Right).unwrap();
+    /// assert_eq!("ayxb", a.compose(&b_prime).unwrap().apply(base).unwrap());
+    /// assert_eq!("ayxb", b.compose(&a_prime).unwrap().apply(base).unwrap());
+    /// ```
+    pub fn transform_with_side(
+        &self,
+        operation2: &Operation<M, D>,
+        side: Side,
+    ) -> Result<(Self, Self), OperationError> {
+        let operation1 = self;
+        if operation1.base_length != operation2.base_length {
+            return Err(OperationError::TransformBaseDifferent);
+        }
+
+        let mut tmp: Box<Component<M::Owned, D>>; // 修复 rust 生命周期检测
+        let (mut operation1prime, mut operation2prime) =
+            (Operation::with_unit(), Operation::with_unit());
+
+        let mut ops1 = self.ops.split_first();
+        let mut ops2 = operation2.ops.split_first();
+        // 和 compose 方法类似
+        // 思路大概是：
+        // 设置两个游标，同时遍历 ops1，ops2；
+        // 每一轮迭代，都要保证游标，在 S 的位置是一致的
+        // 因此我们只需按照递归的思路，思考初始的状态的9种组合即可。
+        // 全部枚举出来后，进行剪枝，就可以得到如下的算法
+        loop {
+            match (ops1, ops2) {
+                (None, None) => break,
+                // 如下两种情况：只要有一方是 Insert，这一方面方的 Prime 就跳过，量一方的 Prime 就插入
+                // 双方都是 Insert 时，由 side 决定先处理哪一方（先处理的一方的插入在前）
+                // (1 种情况) I, I 且 Right
+                (Some((Component::Insert(_), _)), Some((Component::Insert(str2), tail2)))
+                    if side == Side::Right =>
+                {
+                    operation1prime.retain(M::len(str2.borrow()));
+                    operation2prime.insert(str2.clone());
+                    ops2 = tail2.split_first();
+                }
+                // (3 种情况) I, _
+                (Some((Component::Insert(str1), tail1)), _) => {
+                    operation1prime.insert(str1.clone());
+                    operation2prime.retain(M::len(str1.borrow()));
+                    ops1 = tail1.split_first();
+                }
+                // (2 种情况) _, I
+                (_, Some((Component::Insert(str2), tail2))) => {
+                    operation1prime.retain(M::len(str2.borrow()));
+                    operation2prime.insert(str2.clone());
+                    ops2 = tail2.split_first();
+                }
+                // 异常：只要有一方完成另一方未完成，则报错
+                (None, _) => return Err(OperationError::ComposeFirstTooShort),
+                (_, None) => return Err(OperationError::ComposeFirstTooLong),
+                // (1 种情况) R, R
+                (Some((&Component::Retain(n1), tail1)), Some((&Component::Retain(n2), tail2))) => {
+                    let min_n = if n1 > n2 {
+                        tmp = Box::new(Component::Retain(n1 - n2));
+                        ops1 = Some((&tmp, tail1));
+                        ops2 = tail2.split_first();
+                        n2
+                    } else if n1 == n2 {
+                        ops1 = tail1.split_first();
+                        ops2 = tail2.split_first();
+                        n2
+                    } else {
+                        tmp = Box::new(Component::Retain(n2 - n1));
+                        ops1 = tail1.split_first();
+                        ops2 = Some((&tmp, tail2));
+                        n1
+                    };
+                    operation1prime.retain(min_n);
+                    operation2prime.retain(min_n);
+                }
+                // (1 种情况) D, D
+                // 同时删除，我们只需要将删除长的保留后面部分，删除短的直接跳过
+                (Some((Component::Delete(d1), tail1)), Some((Component::Delete(d2), tail2))) => {
+                    let (n1, n2) = (d1.len(), d2.len());
+                    if n1 > n2 {
+                        let (head, tail) = d1.split_at(n2)?;
+                        if head != *d2 {
+                            return Err(OperationError::DeletedTextMismatch);
+                        }
+                        tmp = Box::new(Component::Delete(tail));
+                        ops1 = Some((&tmp, tail1));
+                        ops2 = tail2.split_first();
+                    } else if n1 == n2 {
+                        if d1 != d2 {
+                            return Err(OperationError::DeletedTextMismatch);
+                        }
+                        ops1 = tail1.split_first();
+                        ops2 = tail2.split_first();
+                    } else {
+                        let (head, tail) = d2.split_at(n1)?;
+                        if *d1 != head {
+                            return Err(OperationError::DeletedTextMismatch);
+                        }
+                        tmp = Box::new(Component::Delete(tail));
+                        ops1 = tail1.split_first();
+                        ops2 = Some((&tmp, tail2));
+                    }
+                }
+                // 接下来两种情况是 D,R 和 R,D
+                // (1 种情况) D, R
+                (Some((Component::Delete(d1), tail1)), Some((&Component::Retain(n2), tail2))) => {
+                    let n1 = d1.len();
+                    let deleted = if n1 > n2 {
+                        let (head, tail) = d1.split_at(n2)?;
+                        tmp = Box::new(Component::Delete(tail));
+                        ops1 = Some((&tmp, tail1));
+                        ops2 = tail2.split_first();
+                        head
+                    } else if n1 == n2 {
+                        ops1 = tail1.split_first();
+                        ops2 = tail2.split_first();
+                        d1.clone()
+                    } else {
+                        let deleted = d1.clone();
+                        tmp = Box::new(Component::Retain(n2 - n1));
+                        ops1 = tail1.split_first();
+                        ops2 = Some((&tmp, tail2));
+                        deleted
+                    };
+                    operation1prime.push_delete(deleted);
+                }
+                // (1 种情况) R, D
+                (Some((&Component::Retain(n1), tail1)), Some((Component::Delete(d2), tail2))) => {
+                    let n2 = d2.len();
+                    let deleted = if n1 > n2 {
+                        let deleted = d2.clone();
+                        tmp = Box::new(Component::Retain(n1 - n2));
+                        ops1 = Some((&tmp, tail1));
+                        ops2 = tail2.split_first();
+                        deleted
+                    } else if n1 == n2 {
+                        ops1 = tail1.split_first();
+                        ops2 = tail2.split_first();
+                        d2.clone()
+                    } else {
+                        let (head, tail) = d2.split_at(n1)?;
+                        tmp = Box::new(Component::Delete(tail));
+                        ops1 = tail1.split_first();
+                        ops2 = Some((&tmp, tail2));
+                        head
+                    };
+                    operation2prime.push_delete(deleted);
+                } // _ => return Err(OperationError::TransformNotCompatible),
+            }
+        }
+        return Ok((operation1prime, operation2prime));
+    }
+}
+
+impl<M: SequenceUnit, D: Deletion<M>> Default for Operation<M, D> {
+    fn default() -> Self {
+        Self::with_unit()
+    }
+}
+
+impl<M: SequenceUnit> Operation<M> {
+    /// 删除当前位置的字符串
+    /// # Example
+    /// ```
+    /// use ot_rs::core::TextOperation;
+    /// let mut ops = TextOperation::new();
+    /// ops.delete(1);
+    /// assert_eq!("(1->0){delete(1)}", ops.to_string());
+    /// ops.delete(2);
+    /// assert_eq!("(3->0){delete(3)}", ops.to_string());
+    /// ```
+    pub fn delete(&mut self, n: usize) -> &mut Operation<M> {
+        return self.push_delete(n);
+    }
+
+    /// 生成该操作基于 base 序列的逆操作
+    pub(super) fn invert_slice(&self, base: &M::Slice) -> Result<Operation<M>, OperationError> {
+        if M::len(base) != self.base_length {
+            return Err(OperationError::OperationApplyStringNotCompatible);
+        }
+
+        let mut rest = base; // base 中游标之后的部分
+        let mut inverse = Operation::<M>::with_unit();
+        for op in &self.ops {
+            match op {
+                &Component::Retain(n) => {
+                    inverse.retain(n);
+                    rest = M::split_at(rest, n)?.1;
+                }
+                Component::Insert(str) => {
+                    inverse.delete(M::len(str.borrow()));
+                }
+                &Component::Delete(n) => {
+                    let (deleted, tail) = M::split_at(rest, n)?;
+                    inverse.insert(deleted.to_owned());
+                    rest = tail;
+                }
+            }
+        }
+        return Ok(inverse);
+    }
+}
+
+impl<T: Debug + Clone + PartialEq> SequenceOperation<T> {
+    /// 将操作应用到 base 序列上，并返回一个新序列
+    /// # Example
+    /// ```
+    /// use ot_rs::core::{OperationError, SequenceOperation};
+    /// let mut ops = SequenceOperation::default();
+    /// ops.retain(1).delete(1).retain(1).insert(vec![4]);
+    /// assert_eq!(vec![1, 3, 4], ops.apply(&[1, 2, 3]).unwrap());
+    /// assert_eq!(
+    ///     OperationError::OperationApplyStringNotCompatible,
+    ///     ops.apply(&[1]).unwrap_err()
+    /// );
+    /// ```
+    pub fn apply(&self, base: &[T]) -> Result<Vec<T>, OperationError> {
+        return self.apply_slice(base);
+    }
+
+    /// 生成该操作的逆操作，满足 `apply(apply(s, ops), ops') = s`
+    /// # Example
+    /// ```
+    /// use ot_rs::core::SequenceOperation;
+    /// let base = [1, 2, 3];
+    /// let mut ops = SequenceOperation::default();
+    /// ops.retain(1).delete(1).retain(1).insert(vec![4]);
+    /// let inverse = ops.invert(&base).unwrap();
+    /// assert_eq!(base.to_vec(), inverse.apply(&ops.apply(&base).unwrap()).unwrap());
+    /// ```
+    pub fn invert(&self, base: &[T]) -> Result<SequenceOperation<T>, OperationError> {
+        return self.invert_slice(base);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::SequenceOperation;
+    use crate::core::{Component, Side, TextOperation};
+    use rand::{self, Rng};
+
+    const RAND_TEST_COUNT: usize = 100;
+
+    fn random_items(n: usize) -> Vec<u8> {
+        let mut rng = rand::thread_rng();
+        (0..n).map(|_| rng.gen_range(0..10)).collect()
+    }
+
+    fn random_operation(base: &[u8]) -> SequenceOperation<u8> {
+        let mut ops = SequenceOperation::default();
+        let mut rng = rand::thread_rng();
+        loop {
+            let left = base.len() - ops.base_length();
+            if left == 0 {
+                break;
+            }
+            let r = rng.gen_range(0.0..1.0);
+            let l = rng.gen_range(1..=left);
+            if r < 0.2 {
+                ops.insert(random_items(l));
+            } else if r < 0.4 {
+                ops.delete(l);
+            } else {
+                ops.retain(l);
+            }
+        }
+        if rng.gen_range(0.0..1.0) < 0.3 {
+            ops.insert(random_items(10));
+        }
+        ops
+    }
+
+    #[test]
+    fn test_sequence_operation() {
+        for _ in 0..RAND_TEST_COUNT {
+            let base = random_items(30);
+            let a = random_operation(&base);
+            let after_a = a.apply(&base).unwrap();
+            assert_eq!(base, a.invert(&base).unwrap().apply(&after_a).unwrap());
+
+            let c = random_operation(&after_a);
+            let after_c = c.apply(&after_a).unwrap();
+            assert_eq!(after_c, a.compose(&c).unwrap().apply(&base).unwrap());
+
+            let b = random_operation(&base);
+            for &side in &[Side::Left, Side::Right] {
+                let (a_prime, b_prime) = a.transform_with_side(&b, side).unwrap();
+                assert_eq!(
+                    a.compose(&b_prime).unwrap().apply(&base),
+                    b.compose(&a_prime).unwrap().apply(&base)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_first_cursor() {
+        assert_eq!(0, TextOperation::new().first_cursor());
+        assert_eq!(0, TextOperation::new().delete(1).first_cursor());
+        assert_eq!(1, TextOperation::new().retain(1).first_cursor());
+        assert_eq!(0, TextOperation::new().insert("a").first_cursor());
+    }
+
+    #[test]
+    fn test_get_simple_operation() {
+        assert_eq!(None, TextOperation::new().get_simple_operation());
+        assert_eq!(
+            &Component::Delete(1),
+            TextOperation::new()
+                .delete(1)
+                .get_simple_operation()
+                .unwrap()
+        );
+        assert_eq!(
+            &Component::Retain(1),
+            TextOperation::new()
+                .retain(1)
+                .get_simple_operation()
+                .unwrap()
+        );
+        assert_eq!(
+            &Component::Insert("abc".to_string()),
+            TextOperation::new()
+                .retain(1)
+                .insert("abc")
+                .retain(1)
+                .get_simple_operation()
+                .unwrap()
+        );
+    }
+}
@@ -0,0 +1,44 @@
+/// 当两个操作在同一位置插入时，决定谁的插入在前
+///
+/// 在 P2P 等场景下，双方都会调用 `transform`，为了保证收敛，
+/// 双方必须对同一对操作选择相反的 `Side`（例如由站点 id 或客户端优先级决定，参见 [`Side::by_priority`]）。
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Side {
+    /// `self` 的插入在前（默认）
+    #[default]
+    Left,
+    /// `other` 的插入在前
+    Right,
+}
+
+impl Side {
+    /// 返回相反的一方
+    /// # Example
+    /// ```
+    /// use ot_rs::core::Side;
+    /// assert_eq!(Side::Right, Side::Left.opposite());
+    /// assert_eq!(Side::Left, Side::Right.opposite());
+    /// ```
+    pub fn opposite(self) -> Side {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+
+    /// 根据优先级（例如站点 id）决定 `self` 所在的一方：优先级小的一方的插入在前。
+    /// 只要双方的优先级不同，双方使用同一对优先级调用时，保证得到相反的结果。
+    /// # Example
+    /// ```
+    /// use ot_rs::core::Side;
+    /// assert_eq!(Side::Left, Side::by_priority(1, 2));
+    /// assert_eq!(Side::Right, Side::by_priority(2, 1));
+    /// ```
+    pub fn by_priority<T: Ord>(mine: T, theirs: T) -> Side {
+        if mine <= theirs {
+            Side::Left
+        } else {
+            Side::Right
+        }
+    }
+}
@@ -0,0 +1,19 @@
+//! 测试中共用的随机数据生成
+
+use rand::{self, Rng};
+
+/// 默认的字符集：包含换行符、中文以及在 UTF-16 中占 2 个码元的 emoji
+pub(crate) const CHARSET: [&str; 6] = ["a", "b", "\n", "中", "😄", "😂"];
+
+/// 由 [`CHARSET`] 中的 `n` 个随机字符组成的字符串
+pub(crate) fn random_string(n: usize) -> String {
+    return random_string_from(&CHARSET, n);
+}
+
+/// 由 `pieces` 中的 `n` 个随机片段组成的字符串，用于需要特定内容（例如整行、`\r\n`）的测试
+pub(crate) fn random_string_from(pieces: &[&str], n: usize) -> String {
+    let mut rng = rand::thread_rng();
+    (0..n)
+        .map(|_| pieces[rng.gen_range(0..pieces.len())])
+        .collect()
+}
@@ -0,0 +1,246 @@
+//! 长度单位：`TextOperation` 中的全部长度（`base_length`、`after_length`、`Retain(n)`、`Delete(n)`、
+//! 插入字符串的长度）都以同一种单位计算。
+//!
+//! 不同的客户端使用的单位并不相同：JavaScript 的字符串以 UTF-16 码元计算长度，
+//! 部分编辑器（以及 LSP 的 `utf-8` position encoding）以字节计算，而用户感知的"字符"是字素簇。
+//! 因此 `TextOperation` 的长度单位是一个类型参数，默认为 [`Chars`]（Rust 的 `char`，即 Unicode 标量值）。
+//!
+//! # Example
+//! ```
+//! use ot_rs::core::{TextOperation, Utf16};
+//! // 😄 在 UTF-16 中占 2 个码元
+//! let mut ops = TextOperation::<Utf16>::with_unit();
+//! ops.retain(2).insert("!");
+//! assert_eq!("😄!", ops.apply("😄").unwrap());
+//! ```
+
+use super::error::OperationError;
+use std::fmt::Debug;
+
+/// 长度单位
+pub trait LengthUnit: Debug + Clone + Copy + PartialEq + Eq + Default {
+    /// 字符串在该单位下的长度
+    fn len(s: &str) -> usize;
+
+    /// 字符串的前 `n` 个单位对应的字节偏移量。
+    /// `n` 超出字符串长度时返回 `OperationMoreLeftString`；
+    /// 第 `n` 个单位落在一个字符（或字素簇）的中间时返回 `NotOnUnitBoundary`
+    fn byte_offset(s: &str, n: usize) -> Result<usize, OperationError>;
+
+    /// 在第 `n` 个单位处将字符串分为两部分
+    /// # Example
+    /// ```
+    /// use ot_rs::core::{Chars, LengthUnit, OperationError, Utf16};
+    /// assert_eq!(Ok(("a😄", "b")), Chars::split_at("a😄b", 2));
+    /// assert_eq!(Ok(("a😄", "b")), Utf16::split_at("a😄b", 3));
+    /// assert_eq!(Err(OperationError::NotOnUnitBoundary), Utf16::split_at("a😄b", 2));
+    /// ```
+    fn split_at(s: &str, n: usize) -> Result<(&str, &str), OperationError> {
+        let offset = Self::byte_offset(s, n)?;
+        return Ok(s.split_at(offset));
+    }
+
+    /// 将字符串拆分为该单位下不可分割的最小片段（例如字符、字素簇），用于 diff 等场景
+    fn atoms(s: &str) -> Vec<&str> {
+        s.char_indices()
+            .map(|(i, c)| &s[i..i + c.len_utf8()])
+            .collect()
+    }
+}
+
+/// 以 Rust `char`（Unicode 标量值，即 UTF-32 码元）计算长度，这是默认的单位
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Chars;
+
+impl LengthUnit for Chars {
+    fn len(s: &str) -> usize {
+        s.chars().count()
+    }
+
+    fn byte_offset(s: &str, n: usize) -> Result<usize, OperationError> {
+        if n == 0 {
+            return Ok(0);
+        }
+        return match s.char_indices().nth(n - 1) {
+            Some((i, c)) => Ok(i + c.len_utf8()),
+            None => Err(OperationError::OperationMoreLeftString),
+        };
+    }
+}
+
+/// 以 UTF-16 码元计算长度，与 JavaScript 的 `String.prototype.length` 一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Utf16;
+
+impl LengthUnit for Utf16 {
+    fn len(s: &str) -> usize {
+        s.chars().map(char::len_utf16).sum()
+    }
+
+    fn byte_offset(s: &str, n: usize) -> Result<usize, OperationError> {
+        let mut units = 0;
+        for (i, c) in s.char_indices() {
+            if units == n {
+                return Ok(i);
+            }
+            units += c.len_utf16();
+            if units > n {
+                return Err(OperationError::NotOnUnitBoundary);
+            }
+        }
+        if units == n {
+            return Ok(s.len());
+        }
+        return Err(OperationError::OperationMoreLeftString);
+    }
+}
+
+/// 以字节（UTF-8 码元）计算长度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Bytes;
+
+impl LengthUnit for Bytes {
+    fn len(s: &str) -> usize {
+        s.len()
+    }
+
+    fn byte_offset(s: &str, n: usize) -> Result<usize, OperationError> {
+        if n > s.len() {
+            return Err(OperationError::OperationMoreLeftString);
+        }
+        if !s.is_char_boundary(n) {
+            return Err(OperationError::NotOnUnitBoundary);
+        }
+        return Ok(n);
+    }
+}
+
+/// 以扩展字素簇（用户感知的"字符"）计算长度（需要开启 `graphemes` feature）。
+///
+/// 注意：字素簇的边界与上下文有关，例如在 `e` 后插入一个组合字符会与 `e` 合并为一个字素簇，
+/// 此时插入后的文本长度将小于两部分长度之和。使用该单位时，应保证插入的内容不会与相邻的文本合并。
+#[cfg(feature = "graphemes")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Graphemes;
+
+#[cfg(feature = "graphemes")]
+impl LengthUnit for Graphemes {
+    fn len(s: &str) -> usize {
+        use unicode_segmentation::UnicodeSegmentation;
+        s.graphemes(true).count()
+    }
+
+    fn byte_offset(s: &str, n: usize) -> Result<usize, OperationError> {
+        use unicode_segmentation::UnicodeSegmentation;
+        if n == 0 {
+            return Ok(0);
+        }
+        return match s.grapheme_indices(true).nth(n - 1) {
+            Some((i, g)) => Ok(i + g.len()),
+            None => Err(OperationError::OperationMoreLeftString),
+        };
+    }
+
+    fn atoms(s: &str) -> Vec<&str> {
+        use unicode_segmentation::UnicodeSegmentation;
+        s.graphemes(true).collect()
+    }
+}
+
+/// 将文本中以 `From` 为单位的位置转换为以 `To` 为单位的位置。
+/// 若该位置在 `To` 单位下不是一个合法的边界（例如落在字素簇中间），返回 `NotOnUnitBoundary`
+/// # Example
+/// ```
+/// use ot_rs::core::{convert_index, Bytes, Chars, OperationError, Utf16};
+/// let text = "a😄b";
+/// assert_eq!(Ok(3), convert_index::<Chars, Utf16>(text, 2));
+/// assert_eq!(Ok(5), convert_index::<Utf16, Bytes>(text, 3));
+/// assert_eq!(Ok(2), convert_index::<Bytes, Chars>(text, 5));
+/// assert_eq!(
+///     Err(OperationError::NotOnUnitBoundary),
+///     convert_index::<Bytes, Chars>(text, 2)
+/// );
+/// ```
+pub fn convert_index<From: LengthUnit, To: LengthUnit>(
+    text: &str,
+    index: usize,
+) -> Result<usize, OperationError> {
+    let offset = From::byte_offset(text, index)?;
+    let converted = To::len(&text[..offset]);
+    if To::byte_offset(text, converted)? != offset {
+        return Err(OperationError::NotOnUnitBoundary);
+    }
+    return Ok(converted);
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Bytes, Chars, LengthUnit, Utf16};
+    use crate::core::OperationError;
+
+    #[test]
+    fn test_len() {
+        let s = "a中😄";
+        assert_eq!(3, Chars::len(s));
+        assert_eq!(4, Utf16::len(s));
+        assert_eq!(8, Bytes::len(s));
+    }
+
+    #[test]
+    fn test_byte_offset() {
+        let s = "a中😄";
+        let expected = [
+            Ok(0),
+            Ok(1),
+            Ok(4),
+            Ok(8),
+            Err(OperationError::OperationMoreLeftString),
+        ];
+        for (n, offset) in expected.iter().enumerate() {
+            assert_eq!(offset, &Chars::byte_offset(s, n));
+        }
+        let expected = [
+            Ok(0),
+            Ok(1),
+            Ok(4),
+            Err(OperationError::NotOnUnitBoundary),
+            Ok(8),
+            Err(OperationError::OperationMoreLeftString),
+        ];
+        for (n, offset) in expected.iter().enumerate() {
+            assert_eq!(offset, &Utf16::byte_offset(s, n));
+        }
+        assert_eq!(Ok(4), Bytes::byte_offset(s, 4));
+        assert_eq!(
+            Err(OperationError::NotOnUnitBoundary),
+            Bytes::byte_offset(s, 3)
+        );
+        assert_eq!(
+            Err(OperationError::OperationMoreLeftString),
+            Bytes::byte_offset(s, 9)
+        );
+    }
+
+    #[cfg(feature = "graphemes")]
+    #[test]
+    fn test_graphemes() {
+        use super::Graphemes;
+        // e + 组合用重音符号，以及一个由 ZWJ 连接的 emoji 序列
+        let s = "e\u{301}👨\u{200d}👩\u{200d}👧x";
+        assert_eq!(3, Graphemes::len(s));
+        assert_eq!(Ok(3), Graphemes::byte_offset(s, 1));
+        assert_eq!(
+            Ok(("e\u{301}", "👨\u{200d}👩\u{200d}👧x")),
+            Graphemes::split_at(s, 1)
+        );
+        assert_eq!(
+            Err(OperationError::NotOnUnitBoundary),
+            crate::core::convert_index::<super::Chars, Graphemes>(s, 1)
+        );
+        assert_eq!(
+            Ok(2),
+            crate::core::convert_index::<Graphemes, super::Chars>(s, 1)
+        );
+    }
+}
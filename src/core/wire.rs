@@ -0,0 +1,195 @@
+//! 以 [ot.js](https://github.com/Operational-Transformation/ot.js/blob/master/lib/text-operation.js) 的 JSON 格式
+//! 序列化/反序列化 `TextOperation`（需要开启 `serde` feature）。
+//!
+//! 一个 `TextOperation` 被表示为一个数组，数组中的每一项对应一个原子操作：
+//! - 正整数：`Retain(n)`
+//! - 字符串：`Insert(str)`
+//! - 负整数：`Delete(-n)`
+//!
+//! 注意：ot.js 以 UTF-16 码元计算长度，与 ot.js 交换数据时应使用 `TextOperation<Utf16>`。
+//!
+//! 反序列化失败时，[`OperationError`] 经由 `serde::de::Error::custom` 转换为反序列化器的错误类型，
+//! 调用方无法从中取回 `OperationError` 的变体，只能得到以其 `Display` 开头的错误信息
+//! （`RichTextOperation::from_value`、`Json0Operation::from_value` 则直接返回 `OperationError`）。
+//!
+//! # Example
+//! ```
+//! use ot_rs::core::{OperationError, TextOperation};
+//! let mut ops = TextOperation::new();
+//! ops.retain(1).delete(1).retain(1).insert("d");
+//! let json = serde_json::to_string(&ops).unwrap();
+//! assert_eq!(r#"[1,-1,1,"d"]"#, json);
+//! assert_eq!(ops, serde_json::from_str::<TextOperation>(&json).unwrap());
+//! // 空的原子操作将被拒绝，错误信息以 OperationError 的 Display 开头
+//! let err = serde_json::from_str::<TextOperation>("[1,0]").unwrap_err();
+//! assert!(err.to_string().starts_with(&OperationError::ComponentEmpty.to_string()));
+//! ```
+
+use super::error::OperationError;
+use super::operation::Component;
+use super::text::TextOperation;
+use super::unit::LengthUnit;
+use serde::de::{self, SeqAccess, Visitor};
+use serde::ser::SerializeSeq;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::convert::TryFrom;
+use std::fmt;
+use std::marker::PhantomData;
+
+impl Serialize for Component {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            &Self::Retain(n) => serializer.serialize_u64(n as u64),
+            Self::Insert(str) => serializer.serialize_str(str),
+            &Self::Delete(n) => serializer.serialize_i64(-(n as i64)),
+        }
+    }
+}
+
+struct ComponentVisitor;
+
+impl ComponentVisitor {
+    fn length<E: de::Error, T>(n: T) -> Result<usize, E>
+    where
+        usize: TryFrom<T>,
+    {
+        let n =
+            usize::try_from(n).map_err(|_| E::custom(OperationError::ComponentLengthOverflow))?;
+        if n == 0 {
+            return Err(E::custom(OperationError::ComponentEmpty));
+        }
+        return Ok(n);
+    }
+}
+
+impl<'de> Visitor<'de> for ComponentVisitor {
+    type Value = Component;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a non-zero integer or a non-empty string")
+    }
+
+    fn visit_u64<E: de::Error>(self, n: u64) -> Result<Component, E> {
+        return Ok(Component::Retain(Self::length(n)?));
+    }
+
+    fn visit_i64<E: de::Error>(self, n: i64) -> Result<Component, E> {
+        if n >= 0 {
+            return Ok(Component::Retain(Self::length(n as u64)?));
+        }
+        return Ok(Component::Delete(Self::length(n.unsigned_abs())?));
+    }
+
+    fn visit_str<E: de::Error>(self, str: &str) -> Result<Component, E> {
+        self.visit_string(str.to_string())
+    }
+
+    fn visit_string<E: de::Error>(self, str: String) -> Result<Component, E> {
+        if str.is_empty() {
+            return Err(E::custom(OperationError::ComponentEmpty));
+        }
+        return Ok(Component::Insert(str));
+    }
+}
+
+impl<'de> Deserialize<'de> for Component {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ComponentVisitor)
+    }
+}
+
+impl<U: LengthUnit> Serialize for TextOperation<U> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.ops().len()))?;
+        for op in self.ops() {
+            seq.serialize_element(op)?;
+        }
+        seq.end()
+    }
+}
+
+struct TextOperationVisitor<U>(PhantomData<U>);
+
+impl<'de, U: LengthUnit> Visitor<'de> for TextOperationVisitor<U> {
+    type Value = TextOperation<U>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an array of ot.js text operation components")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<TextOperation<U>, A::Error> {
+        let mut components = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(op) = seq.next_element::<Component>()? {
+            components.push(op);
+        }
+        TextOperation::from_components(components).map_err(de::Error::custom)
+    }
+}
+
+impl<'de, U: LengthUnit> Deserialize<'de> for TextOperation<U> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(TextOperationVisitor(PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::core::{Component, OperationError, TextOperation};
+
+    #[test]
+    fn test_component() {
+        assert_eq!("3", serde_json::to_string(&Component::Retain(3)).unwrap());
+        assert_eq!("-3", serde_json::to_string(&Component::Delete(3)).unwrap());
+        assert_eq!(
+            r#""a\"""#,
+            serde_json::to_string(&Component::Insert("a\"".to_string())).unwrap()
+        );
+        assert_eq!(
+            Component::Delete(2),
+            serde_json::from_str::<Component>("-2").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut ops = TextOperation::new();
+        ops.retain(2).insert("中文😄").delete(3).retain(1);
+        let json = serde_json::to_string(&ops).unwrap();
+        assert_eq!(r#"[2,"中文😄",-3,1]"#, json);
+        assert_eq!(ops, serde_json::from_str(&json).unwrap());
+        assert_eq!(
+            TextOperation::new(),
+            serde_json::from_str::<TextOperation>("[]").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_normalize() {
+        // 相邻的原子操作将被合并，且 D,I 将被规范化为 I,D
+        let ops: TextOperation = serde_json::from_str(r#"[1,1,-1,"a","b"]"#).unwrap();
+        assert_eq!(r#"[2,"ab",-1]"#, serde_json::to_string(&ops).unwrap());
+    }
+
+    #[test]
+    fn test_utf16() {
+        use crate::core::Utf16;
+        let ops: TextOperation<Utf16> = serde_json::from_str(r#"[2,"a",-1]"#).unwrap();
+        assert_eq!("😄a", ops.apply("😄b").unwrap());
+    }
+
+    #[test]
+    fn test_invalid() {
+        let err = serde_json::from_str::<TextOperation>("[1,0]").unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with(&OperationError::ComponentEmpty.to_string()));
+        let err = serde_json::from_str::<TextOperation>(r#"[""]"#).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with(&OperationError::ComponentEmpty.to_string()));
+        assert!(serde_json::from_str::<TextOperation>("[1.5]").is_err());
+        assert!(serde_json::from_str::<TextOperation>("[true]").is_err());
+        assert!(serde_json::from_str::<TextOperation>("{}").is_err());
+    }
+}
@@ -0,0 +1,249 @@
+use super::error::OperationError;
+use super::selection::Selection;
+use super::side::Side;
+use super::text::TextOperation;
+use super::unit::{Chars, LengthUnit};
+
+/// 附加在 [`WrappedOperation`] 上的元数据，定义了操作被 compose、transform、invert 时元数据如何变化。
+///
+/// 实现上参考了 [ot.js 的 WrappedOperation](https://github.com/Operational-Transformation/ot.js/blob/master/lib/wrapped-operation.js)，
+/// 默认实现与 ot.js 一致：invert、transform 时元数据不变，compose 时后一个元数据覆盖前一个。
+///
+/// 可以通过元组组合多个元数据，例如 `(Author, SelectionMeta)`。
+/// # Example
+/// ```
+/// use ot_rs::core::Meta;
+/// #[derive(Debug, Clone, PartialEq)]
+/// struct Author(u32);
+/// impl Meta for Author {}
+/// assert_eq!(Author(2), Author(1).compose(&Author(2)));
+/// ```
+pub trait Meta: Clone {
+    /// 操作被 invert 时的元数据
+    fn invert(&self) -> Self {
+        self.clone()
+    }
+
+    /// 操作与 `other` 对应的操作 compose 时的元数据
+    fn compose(&self, other: &Self) -> Self {
+        other.clone()
+    }
+
+    /// 操作针对 `operation` 进行 transform 时的元数据
+    fn transform<U: LengthUnit>(&self, _operation: &TextOperation<U>) -> Self {
+        self.clone()
+    }
+}
+
+impl Meta for () {}
+
+impl Meta for Selection {
+    fn compose(&self, other: &Selection) -> Selection {
+        Selection::compose(self, other)
+    }
+
+    fn transform<U: LengthUnit>(&self, operation: &TextOperation<U>) -> Selection {
+        Selection::transform(self, operation)
+    }
+}
+
+impl<A: Meta, B: Meta> Meta for (A, B) {
+    fn invert(&self) -> Self {
+        (self.0.invert(), self.1.invert())
+    }
+
+    fn compose(&self, other: &Self) -> Self {
+        (self.0.compose(&other.0), self.1.compose(&other.1))
+    }
+
+    fn transform<U: LengthUnit>(&self, operation: &TextOperation<U>) -> Self {
+        (self.0.transform(operation), self.1.transform(operation))
+    }
+}
+
+/// 操作前后的选择，例如用于撤销后恢复光标位置（对应 ot.js 的 `SelfMeta`）
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct SelectionMeta {
+    /// 操作之前的选择
+    pub before: Selection,
+    /// 操作之后的选择
+    pub after: Selection,
+}
+
+impl SelectionMeta {
+    /// 构造函数
+    pub fn new(before: Selection, after: Selection) -> SelectionMeta {
+        return SelectionMeta { before, after };
+    }
+}
+
+impl Meta for SelectionMeta {
+    /// 逆操作的前后选择互换
+    fn invert(&self) -> SelectionMeta {
+        return SelectionMeta::new(self.after.clone(), self.before.clone());
+    }
+
+    /// 取前一个操作之前的选择与后一个操作之后的选择
+    fn compose(&self, other: &SelectionMeta) -> SelectionMeta {
+        return SelectionMeta::new(self.before.clone(), other.after.clone());
+    }
+
+    fn transform<U: LengthUnit>(&self, operation: &TextOperation<U>) -> SelectionMeta {
+        return SelectionMeta::new(
+            self.before.transform(operation),
+            self.after.transform(operation),
+        );
+    }
+}
+
+/// 附加了元数据（例如作者、操作前后的选择、时间戳、客户端序号）的 [`TextOperation`]。
+/// compose、transform、invert 作用于其中的操作，元数据的变化由 [`Meta`] 决定。
+///
+/// 它实现了 [`OtType`](crate::types::OtType)，因此可以直接用于 [`Client`](crate::client::Client)
+/// 与 [`Server`](crate::server::Server)，使元数据随历史操作一起保存。
+/// # Example
+/// ```
+/// use ot_rs::core::{Selection, SelectionMeta, TextOperation, WrappedOperation};
+/// let mut ops = TextOperation::new();
+/// ops.retain(2).insert("c");
+/// let a = WrappedOperation::new(
+///     ops,
+///     SelectionMeta::new(Selection::cursor(2), Selection::cursor(3)),
+/// );
+/// let inverse = a.invert("ab").unwrap();
+/// assert_eq!("ab", inverse.apply("abc").unwrap());
+/// assert_eq!(Selection::cursor(3), inverse.meta.before);
+///
+/// let mut ops = TextOperation::new();
+/// ops.insert("x").retain(2);
+/// let b = WrappedOperation::new(ops, SelectionMeta::default());
+/// let (a_prime, _) = a.transform(&b).unwrap();
+/// assert_eq!("xabc", a_prime.apply("xab").unwrap());
+/// assert_eq!(Selection::cursor(4), a_prime.meta.after);
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct WrappedOperation<M, U: LengthUnit = Chars> {
+    /// 被包装的操作
+    pub operation: TextOperation<U>,
+    /// 元数据
+    pub meta: M,
+}
+
+impl<M: Meta, U: LengthUnit> WrappedOperation<M, U> {
+    /// 构造函数
+    pub fn new(operation: TextOperation<U>, meta: M) -> WrappedOperation<M, U> {
+        return WrappedOperation { operation, meta };
+    }
+
+    /// 是否为空操作，参见 [`TextOperation::is_noop`]
+    pub fn is_noop(&self) -> bool {
+        self.operation.is_noop()
+    }
+
+    /// 将操作应用到字符串上，参见 [`TextOperation::apply`]
+    pub fn apply<T: Into<String>>(&self, base: T) -> Result<String, OperationError> {
+        self.operation.apply(base)
+    }
+
+    /// 基于应用前的字符串 `base` 求逆，元数据通过 [`Meta::invert`] 变化
+    pub fn invert<T: Into<String>>(
+        &self,
+        base: T,
+    ) -> Result<WrappedOperation<M, U>, OperationError> {
+        return Ok(WrappedOperation::new(
+            self.operation.invert(base)?,
+            self.meta.invert(),
+        ));
+    }
+
+    /// 组合两个操作，元数据通过 [`Meta::compose`] 组合
+    pub fn compose(&self, other: &Self) -> Result<WrappedOperation<M, U>, OperationError> {
+        return Ok(WrappedOperation::new(
+            self.operation.compose(&other.operation)?,
+            self.meta.compose(&other.meta),
+        ));
+    }
+
+    /// 转换两个操作，每一方的元数据通过 [`Meta::transform`] 针对另一方的（转换前的）操作进行转换。
+    /// 等价于 `transform_with_side(other, Side::Left)`
+    pub fn transform(&self, other: &Self) -> Result<(Self, Self), OperationError> {
+        return self.transform_with_side(other, Side::Left);
+    }
+
+    /// 同 `transform`，参见 [`TextOperation::transform_with_side`]
+    pub fn transform_with_side(
+        &self,
+        other: &Self,
+        side: Side,
+    ) -> Result<(Self, Self), OperationError> {
+        let (a_prime, b_prime) = self.operation.transform_with_side(&other.operation, side)?;
+        return Ok((
+            WrappedOperation::new(a_prime, self.meta.transform(&other.operation)),
+            WrappedOperation::new(b_prime, other.meta.transform(&self.operation)),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{SelectionMeta, WrappedOperation};
+    use crate::core::test_util::random_string;
+    use crate::core::{Selection, SelectionRange, TextOperation};
+    use rand::{self, Rng};
+
+    fn random_operation(rng: &mut impl Rng, base: &str) -> TextOperation {
+        let mut target: String = base.chars().filter(|_| rng.gen_bool(0.7)).collect();
+        target.push_str(&random_string(3));
+        return TextOperation::diff(base, &target);
+    }
+
+    fn random_meta(rng: &mut impl Rng, before: usize, after: usize) -> SelectionMeta {
+        let x = rng.gen_range(0..=before);
+        let y = rng.gen_range(0..=after);
+        return SelectionMeta::new(
+            Selection::new(vec![SelectionRange::new(x, rng.gen_range(0..=before))]),
+            Selection::cursor(y),
+        );
+    }
+
+    #[test]
+    fn test_random() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..500 {
+            let base = random_string(10);
+            let a = random_operation(&mut rng, &base);
+            let a_target = a.apply(base.as_str()).unwrap();
+            let b = random_operation(&mut rng, &a_target);
+            let c = random_operation(&mut rng, &base);
+            let wa = WrappedOperation::new(a.clone(), random_meta(&mut rng, 10, a.target_length()));
+            let wb = WrappedOperation::new(
+                b.clone(),
+                random_meta(&mut rng, b.base_length(), b.target_length()),
+            );
+            let wc = WrappedOperation::new(c.clone(), random_meta(&mut rng, 10, c.target_length()));
+
+            // 操作部分与 TextOperation 一致
+            let composed = wa.compose(&wb).unwrap();
+            assert_eq!(a.compose(&b).unwrap(), composed.operation);
+            assert_eq!(wa.meta.before, composed.meta.before);
+            assert_eq!(wb.meta.after, composed.meta.after);
+
+            let inverted = wa.invert(base.as_str()).unwrap();
+            assert_eq!(a.invert(base.as_str()).unwrap(), inverted.operation);
+            assert_eq!(wa.meta.after, inverted.meta.before);
+            assert_eq!(wa.meta.before, inverted.meta.after);
+
+            let (wa_prime, wc_prime) = wa.transform(&wc).unwrap();
+            let (a_prime, c_prime) = a.transform(&c).unwrap();
+            assert_eq!(a_prime, wa_prime.operation);
+            assert_eq!(c_prime, wc_prime.operation);
+            assert_eq!(wa.meta.after.transform(&c), wa_prime.meta.after);
+            assert_eq!(wc.meta.before.transform(&a), wc_prime.meta.before);
+            assert_eq!(
+                wa.compose(&wc_prime).unwrap().apply(base.as_str()).unwrap(),
+                wc.compose(&wa_prime).unwrap().apply(base.as_str()).unwrap()
+            );
+        }
+    }
+}
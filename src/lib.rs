@@ -1 +1,9 @@
+#![allow(clippy::needless_return)]
+
+pub mod client;
 pub mod core;
+pub mod lsp;
+pub mod presence;
+pub mod server;
+pub mod types;
+pub mod undo;
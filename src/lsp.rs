@@ -0,0 +1,427 @@
+//! # LSP（Language Server Protocol）互操作
+//!
+//! 将 LSP 的 `TextEdit` 列表与 [`TextOperation`] 相互转换，并按照协商的 position encoding
+//! （`utf-8`、`utf-16`、`utf-32`）处理行列位置。
+//!
+//! 与 LSP 规范一致，`\n`、`\r\n`、`\r` 均视为换行符；`character` 超出行长度时按行尾处理，
+//! `line` 超出行数时按文档末尾处理。
+//!
+//! # Example
+//! ```
+//! use ot_rs::core::TextOperation;
+//! use ot_rs::lsp::{Position, PositionEncoding, Range, TextEdit};
+//! let base = "fn main() {\r\n    let 😄 = 1;\r\n}\r\n";
+//! let edits = vec![TextEdit::new(
+//!     Range::new(Position::new(1, 8), Position::new(1, 10)),
+//!     "x",
+//! )];
+//! let ops: TextOperation =
+//!     TextOperation::from_lsp_edits(base, &edits, PositionEncoding::Utf16).unwrap();
+//! assert_eq!("fn main() {\r\n    let x = 1;\r\n}\r\n", ops.apply(base).unwrap());
+//! assert_eq!(edits, ops.to_lsp_edits(base, PositionEncoding::Utf16).unwrap());
+//! ```
+
+use crate::core::{Bytes, Chars, LengthUnit, OperationError, TextOperation, Utf16};
+
+/// 位置中 `character` 的计算单位，由客户端与服务端协商（`general.positionEncodings`）
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum PositionEncoding {
+    /// `utf-8`：以字节计算
+    Utf8,
+    /// `utf-16`：以 UTF-16 码元计算，LSP 的默认值
+    #[default]
+    Utf16,
+    /// `utf-32`：以 Unicode 码点计算
+    Utf32,
+}
+
+impl PositionEncoding {
+    /// LSP 中的名称
+    /// # Example
+    /// ```
+    /// use ot_rs::lsp::PositionEncoding;
+    /// assert_eq!("utf-16", PositionEncoding::Utf16.as_str());
+    /// assert_eq!(Some(PositionEncoding::Utf8), PositionEncoding::from_name("utf-8"));
+    /// assert_eq!(None, PositionEncoding::from_name("utf-7"));
+    /// ```
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PositionEncoding::Utf8 => "utf-8",
+            PositionEncoding::Utf16 => "utf-16",
+            PositionEncoding::Utf32 => "utf-32",
+        }
+    }
+
+    /// 由 LSP 中的名称解析
+    pub fn from_name(name: &str) -> Option<PositionEncoding> {
+        match name {
+            "utf-8" => Some(PositionEncoding::Utf8),
+            "utf-16" => Some(PositionEncoding::Utf16),
+            "utf-32" => Some(PositionEncoding::Utf32),
+            _ => None,
+        }
+    }
+
+    fn len(self, s: &str) -> usize {
+        match self {
+            PositionEncoding::Utf8 => Bytes::len(s),
+            PositionEncoding::Utf16 => Utf16::len(s),
+            PositionEncoding::Utf32 => Chars::len(s),
+        }
+    }
+
+    fn byte_offset(self, s: &str, n: usize) -> Result<usize, OperationError> {
+        match self {
+            PositionEncoding::Utf8 => Bytes::byte_offset(s, n),
+            PositionEncoding::Utf16 => Utf16::byte_offset(s, n),
+            PositionEncoding::Utf32 => Chars::byte_offset(s, n),
+        }
+    }
+}
+
+/// 文档中的位置：从 0 开始的行号，以及行内从 0 开始的偏移量（单位由 [`PositionEncoding`] 决定）
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, PartialOrd, Ord)]
+pub struct Position {
+    pub line: u32,
+    pub character: u32,
+}
+
+impl Position {
+    /// 构造函数
+    pub fn new(line: u32, character: u32) -> Position {
+        return Position { line, character };
+    }
+}
+
+/// 文档中的区间，不包含 `end`
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Range {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Range {
+    /// 构造函数
+    pub fn new(start: Position, end: Position) -> Range {
+        return Range { start, end };
+    }
+}
+
+/// 将文档中 `range` 区间的内容替换为 `new_text`
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct TextEdit {
+    pub range: Range,
+    pub new_text: String,
+}
+
+impl TextEdit {
+    /// 构造函数
+    pub fn new<T: Into<String>>(range: Range, new_text: T) -> TextEdit {
+        return TextEdit {
+            range,
+            new_text: new_text.into(),
+        };
+    }
+}
+
+/// 文本的行索引，用于在字节偏移量与行列位置之间转换
+/// # Example
+/// ```
+/// use ot_rs::lsp::{LineIndex, Position, PositionEncoding};
+/// let index = LineIndex::new("a\r\n😄b\rc");
+/// assert_eq!(3, index.line_count());
+/// assert_eq!(Ok(7), index.offset(Position::new(1, 2), PositionEncoding::Utf16));
+/// assert_eq!(Ok(Position::new(1, 1)), index.position(7, PositionEncoding::Utf32));
+/// // character 超出行长度时按行尾处理
+/// assert_eq!(Ok(1), index.offset(Position::new(0, 5), PositionEncoding::Utf8));
+/// ```
+#[derive(Debug, Clone)]
+pub struct LineIndex<'a> {
+    text: &'a str,
+    /// 每一行起始位置的字节偏移量
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    /// 构造函数
+    pub fn new(text: &'a str) -> LineIndex<'a> {
+        let bytes = text.as_bytes();
+        let mut line_starts = vec![0];
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\r' if bytes.get(i + 1) == Some(&b'\n') => {
+                    i += 2;
+                    line_starts.push(i);
+                }
+                b'\r' | b'\n' => {
+                    i += 1;
+                    line_starts.push(i);
+                }
+                _ => i += 1,
+            }
+        }
+        return LineIndex { text, line_starts };
+    }
+
+    /// 行数（以换行符结尾的文本，最后一行为空行）
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// 第 `line` 行（不包括换行符）的字节区间
+    fn line_range(&self, line: usize) -> std::ops::Range<usize> {
+        let start = self.line_starts[line];
+        let end = match self.line_starts.get(line + 1) {
+            Some(&next) => {
+                let line_text = &self.text[start..next];
+                next - (line_text.len() - line_text.trim_end_matches(&['\r', '\n'][..]).len())
+            }
+            None => self.text.len(),
+        };
+        return start..end;
+    }
+
+    /// 将位置转换为字节偏移量。位置落在一个字符的中间（例如 UTF-16 代理对之间）时返回 `NotOnUnitBoundary`
+    pub fn offset(
+        &self,
+        position: Position,
+        encoding: PositionEncoding,
+    ) -> Result<usize, OperationError> {
+        let line = position.line as usize;
+        if line >= self.line_count() {
+            return Ok(self.text.len());
+        }
+        let range = self.line_range(line);
+        let line_text = &self.text[range.clone()];
+        let character = position.character as usize;
+        if character >= encoding.len(line_text) {
+            return Ok(range.end);
+        }
+        return Ok(range.start + encoding.byte_offset(line_text, character)?);
+    }
+
+    /// 将字节偏移量转换为位置。偏移量超出文本长度时返回 `OperationMoreLeftString`；
+    /// 落在一个字符的中间时返回 `NotOnUnitBoundary`，落在 `\r\n` 的中间时返回 `InsideLineBreak`
+    pub fn position(
+        &self,
+        offset: usize,
+        encoding: PositionEncoding,
+    ) -> Result<Position, OperationError> {
+        if offset > self.text.len() {
+            return Err(OperationError::OperationMoreLeftString);
+        }
+        if !self.text.is_char_boundary(offset) {
+            return Err(OperationError::NotOnUnitBoundary);
+        }
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let range = self.line_range(line);
+        if offset > range.end {
+            return Err(OperationError::InsideLineBreak);
+        }
+        let character = encoding.len(&self.text[range.start..offset]);
+        return Ok(Position::new(line as u32, character as u32));
+    }
+}
+
+/// 将字节偏移量转换为以 `U` 为单位的偏移量
+fn unit_offset<U: LengthUnit>(text: &str, offset: usize) -> Result<usize, OperationError> {
+    let n = U::len(&text[..offset]);
+    if U::byte_offset(text, n)? != offset {
+        return Err(OperationError::NotOnUnitBoundary);
+    }
+    return Ok(n);
+}
+
+impl<U: LengthUnit> TextOperation<U> {
+    /// 由一组 LSP `TextEdit` 构造作用于 `base` 的操作。
+    /// 与 LSP 规范一致，全部编辑的区间都基于原始文档，不能相互重叠；
+    /// 同一位置的多个插入按照在列表中的顺序插入
+    pub fn from_lsp_edits(
+        base: &str,
+        edits: &[TextEdit],
+        encoding: PositionEncoding,
+    ) -> Result<TextOperation<U>, OperationError> {
+        let index = LineIndex::new(base);
+        let mut ranges = Vec::with_capacity(edits.len());
+        for edit in edits {
+            let start = index.offset(edit.range.start, encoding)?;
+            let end = index.offset(edit.range.end, encoding)?;
+            let range = unit_offset::<U>(base, start)?..unit_offset::<U>(base, end)?;
+            ranges.push((range, edit.new_text.as_str()));
+        }
+        // 稳定排序，保证同一位置的插入保持原有顺序，并将其合并为一个插入
+        ranges.sort_by_key(|(range, _)| (range.start, range.end));
+        let mut merged: Vec<(std::ops::Range<usize>, String)> = Vec::with_capacity(ranges.len());
+        for (range, text) in ranges {
+            match merged.last_mut() {
+                Some((last, last_text)) if last.start == last.end && *last == range => {
+                    last_text.push_str(text)
+                }
+                _ => merged.push((range, text.to_string())),
+            }
+        }
+        return TextOperation::from_edits(U::len(base), merged);
+    }
+
+    /// 将作用于 `base` 的操作转换为一组 LSP `TextEdit`，按位置从前往后排列
+    pub fn to_lsp_edits(
+        &self,
+        base: &str,
+        encoding: PositionEncoding,
+    ) -> Result<Vec<TextEdit>, OperationError> {
+        if U::len(base) != self.base_length() {
+            return Err(OperationError::OperationApplyStringNotCompatible);
+        }
+        let index = LineIndex::new(base);
+        let mut result = vec![];
+        for (range, text) in self.edits() {
+            let start = index.position(U::byte_offset(base, range.start)?, encoding)?;
+            let end = index.position(U::byte_offset(base, range.end)?, encoding)?;
+            result.push(TextEdit::new(Range::new(start, end), text));
+        }
+        return Ok(result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{LineIndex, Position, PositionEncoding, Range, TextEdit};
+    use crate::core::test_util::random_string_from;
+    use crate::core::{OperationError, TextOperation, Utf16};
+    use rand::{self, Rng};
+
+    /// 包含各种换行符的片段
+    const PIECES: [&str; 7] = ["a", "\r\n", "\n", "\r", "中", "😄", "b"];
+
+    fn random_string(n: usize) -> String {
+        return random_string_from(&PIECES, n);
+    }
+
+    #[test]
+    fn test_line_index() {
+        let index = LineIndex::new("ab\r\ncd\n\re");
+        assert_eq!(4, index.line_count());
+        let enc = PositionEncoding::Utf8;
+        assert_eq!(Ok(Position::new(0, 2)), index.position(2, enc));
+        // \r\n 的中间
+        assert_eq!(Err(OperationError::InsideLineBreak), index.position(3, enc));
+        assert_eq!(Ok(Position::new(1, 0)), index.position(4, enc));
+        assert_eq!(Ok(Position::new(2, 0)), index.position(7, enc));
+        assert_eq!(Ok(Position::new(3, 1)), index.position(9, enc));
+        assert_eq!(
+            Err(OperationError::OperationMoreLeftString),
+            index.position(10, enc)
+        );
+        assert_eq!(Ok(9), index.offset(Position::new(10, 0), enc));
+
+        let index = LineIndex::new("😄");
+        assert_eq!(
+            Err(OperationError::NotOnUnitBoundary),
+            index.offset(Position::new(0, 1), PositionEncoding::Utf16)
+        );
+        assert_eq!(
+            Ok(4),
+            index.offset(Position::new(0, 1), PositionEncoding::Utf32)
+        );
+    }
+
+    /// 随机生成一个不会拆开 `\r\n` 的操作：`\r\n` 被视为一个整体，编辑只发生在其两侧
+    fn random_operation(rng: &mut impl Rng, base: &str) -> TextOperation {
+        let mut units: Vec<&str> = vec![];
+        let mut rest = base;
+        while let Some(c) = rest.chars().next() {
+            let len = if rest.starts_with("\r\n") {
+                2
+            } else {
+                c.len_utf8()
+            };
+            units.push(&rest[..len]);
+            rest = &rest[len..];
+        }
+        let mut ops = TextOperation::new();
+        for unit in units {
+            if rng.gen_bool(0.3) {
+                ops.insert(random_string(rng.gen_range(1..3)));
+            }
+            let n = unit.chars().count();
+            if rng.gen_bool(0.3) {
+                ops.delete(n);
+            } else {
+                ops.retain(n);
+            }
+        }
+        if rng.gen_bool(0.3) {
+            ops.insert(random_string(rng.gen_range(1..3)));
+        }
+        return ops;
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let encodings = [
+            PositionEncoding::Utf8,
+            PositionEncoding::Utf16,
+            PositionEncoding::Utf32,
+        ];
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let base = random_string(20);
+            let ops = random_operation(&mut rng, &base);
+            let after = ops.apply(base.as_str()).unwrap();
+            for &encoding in &encodings {
+                let edits = ops.to_lsp_edits(&base, encoding).unwrap();
+                let converted: TextOperation =
+                    TextOperation::from_lsp_edits(&base, &edits, encoding).unwrap();
+                assert_eq!(after, converted.apply(base.as_str()).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_inside_line_break() {
+        // 在 \r 与 \n 之间插入，无法表示为 LSP 的位置
+        let base = "a\r\nb";
+        let mut ops = TextOperation::new();
+        ops.retain(2).insert("x").retain(2);
+        assert_eq!(
+            Err(OperationError::InsideLineBreak),
+            ops.to_lsp_edits(base, PositionEncoding::Utf16)
+        );
+        // 删除 \n 而保留 \r
+        let mut ops = TextOperation::new();
+        ops.retain(2).delete(1).retain(1);
+        assert_eq!(
+            Err(OperationError::InsideLineBreak),
+            ops.to_lsp_edits(base, PositionEncoding::Utf8)
+        );
+    }
+
+    #[test]
+    fn test_same_position_inserts() {
+        let base = "ab";
+        let at = |line, character| {
+            Range::new(
+                Position::new(line, character),
+                Position::new(line, character),
+            )
+        };
+        let edits = vec![
+            TextEdit::new(at(0, 1), "1"),
+            TextEdit::new(Range::new(Position::new(0, 1), Position::new(0, 2)), "B"),
+            TextEdit::new(at(0, 1), "2"),
+        ];
+        let ops =
+            TextOperation::<Utf16>::from_lsp_edits(base, &edits, PositionEncoding::Utf16).unwrap();
+        assert_eq!("a12B", ops.apply(base).unwrap());
+        let edits = vec![
+            TextEdit::new(Range::new(Position::new(0, 0), Position::new(0, 2)), "x"),
+            TextEdit::new(at(0, 1), "y"),
+        ];
+        assert_eq!(
+            Err(OperationError::EditsOverlap),
+            TextOperation::<Utf16>::from_lsp_edits(base, &edits, PositionEncoding::Utf16)
+        );
+    }
+}
@@ -0,0 +1,447 @@
+//! # 协作者在线状态（光标 / 选择）
+//!
+//! 每个协作者（客户端）的在线状态包括名称、颜色与选择（[`Selection`]）。
+//! 收到的每个操作都需要通过 [`PresenceManager::transform`] 转换全部协作者的选择，
+//! 否则其他人输入后光标位置就会漂移；长时间没有更新的协作者可以通过 [`PresenceManager::expire`] 移除。
+//!
+//! 协作者之间通过 [`PresenceUpdate`] 同步在线状态，名称与颜色只需在加入或变化时发送。
+//! 开启 `serde` feature 后，它被序列化为一个紧凑的数组：`[client_id, [[anchor, head], ...], name, color]`，
+//! 选择为 `null` 表示该协作者离开，名称与颜色为 `null` 表示不变。
+//!
+//! 与 [`Client`](crate::client::Client) 一起使用时，收到的选择基于服务端的版本，
+//! 需要先通过 [`Client::transform_selection`](crate::client::Client::transform_selection) 转换到本地文档上。
+//!
+//! # Example
+//! ```
+//! use ot_rs::core::{Selection, TextOperation};
+//! use ot_rs::presence::{PresenceManager, PresenceUpdate};
+//!
+//! let mut presence = PresenceManager::new(30_000);
+//! presence.apply_update(
+//!     PresenceUpdate::join("bob", "Bob", "#1f77b4", Selection::cursor(3)),
+//!     0,
+//! );
+//! // alice 在文档开头输入，bob 的光标随之移动
+//! let mut ops = TextOperation::new();
+//! ops.insert("xy").retain(5);
+//! presence.transform(&ops, Some("alice"));
+//! assert_eq!(Selection::cursor(5), presence.get("bob").unwrap().selection);
+//!
+//! // bob 长时间没有更新，被移除
+//! assert_eq!(vec!["bob".to_string()], presence.expire(60_000));
+//! assert!(presence.is_empty());
+//! ```
+
+use crate::core::{LengthUnit, Selection, TextOperation};
+use std::collections::btree_map;
+use std::collections::BTreeMap;
+
+/// 未指定颜色时，根据客户端 id 从中选取的默认颜色
+pub const DEFAULT_COLORS: [&str; 8] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#17becf",
+];
+
+/// 根据客户端 id 选取一个稳定的默认颜色
+/// # Example
+/// ```
+/// use ot_rs::presence::default_color;
+/// assert_eq!(default_color("alice"), default_color("alice"));
+/// ```
+pub fn default_color(client_id: &str) -> &'static str {
+    let hash = client_id
+        .bytes()
+        .fold(0usize, |h, b| h.wrapping_mul(31).wrapping_add(b as usize));
+    return DEFAULT_COLORS[hash % DEFAULT_COLORS.len()];
+}
+
+/// 一个协作者的在线状态
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Presence {
+    /// 名称
+    pub name: String,
+    /// 颜色
+    pub color: String,
+    /// 选择
+    pub selection: Selection,
+    /// 最后一次收到更新的时间戳（毫秒）
+    pub last_seen: u64,
+}
+
+/// 在线状态的更新消息，参见[模块文档](self)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PresenceUpdate {
+    /// 客户端 id
+    pub client_id: String,
+    /// 选择，`None` 表示该协作者离开
+    pub selection: Option<Selection>,
+    /// 名称，`None` 表示不变
+    pub name: Option<String>,
+    /// 颜色，`None` 表示不变（新加入时使用 [`default_color`]）
+    pub color: Option<String>,
+}
+
+impl PresenceUpdate {
+    /// 协作者加入
+    pub fn join<I: Into<String>, N: Into<String>, C: Into<String>>(
+        client_id: I,
+        name: N,
+        color: C,
+        selection: Selection,
+    ) -> PresenceUpdate {
+        return PresenceUpdate {
+            client_id: client_id.into(),
+            selection: Some(selection),
+            name: Some(name.into()),
+            color: Some(color.into()),
+        };
+    }
+
+    /// 协作者的选择发生变化
+    pub fn selection<T: Into<String>>(client_id: T, selection: Selection) -> PresenceUpdate {
+        return PresenceUpdate {
+            client_id: client_id.into(),
+            selection: Some(selection),
+            name: None,
+            color: None,
+        };
+    }
+
+    /// 协作者离开
+    pub fn leave<T: Into<String>>(client_id: T) -> PresenceUpdate {
+        return PresenceUpdate {
+            client_id: client_id.into(),
+            selection: None,
+            name: None,
+            color: None,
+        };
+    }
+}
+
+/// 全部协作者的在线状态，参见[模块文档](self)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PresenceManager {
+    clients: BTreeMap<String, Presence>,
+    /// 超过该时长（毫秒）没有更新的协作者将被 `expire` 移除
+    timeout: u64,
+}
+
+impl PresenceManager {
+    /// 构造函数，`timeout` 为协作者的最大空闲时长（毫秒）
+    pub fn new(timeout: u64) -> PresenceManager {
+        return PresenceManager {
+            clients: BTreeMap::new(),
+            timeout,
+        };
+    }
+
+    /// 在时间 `now`（毫秒）收到一个更新消息：加入、更新或移除一个协作者
+    /// # Example
+    /// ```
+    /// use ot_rs::core::Selection;
+    /// use ot_rs::presence::{PresenceManager, PresenceUpdate};
+    /// let mut presence = PresenceManager::new(30_000);
+    /// presence.apply_update(PresenceUpdate::selection("bob", Selection::cursor(1)), 0);
+    /// assert_eq!("bob", presence.get("bob").unwrap().name);
+    /// presence.apply_update(PresenceUpdate::leave("bob"), 10);
+    /// assert_eq!(None, presence.get("bob"));
+    /// ```
+    pub fn apply_update(&mut self, update: PresenceUpdate, now: u64) {
+        let PresenceUpdate {
+            client_id,
+            selection,
+            name,
+            color,
+        } = update;
+        let selection = match selection {
+            Some(selection) => selection,
+            None => {
+                self.clients.remove(&client_id);
+                return;
+            }
+        };
+        match self.clients.get_mut(&client_id) {
+            Some(presence) => {
+                presence.selection = selection;
+                presence.last_seen = now;
+                if let Some(name) = name {
+                    presence.name = name;
+                }
+                if let Some(color) = color {
+                    presence.color = color;
+                }
+            }
+            None => {
+                let presence = Presence {
+                    name: name.unwrap_or_else(|| client_id.clone()),
+                    color: color.unwrap_or_else(|| default_color(&client_id).to_string()),
+                    selection,
+                    last_seen: now,
+                };
+                self.clients.insert(client_id, presence);
+            }
+        }
+    }
+
+    /// 将全部协作者的选择通过一个应用到文档上的操作进行转换。
+    /// `author` 为该操作所属的客户端 id，其选择端点处的插入将推动端点，参见 [`SelectionRange::transform_with`](crate::core::SelectionRange::transform_with)
+    pub fn transform<U: LengthUnit>(&mut self, operation: &TextOperation<U>, author: Option<&str>) {
+        for (client_id, presence) in self.clients.iter_mut() {
+            let is_own_op = author == Some(client_id.as_str());
+            presence.selection = presence.selection.transform_with(operation, is_own_op);
+        }
+    }
+
+    /// 移除在时间 `now`（毫秒）之前超过空闲时长没有更新的协作者，返回被移除的客户端 id
+    pub fn expire(&mut self, now: u64) -> Vec<String> {
+        let timeout = self.timeout;
+        let expired: Vec<String> = self
+            .clients
+            .iter()
+            .filter(|(_, presence)| now.saturating_sub(presence.last_seen) > timeout)
+            .map(|(client_id, _)| client_id.clone())
+            .collect();
+        for client_id in expired.iter() {
+            self.clients.remove(client_id);
+        }
+        return expired;
+    }
+
+    /// 获取一个协作者的在线状态
+    pub fn get(&self, client_id: &str) -> Option<&Presence> {
+        self.clients.get(client_id)
+    }
+
+    /// 移除一个协作者
+    pub fn remove(&mut self, client_id: &str) -> Option<Presence> {
+        self.clients.remove(client_id)
+    }
+
+    /// 按客户端 id 的顺序遍历全部协作者
+    pub fn clients(&self) -> btree_map::Iter<'_, String, Presence> {
+        self.clients.iter()
+    }
+
+    /// 协作者的数量
+    pub fn len(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// 是否没有协作者
+    pub fn is_empty(&self) -> bool {
+        self.clients.is_empty()
+    }
+}
+
+#[cfg(feature = "serde")]
+mod wire {
+    use super::PresenceUpdate;
+    use crate::core::{Selection, SelectionRange};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    type Compact = (
+        String,
+        Option<Vec<(usize, usize)>>,
+        Option<String>,
+        Option<String>,
+    );
+
+    impl Serialize for PresenceUpdate {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            let ranges = self
+                .selection
+                .as_ref()
+                .map(|s| s.ranges.iter().map(|r| (r.anchor, r.head)).collect());
+            let compact: Compact = (
+                self.client_id.clone(),
+                ranges,
+                self.name.clone(),
+                self.color.clone(),
+            );
+            compact.serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for PresenceUpdate {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let (client_id, ranges, name, color) = Compact::deserialize(deserializer)?;
+            let selection = ranges.map(|ranges| {
+                Selection::new(
+                    ranges
+                        .into_iter()
+                        .map(|(anchor, head)| SelectionRange::new(anchor, head))
+                        .collect(),
+                )
+            });
+            return Ok(PresenceUpdate {
+                client_id,
+                selection,
+                name,
+                color,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{PresenceManager, PresenceUpdate};
+    use crate::core::{Selection, SelectionRange, TextOperation};
+    use rand::{self, Rng};
+
+    #[test]
+    fn test_update_and_expire() {
+        let mut presence = PresenceManager::new(100);
+        // 各参数可以是不同的类型
+        presence.apply_update(
+            PresenceUpdate::join("a".to_string(), "Alice", "red", Selection::cursor(0)),
+            0,
+        );
+        presence.apply_update(PresenceUpdate::selection("b", Selection::cursor(1)), 50);
+        presence.apply_update(PresenceUpdate::selection("a", Selection::cursor(2)), 80);
+        let a = presence.get("a").unwrap();
+        assert_eq!(
+            ("Alice", "red", 80),
+            (a.name.as_str(), a.color.as_str(), a.last_seen)
+        );
+        assert_eq!(Selection::cursor(2), a.selection);
+        assert_eq!(super::default_color("b"), presence.get("b").unwrap().color);
+
+        assert!(presence.expire(150).is_empty());
+        assert_eq!(vec!["b".to_string()], presence.expire(151));
+        assert_eq!(
+            vec!["a".to_string()],
+            presence
+                .clients()
+                .map(|(id, _)| id.clone())
+                .collect::<Vec<_>>()
+        );
+        presence.apply_update(PresenceUpdate::leave("a"), 160);
+        assert!(presence.is_empty());
+    }
+
+    /// 端点 `index` 之前、之后的原始字符（大写字母，互不相同）
+    fn split_originals(doc: &str, index: usize) -> (String, String) {
+        let before = doc.chars().take(index).filter(char::is_ascii_uppercase);
+        let after = doc.chars().skip(index).filter(char::is_ascii_uppercase);
+        return (before.collect(), after.collect());
+    }
+
+    #[test]
+    fn test_random_transform() {
+        let mut rng = rand::thread_rng();
+        let ids = ["a", "b", "c"];
+        for _ in 0..200 {
+            // 原始字符互不相同，插入的字符为小写字母
+            let mut doc: String = ('A'..='J').collect();
+            let mut presence = PresenceManager::new(1000);
+            for id in ids.iter() {
+                let range = SelectionRange::new(rng.gen_range(0..=10), rng.gen_range(0..=10));
+                presence.apply_update(
+                    PresenceUpdate::selection(*id, Selection::new(vec![range])),
+                    0,
+                );
+            }
+            for _ in 0..10 {
+                let mut ops = TextOperation::new();
+                for _ in doc.chars() {
+                    if rng.gen_bool(0.2) {
+                        ops.insert(rng.gen_range('a'..='e').to_string());
+                    }
+                    if rng.gen_bool(0.2) {
+                        ops.delete(1);
+                    } else {
+                        ops.retain(1);
+                    }
+                }
+                let after = ops.apply(doc.as_str()).unwrap();
+                let author = ids[rng.gen_range(0..ids.len())];
+                let before = presence.clone();
+                presence.transform(&ops, Some(author));
+                for (id, p) in presence.clients() {
+                    let old = &before.get(id).unwrap().selection.ranges[0];
+                    let new = &p.selection.ranges[0];
+                    for &(old_index, new_index) in
+                        [(old.anchor, new.anchor), (old.head, new.head)].iter()
+                    {
+                        // 未被删除的原始字符与端点的相对位置不变
+                        assert!(new_index <= after.chars().count());
+                        let (old_before, old_after) = split_originals(&doc, old_index);
+                        let (new_before, new_after) = split_originals(&after, new_index);
+                        let kept = |s: String| -> String {
+                            s.chars().filter(|c| after.contains(*c)).collect()
+                        };
+                        assert_eq!(kept(old_before), new_before);
+                        assert_eq!(kept(old_after), new_after);
+                    }
+                }
+                doc = after;
+            }
+        }
+    }
+
+    #[test]
+    fn test_transform_own_insert() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let len = rng.gen_range(0..10);
+            let index = rng.gen_range(0..=len);
+            let mut presence = PresenceManager::new(1000);
+            for id in ["a", "b", "c"].iter() {
+                presence.apply_update(PresenceUpdate::selection(*id, Selection::cursor(index)), 0);
+            }
+            // 在光标处插入：作者的光标被推到插入内容之后，其他协作者的光标保持在插入内容之前
+            let mut ops = TextOperation::new();
+            ops.retain(index).insert("xy").retain(len - index);
+            presence.transform(&ops, Some("b"));
+            assert_eq!(
+                Selection::cursor(index + 2),
+                presence.get("b").unwrap().selection
+            );
+            assert_eq!(
+                Selection::cursor(index),
+                presence.get("a").unwrap().selection
+            );
+            assert_eq!(
+                Selection::cursor(index),
+                presence.get("c").unwrap().selection
+            );
+            // 没有作者时，全部光标都保持在插入内容之前
+            presence.transform(&ops_at(index + 2, len + 2), None);
+            assert_eq!(
+                Selection::cursor(index + 2),
+                presence.get("b").unwrap().selection
+            );
+        }
+    }
+
+    fn ops_at(index: usize, len: usize) -> TextOperation {
+        let mut ops = TextOperation::new();
+        ops.retain(index).insert("z").retain(len - index);
+        return ops;
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let update = PresenceUpdate::join(
+            "a",
+            "Alice",
+            "red",
+            Selection::new(vec![SelectionRange::new(1, 3), SelectionRange::cursor(5)]),
+        );
+        let json = serde_json::to_string(&update).unwrap();
+        assert_eq!(r#"["a",[[1,3],[5,5]],"Alice","red"]"#, json);
+        assert_eq!(update, serde_json::from_str(&json).unwrap());
+        let leave = PresenceUpdate::leave("a");
+        let json = serde_json::to_string(&leave).unwrap();
+        assert_eq!(r#"["a",null,null,null]"#, json);
+        assert_eq!(leave, serde_json::from_str(&json).unwrap());
+    }
+}
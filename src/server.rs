@@ -0,0 +1,234 @@
+//! # 服务端
+//!
+//! 实现上参考了 [ot.js 的 Server](https://github.com/Operational-Transformation/ot.js/blob/master/lib/server.js)。
+//!
+//! 服务端保存当前文档与全部已应用的操作（即版本历史），第 `n` 个操作将文档从版本 `n` 变为版本 `n + 1`。
+//! 客户端发送的操作基于其所知的版本，服务端将其针对该版本之后的全部操作进行转换，应用到文档上，
+//! 再将转换后的操作广播给其他客户端（发送方收到确认，参见 [`Client::server_ack`](crate::client::Client::server_ack)）。
+//!
+//! # Example
+//! ```
+//! use ot_rs::core::TextOperation;
+//! use ot_rs::server::Server;
+//!
+//! let mut server: Server = Server::new("ab".to_string());
+//! let mut a = TextOperation::new();
+//! a.insert("x").retain(2);
+//! let mut b = TextOperation::new();
+//! b.retain(2).insert("y");
+//! // 两个客户端同时基于版本 0 编辑
+//! assert_eq!(a, server.receive_operation(0, a.clone()).unwrap());
+//! let broadcast = server.receive_operation(0, b).unwrap();
+//! assert_eq!("(3->4){retain(3).insert(\"y\")}", broadcast.to_string());
+//! assert_eq!("xaby", server.document());
+//! assert_eq!(2, server.revision());
+//! ```
+
+use crate::core::{OperationError, Side, TextOperation};
+use crate::types::OtType;
+use std::fmt;
+
+/// 服务端，参见[模块文档](self)
+pub struct Server<T: OtType = TextOperation> {
+    document: T::Snapshot,
+    operations: Vec<T::Op>,
+    /// `operations` 中第一个操作所基于的版本，更早的操作已被丢弃
+    first_revision: usize,
+    history_limit: Option<usize>,
+}
+
+impl<T: OtType> Server<T> {
+    /// 以版本 0 的文档 `document` 构造服务端，保留全部历史操作
+    pub fn new(document: T::Snapshot) -> Server<T> {
+        return Server {
+            document,
+            operations: vec![],
+            first_revision: 0,
+            history_limit: None,
+        };
+    }
+
+    /// 以版本 0 的文档 `document` 构造服务端，只保留最近的 `limit` 个历史操作。
+    /// 基于更早版本的操作将无法被转换，并返回 `RevisionTooOld`
+    /// # Example
+    /// ```
+    /// use ot_rs::core::{OperationError, TextOperation};
+    /// use ot_rs::server::Server;
+    /// let mut server: Server = Server::with_history_limit(String::new(), 1);
+    /// let mut a = TextOperation::new();
+    /// a.insert("a");
+    /// server.receive_operation(0, a.clone()).unwrap();
+    /// server.receive_operation(0, a.clone()).unwrap();
+    /// assert_eq!(Err(OperationError::RevisionTooOld(0)), server.receive_operation(0, a));
+    /// ```
+    pub fn with_history_limit(document: T::Snapshot, limit: usize) -> Server<T> {
+        let mut server = Server::new(document);
+        server.history_limit = Some(limit);
+        return server;
+    }
+
+    /// 当前文档
+    pub fn document(&self) -> &T::Snapshot {
+        &self.document
+    }
+
+    /// 当前文档的版本，即已应用的操作的数量
+    pub fn revision(&self) -> usize {
+        self.first_revision + self.operations.len()
+    }
+
+    /// 基于版本 `revision` 之后的全部操作，例如用于客户端重连后追赶最新的版本
+    /// # Example
+    /// ```
+    /// use ot_rs::core::{OperationError, TextOperation};
+    /// use ot_rs::server::Server;
+    /// let mut server: Server = Server::new(String::new());
+    /// let mut a = TextOperation::new();
+    /// a.insert("a");
+    /// server.receive_operation(0, a.clone()).unwrap();
+    /// assert_eq!(&[a], server.operations_since(0).unwrap());
+    /// assert!(server.operations_since(1).unwrap().is_empty());
+    /// assert_eq!(Err(OperationError::RevisionInFuture(2)), server.operations_since(2));
+    /// ```
+    pub fn operations_since(&self, revision: usize) -> Result<&[T::Op], OperationError> {
+        if revision > self.revision() {
+            return Err(OperationError::RevisionInFuture(revision));
+        }
+        if revision < self.first_revision {
+            return Err(OperationError::RevisionTooOld(revision));
+        }
+        return Ok(&self.operations[revision - self.first_revision..]);
+    }
+
+    /// 收到基于版本 `revision` 的操作 `operation`：将其针对该版本之后的全部操作进行转换并应用到文档上，
+    /// 返回转换后需要广播给其他客户端的操作。
+    ///
+    /// `revision` 大于当前版本时返回 `RevisionInFuture`，早于所保留的历史时返回 `RevisionTooOld`；
+    /// 出错时文档与历史保持不变
+    pub fn receive_operation(
+        &mut self,
+        revision: usize,
+        operation: T::Op,
+    ) -> Result<T::Op, OperationError> {
+        let mut operation = operation;
+        for concurrent in self.operations_since(revision)? {
+            operation = T::transform(&operation, concurrent, Side::Left)?;
+        }
+        self.document = T::apply(&self.document, &operation)?;
+        self.operations.push(operation.clone());
+        if let Some(limit) = self.history_limit {
+            if self.operations.len() > limit {
+                let discarded = self.operations.len() - limit;
+                self.operations.drain(..discarded);
+                self.first_revision += discarded;
+            }
+        }
+        return Ok(operation);
+    }
+}
+
+impl<T: OtType> fmt::Debug for Server<T>
+where
+    T::Snapshot: fmt::Debug,
+    T::Op: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Server")
+            .field("document", &self.document)
+            .field("operations", &self.operations)
+            .field("first_revision", &self.first_revision)
+            .field("history_limit", &self.history_limit)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::Server;
+    use crate::core::{Meta, OperationError, TextOperation, WrappedOperation};
+    use rand::{self, Rng};
+
+    #[test]
+    fn test_errors() {
+        let mut server: Server = Server::with_history_limit("a".to_string(), 2);
+        let mut ops = TextOperation::new();
+        ops.retain(1).insert("b");
+        assert_eq!(
+            Err(OperationError::RevisionInFuture(1)),
+            server.receive_operation(1, ops.clone())
+        );
+        for _ in 0..3 {
+            server.receive_operation(0, ops.clone()).unwrap();
+        }
+        assert_eq!("abbb", server.document());
+        assert_eq!(3, server.revision());
+        assert_eq!(
+            Err(OperationError::RevisionTooOld(0)),
+            server.receive_operation(0, ops.clone())
+        );
+        assert_eq!(2, server.operations_since(1).unwrap().len());
+
+        // 操作无法应用时，文档与历史保持不变
+        assert_eq!(
+            Err(OperationError::OperationApplyStringNotCompatible),
+            server.receive_operation(3, ops)
+        );
+        assert_eq!("abbb", server.document());
+        assert_eq!(3, server.revision());
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Author(&'static str);
+
+    impl Meta for Author {}
+
+    #[test]
+    fn test_wrapped() {
+        // 作者随历史操作一起保存
+        let mut server: Server<WrappedOperation<Author>> = Server::new("ab".to_string());
+        let mut a = TextOperation::new();
+        a.insert("x").retain(2);
+        let mut b = TextOperation::new();
+        b.retain(2).insert("y");
+        server
+            .receive_operation(0, WrappedOperation::new(a, Author("alice")))
+            .unwrap();
+        let broadcast = server
+            .receive_operation(0, WrappedOperation::new(b, Author("bob")))
+            .unwrap();
+        assert_eq!(Author("bob"), broadcast.meta);
+        assert_eq!("xaby", server.document());
+        let authors: Vec<_> = server
+            .operations_since(0)
+            .unwrap()
+            .iter()
+            .map(|op| op.meta.0)
+            .collect();
+        assert_eq!(vec!["alice", "bob"], authors);
+    }
+
+    #[test]
+    fn test_random() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            // 多个客户端基于随机的旧版本发送操作，服务端的结果与依次应用转换后的操作一致
+            let mut server: Server = Server::new("abc".to_string());
+            let mut documents = vec![server.document().clone()];
+            for _ in 0..10 {
+                let revision = rng.gen_range(0..documents.len());
+                let base = &documents[revision];
+                let target: String = base.chars().filter(|_| rng.gen_bool(0.8)).collect();
+                let operation = TextOperation::diff(base, &format!("{}x", target));
+                let transformed = server.receive_operation(revision, operation).unwrap();
+                let last = documents.last().unwrap();
+                assert_eq!(
+                    server.document(),
+                    &transformed.apply(last.as_str()).unwrap()
+                );
+                documents.push(server.document().clone());
+            }
+            assert_eq!(10, server.revision());
+        }
+    }
+}
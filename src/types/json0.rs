@@ -0,0 +1,1609 @@
+//! # JSON 文档 OT 类型（兼容 ShareDB json0）
+//!
+//! 实现上参考了 [ottypes/json0](https://github.com/ottypes/json0/blob/master/lib/json0.js)（需要开启 `json` feature）。
+//!
+//! 一个 [`Json0Operation`] 由若干个 [`Json0Component`] 组成，每个原子操作由路径 `p` 与动作组成：
+//!
+//! | 动作 | json0 格式 | 路径指向 |
+//! | --- | --- | --- |
+//! | `ListInsert(v)` | `{p, li}` | 插入后的数组下标 |
+//! | `ListDelete(v)` | `{p, ld}` | 被删除的数组下标 |
+//! | `ListReplace(before, after)` | `{p, ld, li}` | 被替换的数组下标 |
+//! | `ListMove(to)` | `{p, lm}` | 被移动的数组下标 |
+//! | `ObjectInsert(v)` | `{p, oi}` | 插入的对象键 |
+//! | `ObjectDelete(v)` | `{p, od}` | 被删除的对象键 |
+//! | `ObjectReplace(before, after)` | `{p, od, oi}` | 被替换的对象键 |
+//! | `NumberAdd(n)` | `{p, na}` | 数字本身 |
+//! | `StringInsert(s)` | `{p, si}` | 字符串中的插入位置 |
+//! | `StringDelete(s)` | `{p, sd}` | 字符串中被删除的文本的起始位置 |
+//! | `Text(op)` | `{p, t: "text-otjs", o}` | 字符串本身，`o` 为 ot.js 格式的 [`TextOperation`] |
+//!
+//! 字符串中的位置与长度以 `U` 为单位计算，与 ShareDB 的 JavaScript 客户端交换数据时应使用 `Utf16`。
+//! json0 客户端发送的 `si`/`sd` 可以与 `TextOperation` 子类型作用于同一个字符串，两者之间可以相互转换。
+//!
+//! 注意：`text-otjs`（[`TEXT_SUBTYPE`]）不是 json0 内置的子类型，JavaScript 端需要将兼容 ot.js 格式的子类型
+//! 以该名称通过 `json0.registerSubtype` 注册后才能使用；json0 内置的 `text0` 子类型不受支持，其操作可以改为以 `si`/`sd` 发送。
+//!
+//! # Example
+//! ```
+//! use ot_rs::core::Side;
+//! use ot_rs::types::json0::Json0Operation;
+//! use serde_json::json;
+//!
+//! let doc = json!({"todos": ["a", "b"], "count": 2});
+//! let a: Json0Operation = serde_json::from_value(json!([
+//!     {"p": ["todos", 2], "li": "c"},
+//!     {"p": ["count"], "na": 1},
+//! ])).unwrap();
+//! let b: Json0Operation = serde_json::from_value(json!([{"p": ["todos", 0], "ld": "a"}])).unwrap();
+//!
+//! let (a_prime, b_prime) = a.transform_with_side(&b, Side::Left).unwrap();
+//! let left = b_prime.apply(&a.apply(&doc).unwrap()).unwrap();
+//! let right = a_prime.apply(&b.apply(&doc).unwrap()).unwrap();
+//! assert_eq!(json!({"todos": ["b", "c"], "count": 3}), left);
+//! assert_eq!(left, right);
+//! assert_eq!(json!([{"p": ["todos", 1], "li": "c"}, {"p": ["count"], "na": 1}]), a_prime.to_value());
+//! ```
+
+use crate::core::{Chars, Component, LengthUnit, OperationError, Side, TextOperation};
+use crate::types::{OtType, UnitTypeNames};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Number, Value};
+
+/// json0 格式中 `TextOperation` 子类型的名称
+pub const TEXT_SUBTYPE: &str = "text-otjs";
+
+/// 路径中的一段：对象的键或数组的下标
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    /// 对象的键
+    Key(String),
+    /// 数组的下标
+    Index(usize),
+}
+
+impl From<&str> for PathSegment {
+    fn from(key: &str) -> PathSegment {
+        PathSegment::Key(key.to_string())
+    }
+}
+
+impl From<String> for PathSegment {
+    fn from(key: String) -> PathSegment {
+        PathSegment::Key(key)
+    }
+}
+
+impl From<usize> for PathSegment {
+    fn from(index: usize) -> PathSegment {
+        PathSegment::Index(index)
+    }
+}
+
+/// 原子操作的动作，参见[模块文档](self)
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json0Action<U: LengthUnit = Chars> {
+    /// 在数组中插入一个值
+    ListInsert(Value),
+    /// 从数组中删除一个值
+    ListDelete(Value),
+    /// 替换数组中的一个值：`(before, after)`
+    ListReplace(Value, Value),
+    /// 将数组中的一个值移动到给定的下标
+    ListMove(usize),
+    /// 在对象中插入一个键
+    ObjectInsert(Value),
+    /// 从对象中删除一个键
+    ObjectDelete(Value),
+    /// 替换对象中的一个键的值：`(before, after)`
+    ObjectReplace(Value, Value),
+    /// 为数字加上给定的值
+    NumberAdd(Number),
+    /// 在字符串中插入文本
+    StringInsert(String),
+    /// 从字符串中删除文本
+    StringDelete(String),
+    /// 编辑字符串
+    Text(TextOperation<U>),
+}
+
+/// 原子操作：在路径 `path` 上执行动作 `action`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Json0Component<U: LengthUnit = Chars> {
+    pub path: Vec<PathSegment>,
+    pub action: Json0Action<U>,
+}
+
+impl<U: LengthUnit> Json0Component<U> {
+    /// 构造一个原子操作
+    pub fn new(path: Vec<PathSegment>, action: Json0Action<U>) -> Json0Component<U> {
+        return Json0Component { path, action };
+    }
+
+    /// 检查路径与动作是否相符：数组操作的路径以下标结尾，对象操作的路径以键结尾
+    fn validate(&self) -> Result<(), OperationError> {
+        let valid = matches!(
+            (&self.action, self.path.last()),
+            (Json0Action::NumberAdd(_), _)
+                | (Json0Action::Text(_), _)
+                | (Json0Action::ListInsert(_), Some(PathSegment::Index(_)))
+                | (Json0Action::ListDelete(_), Some(PathSegment::Index(_)))
+                | (Json0Action::ListReplace(..), Some(PathSegment::Index(_)))
+                | (Json0Action::ListMove(_), Some(PathSegment::Index(_)))
+                | (Json0Action::ObjectInsert(_), Some(PathSegment::Key(_)))
+                | (Json0Action::ObjectDelete(_), Some(PathSegment::Key(_)))
+                | (Json0Action::ObjectReplace(..), Some(PathSegment::Key(_)))
+                | (Json0Action::StringInsert(_), Some(PathSegment::Index(_)))
+                | (Json0Action::StringDelete(_), Some(PathSegment::Index(_)))
+        );
+        if !valid {
+            return Err(OperationError::JsonPathInvalid);
+        }
+        return Ok(());
+    }
+
+    /// 原子操作所作用的值的路径长度：`na` 与 `t` 作用于路径指向的值的"内部"，因此加一
+    fn operand_len(&self) -> usize {
+        match self.action {
+            Json0Action::NumberAdd(_) | Json0Action::Text(_) => self.path.len() + 1,
+            _ => self.path.len(),
+        }
+    }
+
+    fn index_at(&self, i: usize) -> Option<usize> {
+        match self.path.get(i) {
+            Some(&PathSegment::Index(index)) => Some(index),
+            _ => None,
+        }
+    }
+
+    /// 是否为字符串的编辑：`Text`、`StringInsert` 或 `StringDelete`
+    fn is_string_edit(&self) -> bool {
+        matches!(
+            self.action,
+            Json0Action::Text(_) | Json0Action::StringInsert(_) | Json0Action::StringDelete(_)
+        )
+    }
+
+    /// 将字符串的编辑转换为作用于长度为 `base_length` 的字符串的 `TextOperation`
+    fn to_text_operation(&self, base_length: usize) -> Result<TextOperation<U>, OperationError> {
+        let mut ops = TextOperation::with_unit();
+        let offset = self.index_at(self.path.len().saturating_sub(1));
+        match (&self.action, offset) {
+            (Json0Action::Text(op), _) => return Ok(op.clone()),
+            (Json0Action::StringInsert(s), Some(offset)) => {
+                let rest = base_length
+                    .checked_sub(offset)
+                    .ok_or(OperationError::JsonPathInvalid)?;
+                ops.retain(offset).insert(s.as_str()).retain(rest);
+            }
+            (Json0Action::StringDelete(s), Some(offset)) => {
+                let rest = base_length
+                    .checked_sub(offset + U::len(s))
+                    .ok_or(OperationError::JsonPathInvalid)?;
+                ops.retain(offset).delete(U::len(s)).retain(rest);
+            }
+            _ => return Err(OperationError::JsonComponentInvalid),
+        }
+        return Ok(ops);
+    }
+
+    /// `si`/`sd` 编辑结束的位置，即所作用的字符串至少应有的长度
+    fn string_end(&self) -> usize {
+        let offset = self.index_at(self.path.len().saturating_sub(1));
+        match (&self.action, offset) {
+            (Json0Action::StringInsert(_), Some(offset)) => offset,
+            (Json0Action::StringDelete(s), Some(offset)) => offset + U::len(s),
+            _ => 0,
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        let mut map = Map::new();
+        let path = self
+            .path
+            .iter()
+            .map(|p| match p {
+                PathSegment::Key(key) => Value::String(key.clone()),
+                &PathSegment::Index(index) => Value::from(index),
+            })
+            .collect();
+        map.insert("p".to_string(), Value::Array(path));
+        let mut put = |key: &str, value: Value| {
+            map.insert(key.to_string(), value);
+        };
+        match &self.action {
+            Json0Action::ListInsert(v) => put("li", v.clone()),
+            Json0Action::ListDelete(v) => put("ld", v.clone()),
+            Json0Action::ListReplace(before, after) => {
+                put("ld", before.clone());
+                put("li", after.clone());
+            }
+            &Json0Action::ListMove(to) => put("lm", Value::from(to)),
+            Json0Action::ObjectInsert(v) => put("oi", v.clone()),
+            Json0Action::ObjectDelete(v) => put("od", v.clone()),
+            Json0Action::ObjectReplace(before, after) => {
+                put("od", before.clone());
+                put("oi", after.clone());
+            }
+            Json0Action::NumberAdd(n) => put("na", Value::Number(n.clone())),
+            Json0Action::StringInsert(s) => put("si", Value::from(s.as_str())),
+            Json0Action::StringDelete(s) => put("sd", Value::from(s.as_str())),
+            Json0Action::Text(op) => {
+                put("t", Value::from(TEXT_SUBTYPE));
+                put("o", serde_json::to_value(op).unwrap_or(Value::Null));
+            }
+        }
+        return Value::Object(map);
+    }
+
+    fn from_value(value: &Value) -> Result<Json0Component<U>, OperationError> {
+        let map = value
+            .as_object()
+            .ok_or(OperationError::JsonComponentInvalid)?;
+        let path = map
+            .get("p")
+            .and_then(|p| p.as_array())
+            .ok_or(OperationError::JsonComponentInvalid)?
+            .iter()
+            .map(|p| match p {
+                Value::String(key) => Ok(PathSegment::Key(key.clone())),
+                Value::Number(n) => n
+                    .as_u64()
+                    .map(|n| PathSegment::Index(n as usize))
+                    .ok_or(OperationError::JsonComponentInvalid),
+                _ => Err(OperationError::JsonComponentInvalid),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let get = |key: &str| map.get(key).cloned();
+        let action = if let Some(t) = map.get("t") {
+            if t.as_str() != Some(TEXT_SUBTYPE) {
+                return Err(OperationError::JsonComponentInvalid);
+            }
+            let op = serde_json::from_value(get("o").ok_or(OperationError::JsonComponentInvalid)?)
+                .map_err(|_| OperationError::JsonComponentInvalid)?;
+            Json0Action::Text(op)
+        } else if let Some(na) = map.get("na") {
+            Json0Action::NumberAdd(
+                na.as_number()
+                    .cloned()
+                    .ok_or(OperationError::JsonComponentInvalid)?,
+            )
+        } else if let Some(si) = map.get("si") {
+            Json0Action::StringInsert(
+                si.as_str()
+                    .ok_or(OperationError::JsonComponentInvalid)?
+                    .to_string(),
+            )
+        } else if let Some(sd) = map.get("sd") {
+            Json0Action::StringDelete(
+                sd.as_str()
+                    .ok_or(OperationError::JsonComponentInvalid)?
+                    .to_string(),
+            )
+        } else if let Some(lm) = map.get("lm") {
+            Json0Action::ListMove(lm.as_u64().ok_or(OperationError::JsonComponentInvalid)? as usize)
+        } else {
+            match (get("li"), get("ld"), get("oi"), get("od")) {
+                (Some(li), None, None, None) => Json0Action::ListInsert(li),
+                (None, Some(ld), None, None) => Json0Action::ListDelete(ld),
+                (Some(li), Some(ld), None, None) => Json0Action::ListReplace(ld, li),
+                (None, None, Some(oi), None) => Json0Action::ObjectInsert(oi),
+                (None, None, None, Some(od)) => Json0Action::ObjectDelete(od),
+                (None, None, Some(oi), Some(od)) => Json0Action::ObjectReplace(od, oi),
+                _ => return Err(OperationError::JsonComponentInvalid),
+            }
+        };
+        let component = Json0Component { path, action };
+        component.validate()?;
+        return Ok(component);
+    }
+}
+
+/// JSON 文档上的操作，参见[模块文档](self)
+///
+/// 与 `TextOperation` 一样，`U` 为其中字符串子操作的长度单位；与 ShareDB 的 JavaScript 客户端交换数据时应使用 `Utf16`。
+/// 此时类型的默认名称为 `json0-utf16`（参见 [`UnitTypeNames`]），需要以 `json0` 命名时可以使用 `register_as`。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Json0Operation<U: LengthUnit = Chars> {
+    components: Vec<Json0Component<U>>,
+}
+
+impl<U: LengthUnit> Default for Json0Operation<U> {
+    fn default() -> Self {
+        return Json0Operation { components: vec![] };
+    }
+}
+
+impl Json0Operation {
+    /// 构造一个空操作
+    pub fn new() -> Json0Operation {
+        return Json0Operation::default();
+    }
+}
+
+impl<U: LengthUnit> Json0Operation<U> {
+    /// 构造一个空操作，并指定字符串子操作的长度单位
+    pub fn with_unit() -> Json0Operation<U> {
+        return Json0Operation::default();
+    }
+
+    /// 由原子操作列表构造一个操作，相邻的可合并的原子操作将被合并
+    /// # Example
+    /// ```
+    /// use ot_rs::types::json0::{Json0Action, Json0Component, Json0Operation};
+    /// use serde_json::json;
+    /// let ops: Json0Operation = Json0Operation::from_components(vec![
+    ///     Json0Component::new(vec!["a".into()], Json0Action::ObjectInsert(json!(1))),
+    ///     Json0Component::new(vec!["a".into()], Json0Action::ObjectDelete(json!(1))),
+    /// ]).unwrap();
+    /// assert!(ops.is_noop());
+    /// // 数组操作的路径必须以下标结尾
+    /// let invalid: Result<Json0Operation, _> = Json0Operation::from_components(vec![
+    ///     Json0Component::new(vec!["a".into()], Json0Action::ListInsert(json!(1))),
+    /// ]);
+    /// assert!(invalid.is_err());
+    /// ```
+    pub fn from_components<I: IntoIterator<Item = Json0Component<U>>>(
+        components: I,
+    ) -> Result<Json0Operation<U>, OperationError> {
+        let mut operation = Json0Operation::with_unit();
+        for component in components {
+            operation.push(component)?;
+        }
+        return Ok(operation);
+    }
+
+    /// 追加一个原子操作，可能与最后一个原子操作合并
+    pub fn push(
+        &mut self,
+        component: Json0Component<U>,
+    ) -> Result<&mut Json0Operation<U>, OperationError> {
+        component.validate()?;
+        append(&mut self.components, component)?;
+        return Ok(self);
+    }
+
+    /// 原子操作列表
+    pub fn components(&self) -> &[Json0Component<U>] {
+        &self.components
+    }
+
+    /// 操作是否不产生任何影响
+    pub fn is_noop(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    /// 转换为 json0 格式
+    pub fn to_value(&self) -> Value {
+        Value::Array(self.components.iter().map(|c| c.to_value()).collect())
+    }
+
+    /// 从 json0 格式解析，单个原子操作也被接受
+    pub fn from_value(value: &Value) -> Result<Json0Operation<U>, OperationError> {
+        if let Value::Array(components) = value {
+            return Json0Operation::from_components(
+                components
+                    .iter()
+                    .map(Json0Component::from_value)
+                    .collect::<Result<Vec<_>, _>>()?,
+            );
+        }
+        return Json0Operation::from_components(vec![Json0Component::from_value(value)?]);
+    }
+
+    /// 将操作应用到 JSON 文档上
+    /// # Example
+    /// ```
+    /// use ot_rs::types::json0::Json0Operation;
+    /// use serde_json::json;
+    /// let ops: Json0Operation = Json0Operation::from_value(&json!([
+    ///     {"p": ["list", 0], "lm": 2},
+    ///     {"p": ["title"], "t": "text-otjs", "o": [2, "!"]},
+    /// ])).unwrap();
+    /// let doc = json!({"list": [1, 2, 3], "title": "hi"});
+    /// assert_eq!(json!({"list": [2, 3, 1], "title": "hi!"}), ops.apply(&doc).unwrap());
+    /// ```
+    pub fn apply(&self, doc: &Value) -> Result<Value, OperationError> {
+        let mut doc = doc.clone();
+        for component in &self.components {
+            apply_component(&mut doc, component)?;
+        }
+        return Ok(doc);
+    }
+
+    /// 基于文档 `doc` 生成逆操作，被删除的值取自 `doc` 而非原子操作中记录的值
+    /// # Example
+    /// ```
+    /// use ot_rs::types::json0::Json0Operation;
+    /// use serde_json::json;
+    /// let ops: Json0Operation = Json0Operation::from_value(&json!([
+    ///     {"p": ["a"], "od": null},
+    ///     {"p": ["b", 0], "lm": 1},
+    /// ])).unwrap();
+    /// let doc = json!({"a": {"x": 1}, "b": [1, 2]});
+    /// let inverse = ops.invert(&doc).unwrap();
+    /// assert_eq!(json!([{"p": ["b", 1], "lm": 0}, {"p": ["a"], "oi": {"x": 1}}]), inverse.to_value());
+    /// assert_eq!(doc, inverse.apply(&ops.apply(&doc).unwrap()).unwrap());
+    /// ```
+    pub fn invert(&self, doc: &Value) -> Result<Json0Operation<U>, OperationError> {
+        let mut doc = doc.clone();
+        let mut inverted = Vec::with_capacity(self.components.len());
+        for component in &self.components {
+            inverted.push(invert_component(&mut doc, component)?);
+            apply_component(&mut doc, component)?;
+        }
+        inverted.reverse();
+        return Json0Operation::from_components(inverted);
+    }
+
+    /// 合并连续的两个操作
+    pub fn compose(
+        &self,
+        operation2: &Json0Operation<U>,
+    ) -> Result<Json0Operation<U>, OperationError> {
+        let mut composed = self.clone();
+        for component in &operation2.components {
+            append(&mut composed.components, component.clone())?;
+        }
+        return Ok(composed);
+    }
+
+    /// 转换基于同一文档的两个操作，返回 `(a', b')`，满足 `b'.apply(self.apply(doc)) == a'.apply(operation2.apply(doc))`。
+    /// 双方在同一位置插入时，由 `side` 决定 `self` 的插入是否在前，参见 [`Side`]
+    pub fn transform_with_side(
+        &self,
+        operation2: &Json0Operation<U>,
+        side: Side,
+    ) -> Result<(Json0Operation<U>, Json0Operation<U>), OperationError> {
+        if side == Side::Left {
+            let (left, right) = transform_x(&self.components, &operation2.components)?;
+            return Ok((
+                Json0Operation { components: left },
+                Json0Operation { components: right },
+            ));
+        }
+        let (left, right) = transform_x(&operation2.components, &self.components)?;
+        return Ok((
+            Json0Operation { components: right },
+            Json0Operation { components: left },
+        ));
+    }
+
+    /// 同 `transform_with_side(operation2, Side::Left)`
+    pub fn transform(
+        &self,
+        operation2: &Json0Operation<U>,
+    ) -> Result<(Json0Operation<U>, Json0Operation<U>), OperationError> {
+        self.transform_with_side(operation2, Side::Left)
+    }
+}
+
+impl<U: LengthUnit> Serialize for Json0Operation<U> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_value().serialize(serializer)
+    }
+}
+
+impl<'de, U: LengthUnit> Deserialize<'de> for Json0Operation<U> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        Json0Operation::from_value(&value).map_err(serde::de::Error::custom)
+    }
+}
+
+impl<U: UnitTypeNames> OtType for Json0Operation<U> {
+    const NAME: &'static str = U::JSON0;
+
+    type Snapshot = Value;
+    type Op = Json0Operation<U>;
+
+    fn create() -> Value {
+        Value::Null
+    }
+
+    fn apply(snapshot: &Value, op: &Json0Operation<U>) -> Result<Value, OperationError> {
+        op.apply(snapshot)
+    }
+
+    fn compose(
+        a: &Json0Operation<U>,
+        b: &Json0Operation<U>,
+    ) -> Result<Json0Operation<U>, OperationError> {
+        a.compose(b)
+    }
+
+    fn transform(
+        op: &Json0Operation<U>,
+        other: &Json0Operation<U>,
+        side: Side,
+    ) -> Result<Json0Operation<U>, OperationError> {
+        return Ok(op.transform_with_side(other, side)?.0);
+    }
+
+    fn transform_pair(
+        a: &Json0Operation<U>,
+        b: &Json0Operation<U>,
+        side: Side,
+    ) -> Result<(Json0Operation<U>, Json0Operation<U>), OperationError> {
+        a.transform_with_side(b, side)
+    }
+
+    fn invert(
+        snapshot: &Value,
+        op: &Json0Operation<U>,
+    ) -> Result<Json0Operation<U>, OperationError> {
+        op.invert(snapshot)
+    }
+
+    fn normalize(op: Json0Operation<U>) -> Json0Operation<U> {
+        // 通过 from_components/push 构造的操作总是规范的
+        op
+    }
+
+    fn is_noop(op: &Json0Operation<U>) -> bool {
+        op.is_noop()
+    }
+}
+
+fn add_numbers(a: &Number, b: &Number) -> Result<Number, OperationError> {
+    if let (Some(a), Some(b)) = (a.as_i64(), b.as_i64()) {
+        if let Some(sum) = a.checked_add(b) {
+            return Ok(sum.into());
+        }
+    }
+    let sum = a.as_f64().unwrap_or(f64::NAN) + b.as_f64().unwrap_or(f64::NAN);
+    return Number::from_f64(sum).ok_or(OperationError::JsonValueMismatch);
+}
+
+fn negate_number(n: &Number) -> Result<Number, OperationError> {
+    if let Some(neg) = n.as_i64().and_then(|n| n.checked_neg()) {
+        return Ok(neg.into());
+    }
+    return Number::from_f64(-n.as_f64().unwrap_or(f64::NAN))
+        .ok_or(OperationError::JsonValueMismatch);
+}
+
+fn lookup_mut<'a>(
+    doc: &'a mut Value,
+    path: &[PathSegment],
+) -> Result<&'a mut Value, OperationError> {
+    let mut value = doc;
+    for segment in path {
+        value = match (value, segment) {
+            (Value::Object(map), PathSegment::Key(key)) => map.get_mut(key),
+            (Value::Array(list), &PathSegment::Index(index)) => list.get_mut(index),
+            _ => None,
+        }
+        .ok_or(OperationError::JsonPathInvalid)?;
+    }
+    return Ok(value);
+}
+
+/// 在第 `offset` 个单位处将字符串分为两部分，`offset` 超出字符串长度时返回 `JsonPathInvalid`
+fn split_string<U: LengthUnit>(text: &str, offset: usize) -> Result<(&str, &str), OperationError> {
+    return match U::split_at(text, offset) {
+        Err(OperationError::OperationMoreLeftString) => Err(OperationError::JsonPathInvalid),
+        result => result,
+    };
+}
+
+fn apply_component<U: LengthUnit>(
+    doc: &mut Value,
+    component: &Json0Component<U>,
+) -> Result<(), OperationError> {
+    match &component.action {
+        Json0Action::Text(op) => {
+            let value = lookup_mut(doc, &component.path)?;
+            let text = value.as_str().ok_or(OperationError::JsonValueMismatch)?;
+            *value = Value::String(op.apply(text)?);
+            return Ok(());
+        }
+        Json0Action::NumberAdd(n) => {
+            let value = lookup_mut(doc, &component.path)?;
+            let number = value.as_number().ok_or(OperationError::JsonValueMismatch)?;
+            *value = Value::Number(add_numbers(number, n)?);
+            return Ok(());
+        }
+        _ => {}
+    }
+    let (last, parent_path) = component
+        .path
+        .split_last()
+        .ok_or(OperationError::JsonPathInvalid)?;
+    let parent = lookup_mut(doc, parent_path)?;
+    match (&component.action, parent, last) {
+        (Json0Action::ListInsert(v), Value::Array(list), &PathSegment::Index(index))
+            if index <= list.len() =>
+        {
+            list.insert(index, v.clone());
+        }
+        (Json0Action::ListDelete(_), Value::Array(list), &PathSegment::Index(index))
+            if index < list.len() =>
+        {
+            list.remove(index);
+        }
+        (Json0Action::ListReplace(_, v), Value::Array(list), &PathSegment::Index(index))
+            if index < list.len() =>
+        {
+            list[index] = v.clone();
+        }
+        (&Json0Action::ListMove(to), Value::Array(list), &PathSegment::Index(index))
+            if index < list.len() && to < list.len() =>
+        {
+            let value = list.remove(index);
+            list.insert(to, value);
+        }
+        (Json0Action::ObjectInsert(v), Value::Object(map), PathSegment::Key(key))
+        | (Json0Action::ObjectReplace(_, v), Value::Object(map), PathSegment::Key(key)) => {
+            map.insert(key.clone(), v.clone());
+        }
+        (Json0Action::ObjectDelete(_), Value::Object(map), PathSegment::Key(key)) => {
+            map.remove(key);
+        }
+        (Json0Action::StringInsert(s), Value::String(text), &PathSegment::Index(offset)) => {
+            let (before, after) = split_string::<U>(text, offset)?;
+            *text = format!("{}{}{}", before, s, after);
+        }
+        (Json0Action::StringDelete(s), Value::String(text), &PathSegment::Index(offset)) => {
+            let (before, after) = split_string::<U>(text, offset)?;
+            // 与 json0 一致，被删除的文本必须与文档中的文本相符
+            let after = after
+                .strip_prefix(s.as_str())
+                .ok_or(OperationError::JsonValueMismatch)?;
+            *text = format!("{}{}", before, after);
+        }
+        (_, Value::Array(_), _) | (_, Value::Object(_), _) => {
+            return Err(OperationError::JsonPathInvalid);
+        }
+        _ => return Err(OperationError::JsonValueMismatch),
+    }
+    return Ok(());
+}
+
+/// 基于应用 `component` 之前的文档 `doc` 生成 `component` 的逆
+fn invert_component<U: LengthUnit>(
+    doc: &mut Value,
+    component: &Json0Component<U>,
+) -> Result<Json0Component<U>, OperationError> {
+    let path = component.path.clone();
+    let action = match &component.action {
+        Json0Action::Text(op) => {
+            let value = lookup_mut(doc, &path)?;
+            let text = value.as_str().ok_or(OperationError::JsonValueMismatch)?;
+            Json0Action::Text(op.invert(text)?)
+        }
+        Json0Action::NumberAdd(n) => Json0Action::NumberAdd(negate_number(n)?),
+        // 被删除的文本在应用时已与文档核对
+        Json0Action::StringInsert(s) => Json0Action::StringDelete(s.clone()),
+        Json0Action::StringDelete(s) => Json0Action::StringInsert(s.clone()),
+        Json0Action::ListInsert(v) => Json0Action::ListDelete(v.clone()),
+        Json0Action::ListDelete(_) => Json0Action::ListInsert(lookup_mut(doc, &path)?.clone()),
+        Json0Action::ListReplace(_, after) => {
+            Json0Action::ListReplace(after.clone(), lookup_mut(doc, &path)?.clone())
+        }
+        &Json0Action::ListMove(to) => {
+            let mut path = path;
+            let from = path.pop();
+            path.push(PathSegment::Index(to));
+            let from = match from {
+                Some(PathSegment::Index(from)) => from,
+                _ => return Err(OperationError::JsonPathInvalid),
+            };
+            return Ok(Json0Component::new(path, Json0Action::ListMove(from)));
+        }
+        Json0Action::ObjectInsert(v) => match lookup_mut(doc, &path) {
+            // 插入已存在的键相当于替换
+            Ok(before) => Json0Action::ObjectReplace(v.clone(), before.clone()),
+            Err(_) => Json0Action::ObjectDelete(v.clone()),
+        },
+        Json0Action::ObjectDelete(_) => Json0Action::ObjectInsert(lookup_mut(doc, &path)?.clone()),
+        Json0Action::ObjectReplace(_, after) => match lookup_mut(doc, &path) {
+            Ok(before) => Json0Action::ObjectReplace(after.clone(), before.clone()),
+            Err(_) => Json0Action::ObjectDelete(after.clone()),
+        },
+    };
+    return Ok(Json0Component::new(path, action));
+}
+
+/// 尝试将 `next` 合并到紧邻其前的 `last` 中（两者路径相同）：
+/// `None` 表示无法合并，`Some(None)` 表示两者相互抵消，`Some(Some(action))` 表示合并为 `action`
+#[allow(clippy::option_option)]
+fn merge<U: LengthUnit>(
+    last: &Json0Action<U>,
+    next: &Json0Action<U>,
+) -> Result<Option<Option<Json0Action<U>>>, OperationError> {
+    use Json0Action::*;
+    let merged = match (last, next) {
+        (Text(a), Text(b)) => {
+            let composed = a.compose(b)?;
+            Some(if composed.is_noop() {
+                None
+            } else {
+                Some(Text(composed))
+            })
+        }
+        (NumberAdd(a), NumberAdd(b)) => Some(Some(NumberAdd(add_numbers(a, b)?))),
+        // 同一下标上的删除或替换作用的必然是刚刚插入的值
+        (ListInsert(_), ListDelete(_)) => Some(None),
+        (ListInsert(_), ListReplace(_, after)) => Some(Some(ListInsert(after.clone()))),
+        (ListReplace(before, _), ListDelete(_)) => Some(Some(ListDelete(before.clone()))),
+        (ListReplace(before, _), ListReplace(_, after)) => {
+            Some(Some(ListReplace(before.clone(), after.clone())))
+        }
+        (ObjectDelete(before), ObjectInsert(after)) => {
+            Some(Some(ObjectReplace(before.clone(), after.clone())))
+        }
+        (ObjectInsert(_), ObjectDelete(_)) => Some(None),
+        (ObjectInsert(_), ObjectReplace(_, after)) => Some(Some(ObjectInsert(after.clone()))),
+        (ObjectReplace(before, _), ObjectDelete(_)) => Some(Some(ObjectDelete(before.clone()))),
+        (ObjectReplace(before, _), ObjectReplace(_, after)) => {
+            Some(Some(ObjectReplace(before.clone(), after.clone())))
+        }
+        // 在同一位置插入后删除（或删除后插入）相同的文本
+        (StringInsert(a), StringDelete(b)) | (StringDelete(a), StringInsert(b)) if a == b => {
+            Some(None)
+        }
+        _ => None,
+    };
+    return Ok(merged);
+}
+
+/// 对应 json0 的 `append`：追加原子操作，并尽可能与最后一个原子操作合并
+fn append<U: LengthUnit>(
+    components: &mut Vec<Json0Component<U>>,
+    component: Json0Component<U>,
+) -> Result<(), OperationError> {
+    match &component.action {
+        &Json0Action::ListMove(to) if component.index_at(component.path.len() - 1) == Some(to) => {
+            return Ok(());
+        }
+        Json0Action::Text(op) if op.is_noop() => return Ok(()),
+        Json0Action::StringInsert(s) | Json0Action::StringDelete(s) if s.is_empty() => {
+            return Ok(());
+        }
+        _ => {}
+    }
+    if let Some(last) = components.last_mut() {
+        if last.path == component.path {
+            match merge(&last.action, &component.action)? {
+                Some(Some(action)) => last.action = action,
+                Some(None) => {
+                    components.pop();
+                }
+                None => components.push(component),
+            }
+            return Ok(());
+        }
+    }
+    components.push(component);
+    return Ok(());
+}
+
+/// 对应 json0 的 `commonLengthForOps`：若 `a` 所作用的容器的路径是 `b` 的路径的前缀，返回该容器路径的长度
+fn common_length<U: LengthUnit>(a: &Json0Component<U>, b: &Json0Component<U>) -> Option<usize> {
+    let a_len = a.operand_len();
+    let b_len = b.operand_len();
+    if a_len == 0 || b_len == 0 || a_len > b_len {
+        return None;
+    }
+    let common = a_len - 1;
+    if a.path[..common.min(a.path.len())] != b.path[..common.min(b.path.len())] {
+        return None;
+    }
+    return Some(common);
+}
+
+/// 对应 json0 的 `transformComponent`：将 `component` 针对 `other` 进行转换，
+/// `side` 为 `Side::Left` 时 `component` 在冲突中获胜；返回空列表表示 `component` 被消除，
+/// 字符串的删除中间被插入了文本时将被拆分为多个原子操作
+fn transform_component<U: LengthUnit>(
+    component: &Json0Component<U>,
+    other: &Json0Component<U>,
+    side: Side,
+) -> Result<Vec<Json0Component<U>>, OperationError> {
+    use Json0Action::*;
+    let mut c = component.clone();
+    let c_len = c.operand_len();
+    let other_len = other.operand_len();
+
+    // c 删除的值被 other 修改了：更新 c 所记录的被删除的值，以保持 c 可逆
+    if let Some(common) = common_length(&c, other) {
+        if other_len > c_len && c.path.get(common) == other.path.get(common) {
+            if let ListDelete(before)
+            | ListReplace(before, _)
+            | ObjectDelete(before)
+            | ObjectReplace(before, _) = &mut c.action
+            {
+                let sub = Json0Component::new(other.path[c_len..].to_vec(), other.action.clone());
+                apply_component(before, &sub)?;
+            }
+        }
+    }
+
+    let common = match common_length(other, &c) {
+        Some(common) => common,
+        None => return Ok(vec![c]),
+    };
+    let common_operand = c_len == other_len;
+    let same = c.path.get(common).is_some() && c.path.get(common) == other.path.get(common);
+    let p = c.index_at(common);
+    let other_p = other.index_at(common);
+
+    // 双方编辑同一个字符串
+    if common_operand && c.is_string_edit() && other.is_string_edit() {
+        return transform_string(&c, other, side);
+    }
+
+    match &other.action {
+        Text(_) | StringInsert(_) | StringDelete(_) | NumberAdd(_) => {}
+        ListReplace(_, other_inserted) => {
+            if same {
+                if !common_operand {
+                    return Ok(vec![]);
+                }
+                match &c.action {
+                    // 双方替换同一个值：只有一方可以保留
+                    ListReplace(_, inserted) if side == Side::Left => {
+                        c.action = ListReplace(other_inserted.clone(), inserted.clone());
+                    }
+                    ListDelete(_) | ListReplace(..) => return Ok(vec![]),
+                    _ => {}
+                }
+            }
+        }
+        ListInsert(_) => {
+            if let (Some(p), Some(other_p)) = (p, other_p) {
+                if matches!(c.action, ListInsert(_)) && common_operand && p == other_p {
+                    // 在同一位置插入，左侧在前
+                    if side == Side::Right {
+                        c.path[common] = PathSegment::Index(p + 1);
+                    }
+                } else if other_p <= p {
+                    c.path[common] = PathSegment::Index(p + 1);
+                }
+                if let (ListMove(to), true) = (&mut c.action, common_operand) {
+                    if other_p <= *to {
+                        *to += 1;
+                    }
+                }
+            }
+        }
+        ListDelete(_) => {
+            if let (Some(p), Some(other_p)) = (p, other_p) {
+                if let (ListMove(to), true) = (&mut c.action, common_operand) {
+                    if other_p == p {
+                        // 被移动的值已被删除
+                        return Ok(vec![]);
+                    }
+                    if other_p < *to || (other_p == *to && p < *to) {
+                        *to -= 1;
+                    }
+                }
+                if other_p < p {
+                    c.path[common] = PathSegment::Index(p - 1);
+                } else if other_p == p {
+                    if other_len < c_len {
+                        // c 作用于被删除的值的内部
+                        return Ok(vec![]);
+                    }
+                    match &c.action {
+                        // c 替换的值已被删除，c 变为插入
+                        ListReplace(_, inserted) => c.action = ListInsert(inserted.clone()),
+                        ListDelete(_) => return Ok(vec![]),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        &ListMove(other_to) => {
+            if let (Some(p), Some(other_from)) = (p, other_p) {
+                if !transform_by_move(
+                    &mut c,
+                    common,
+                    p,
+                    other_from,
+                    other_to,
+                    side,
+                    common_operand,
+                ) {
+                    return Ok(vec![]);
+                }
+            }
+        }
+        ObjectReplace(_, other_inserted) => {
+            if same {
+                match &c.action {
+                    ObjectInsert(inserted) | ObjectReplace(_, inserted) if common_operand => {
+                        if side == Side::Right {
+                            return Ok(vec![]);
+                        }
+                        c.action = ObjectReplace(other_inserted.clone(), inserted.clone());
+                    }
+                    // other 替换了 c 所作用的值（或其祖先）
+                    _ => return Ok(vec![]),
+                }
+            }
+        }
+        ObjectInsert(other_inserted) => {
+            if same && common_operand {
+                if let ObjectInsert(inserted) | ObjectReplace(_, inserted) = &c.action {
+                    // 在同一个键插入，左侧获胜
+                    if side == Side::Right {
+                        return Ok(vec![]);
+                    }
+                    c.action = ObjectReplace(other_inserted.clone(), inserted.clone());
+                }
+            }
+        }
+        ObjectDelete(_) => {
+            if same {
+                if !common_operand {
+                    return Ok(vec![]);
+                }
+                match &c.action {
+                    ObjectInsert(_) => {}
+                    ObjectReplace(_, inserted) => c.action = ObjectInsert(inserted.clone()),
+                    _ => return Ok(vec![]),
+                }
+            }
+        }
+    }
+    return Ok(vec![c]);
+}
+
+/// 将作用于同一个字符串的两个编辑 `c` 与 `other` 都转换为 `TextOperation` 后进行转换，
+/// 结果再转换回 `c` 的形式。`si`/`sd` 不包含字符串的长度：任一方为 `Text` 时取其长度，否则取足以容纳双方的长度
+fn transform_string<U: LengthUnit>(
+    c: &Json0Component<U>,
+    other: &Json0Component<U>,
+    side: Side,
+) -> Result<Vec<Json0Component<U>>, OperationError> {
+    let base_length = match (&c.action, &other.action) {
+        (Json0Action::Text(op), _) | (_, Json0Action::Text(op)) => op.base_length(),
+        _ => c.string_end().max(other.string_end()),
+    };
+    let other_op = other.to_text_operation(base_length)?;
+    let op = c
+        .to_text_operation(base_length)?
+        .transform_with_side(&other_op, side)?
+        .0;
+    if op.is_noop() {
+        return Ok(vec![]);
+    }
+    let deleted = match &c.action {
+        Json0Action::Text(_) => {
+            return Ok(vec![Json0Component::new(
+                c.path.clone(),
+                Json0Action::Text(op),
+            )]);
+        }
+        Json0Action::StringDelete(s) => {
+            surviving_text::<U>(s, c.string_end() - U::len(s), &other_op)?
+        }
+        _ => String::new(),
+    };
+
+    // 依次生成 si/sd，每个原子操作的位置为应用其之前的原子操作后的位置
+    let container = &c.path[..c.path.len() - 1];
+    let at = |offset: usize| {
+        let mut path = container.to_vec();
+        path.push(PathSegment::Index(offset));
+        return path;
+    };
+    let mut components = vec![];
+    let mut offset = 0;
+    let mut deleted = deleted.as_str();
+    for component in op.ops() {
+        match component {
+            &Component::Retain(n) => offset += n,
+            Component::Insert(s) => {
+                components.push(Json0Component::new(
+                    at(offset),
+                    Json0Action::StringInsert(s.clone()),
+                ));
+                offset += U::len(s);
+            }
+            &Component::Delete(n) => {
+                let (text, rest) = U::split_at(deleted, n)?;
+                components.push(Json0Component::new(
+                    at(offset),
+                    Json0Action::StringDelete(text.to_string()),
+                ));
+                deleted = rest;
+            }
+        }
+    }
+    return Ok(components);
+}
+
+/// 从位置 `offset` 开始的文本 `text` 中未被 `other` 删除的部分
+fn surviving_text<U: LengthUnit>(
+    text: &str,
+    offset: usize,
+    other: &TextOperation<U>,
+) -> Result<String, OperationError> {
+    let end = offset + U::len(text);
+    let mut surviving = String::new();
+    let mut position = 0;
+    for component in other.ops() {
+        match component {
+            &Component::Retain(n) => {
+                let (from, to) = (position.max(offset), (position + n).min(end));
+                if from < to {
+                    let from = U::byte_offset(text, from - offset)?;
+                    let to = U::byte_offset(text, to - offset)?;
+                    surviving.push_str(&text[from..to]);
+                }
+                position += n;
+            }
+            &Component::Delete(n) => position += n,
+            Component::Insert(_) => {}
+        }
+    }
+    return Ok(surviving);
+}
+
+/// 将 `c` 针对移动操作 `lm: other_to`（作用于下标 `other_from`）进行转换，
+/// `p` 为 `c` 的路径在第 `common` 段的下标；返回 `false` 表示 `c` 被消除
+fn transform_by_move<U: LengthUnit>(
+    c: &mut Json0Component<U>,
+    common: usize,
+    p: usize,
+    other_from: usize,
+    other_to: usize,
+    side: Side,
+    common_operand: bool,
+) -> bool {
+    let set = |c: &mut Json0Component<U>, index: isize| {
+        c.path[common] = PathSegment::Index(index as usize);
+    };
+    let (from, other_from, other_to) = (p as isize, other_from as isize, other_to as isize);
+    if let (&Json0Action::ListMove(to), true) = (&c.action, common_operand) {
+        // 双方都在移动同一个数组中的值
+        if other_from == other_to {
+            return true;
+        }
+        let to = to as isize;
+        if from == other_from {
+            // 双方移动同一个值，左侧获胜
+            if side == Side::Right {
+                return false;
+            }
+            set(c, other_to);
+            if from == to {
+                c.action = Json0Action::ListMove(other_to as usize);
+            }
+            return true;
+        }
+        let mut new_from = from;
+        let mut new_to = to;
+        if from > other_from {
+            new_from -= 1;
+        }
+        if from > other_to {
+            new_from += 1;
+        } else if from == other_to && other_from > other_to {
+            new_from += 1;
+            if from == to {
+                new_to += 1;
+            }
+        }
+        if to > other_from || (to == other_from && to > from) {
+            new_to -= 1;
+        }
+        if to > other_to {
+            new_to += 1;
+        } else if to == other_to {
+            if (other_to > other_from && to > from) || (other_to < other_from && to < from) {
+                if side == Side::Right {
+                    new_to += 1;
+                }
+            } else if to > from {
+                new_to += 1;
+            } else if to == other_from {
+                new_to -= 1;
+            }
+        }
+        set(c, new_from);
+        c.action = Json0Action::ListMove(new_to as usize);
+        return true;
+    }
+    if matches!(c.action, Json0Action::ListInsert(_)) && common_operand {
+        let mut new_p = from;
+        if from > other_from {
+            new_p -= 1;
+        }
+        if from > other_to {
+            new_p += 1;
+        }
+        set(c, new_p);
+        return true;
+    }
+    // 其他原子操作关心的是其所作用的值被移动到了哪里
+    if from == other_from {
+        set(c, other_to);
+        return true;
+    }
+    let mut new_p = from;
+    if from > other_from {
+        new_p -= 1;
+    }
+    if from > other_to || (from == other_to && other_from > other_to) {
+        new_p += 1;
+    }
+    set(c, new_p);
+    return true;
+}
+
+/// 对应 json0 的 `transformX`：`left` 在冲突中获胜，返回 `(left', right')`
+#[allow(clippy::type_complexity)]
+fn transform_x<U: LengthUnit>(
+    left: &[Json0Component<U>],
+    right: &[Json0Component<U>],
+) -> Result<(Vec<Json0Component<U>>, Vec<Json0Component<U>>), OperationError> {
+    let mut left = left.to_vec();
+    let mut new_right = vec![];
+    for right_component in right {
+        let mut right_components = vec![right_component.clone()];
+        let mut new_left = Vec::with_capacity(left.len());
+        let mut k = 0;
+        while k < left.len() && right_components.len() == 1 {
+            let r = right_components.remove(0);
+            for l in transform_component(&left[k], &r, Side::Left)? {
+                append(&mut new_left, l)?;
+            }
+            right_components = transform_component(&r, &left[k], Side::Right)?;
+            k += 1;
+        }
+        if right_components.len() > 1 {
+            // 右侧的原子操作被拆分为多个，与剩余的左侧原子操作递归地转换
+            let (rest_left, rest_right) = transform_x(&left[k..], &right_components)?;
+            for l in rest_left {
+                append(&mut new_left, l)?;
+            }
+            right_components = rest_right;
+        } else {
+            // 右侧的原子操作已被消除（或已与全部左侧原子操作转换），剩余的左侧原子操作无需转换
+            for l in &left[k..] {
+                append(&mut new_left, l.clone())?;
+            }
+        }
+        for r in right_components {
+            append(&mut new_right, r)?;
+        }
+        left = new_left;
+    }
+    return Ok((left, new_right));
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Json0Action, Json0Component, Json0Operation, PathSegment};
+    use crate::core::{OperationError, Side, TextOperation, Utf16};
+    use crate::types::OtType;
+    use rand::{self, Rng};
+    use serde_json::{json, Value};
+
+    fn op(value: Value) -> Json0Operation {
+        Json0Operation::from_value(&value).unwrap()
+    }
+
+    fn check_transform(doc: &Value, a: &Json0Operation, b: &Json0Operation) {
+        for &side in &[Side::Left, Side::Right] {
+            let (a_prime, b_prime) = a.transform_with_side(b, side).unwrap();
+            let left = b_prime.apply(&a.apply(doc).unwrap()).unwrap();
+            let right = a_prime.apply(&b.apply(doc).unwrap()).unwrap();
+            assert_eq!(
+                left,
+                right,
+                "a={} b={} side={:?}",
+                a.to_value(),
+                b.to_value(),
+                side
+            );
+            assert_eq!(
+                a_prime,
+                <Json0Operation as OtType>::transform(a, b, side).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_wire_format() {
+        let value = json!([
+            {"p": ["a", 0], "li": 1},
+            {"p": ["a", 1], "ld": 2, "li": 3},
+            {"p": ["a", 2], "lm": 0},
+            {"p": ["b"], "od": {}, "oi": []},
+            {"p": ["c"], "na": -1.5},
+            {"p": ["d"], "t": "text-otjs", "o": [1, "x", -1]},
+            {"p": ["e", 1], "si": "x"},
+            {"p": ["e", 0], "sd": "a"},
+        ]);
+        let ops = op(value.clone());
+        assert_eq!(value, ops.to_value());
+        assert_eq!(value, serde_json::to_value(&ops).unwrap());
+        assert_eq!(
+            Json0Action::ObjectReplace(json!({}), json!([])),
+            ops.components()[3].action
+        );
+        // 单个原子操作
+        assert_eq!(1, op(json!({"p": ["a"], "oi": 1})).components().len());
+        for invalid in [
+            json!([{"p": ["a"], "si": "x"}]),
+            json!([{"p": ["a", 0], "sd": 1}]),
+            json!([{"p": ["a"], "t": "text0", "o": []}]),
+            json!([{"p": [-1], "li": 1}]),
+            json!([{"p": ["a"], "li": 1}]),
+            json!([{"p": [0], "oi": 1}]),
+            json!([{"p": [0], "lm": "a"}]),
+            json!([{"p": ["a"], "li": 1, "oi": 1}]),
+            json!([{"li": 1}]),
+            json!([{"p": ["a"]}]),
+        ] {
+            assert!(Json0Operation::<crate::core::Chars>::from_value(&invalid).is_err());
+        }
+        assert!(serde_json::from_value::<Json0Operation>(json!([{"p": []}])).is_err());
+    }
+
+    #[test]
+    fn test_apply() {
+        let doc = json!({"a": [1, 2, 3], "n": 1, "s": "ab"});
+        assert_eq!(
+            json!({"a": [0, 1, 2, 3], "n": 1, "s": "ab"}),
+            op(json!([{"p": ["a", 0], "li": 0}])).apply(&doc).unwrap()
+        );
+        assert_eq!(
+            json!({"a": [1, 3], "n": 3, "s": "abc"}),
+            op(json!([
+                {"p": ["a", 1], "ld": 2},
+                {"p": ["n"], "na": 2},
+                {"p": ["s"], "t": "text-otjs", "o": [2, "c"]},
+            ]))
+            .apply(&doc)
+            .unwrap()
+        );
+        assert_eq!(
+            json!({"a": [1, 2, 3], "n": 1.5, "s": "ab", "o": {"x": null}}),
+            op(json!([{"p": ["o"], "oi": {}}, {"p": ["o", "x"], "oi": null}, {"p": ["n"], "na": 0.5}]))
+                .apply(&doc)
+                .unwrap()
+        );
+        assert_eq!(
+            json!(3),
+            op(json!([{"p": [], "na": 2}])).apply(&json!(1)).unwrap()
+        );
+        let error = |ops: Value| op(ops).apply(&doc).unwrap_err();
+        assert_eq!(
+            OperationError::JsonPathInvalid,
+            error(json!([{"p": ["a", 4], "li": 0}]))
+        );
+        assert_eq!(
+            OperationError::JsonPathInvalid,
+            error(json!([{"p": ["x", 0], "ld": 0}]))
+        );
+        assert_eq!(
+            OperationError::JsonPathInvalid,
+            error(json!([{"p": ["a", "x"], "oi": 0}]))
+        );
+        assert_eq!(
+            OperationError::JsonValueMismatch,
+            error(json!([{"p": ["s"], "na": 1}]))
+        );
+        assert_eq!(
+            OperationError::JsonValueMismatch,
+            error(json!([{"p": ["n", 0], "li": 0}]))
+        );
+    }
+
+    #[test]
+    fn test_compose() {
+        let doc = json!({"a": [], "n": 1});
+        let a = op(json!([{"p": ["a", 0], "li": "x"}, {"p": ["n"], "na": 1}]));
+        let b = op(json!([{"p": ["n"], "na": 2}, {"p": ["b"], "oi": 1}]));
+        let ab = a.compose(&b).unwrap();
+        assert_eq!(
+            json!([{"p": ["a", 0], "li": "x"}, {"p": ["n"], "na": 3}, {"p": ["b"], "oi": 1}]),
+            ab.to_value()
+        );
+        assert_eq!(
+            b.apply(&a.apply(&doc).unwrap()).unwrap(),
+            ab.apply(&doc).unwrap()
+        );
+        // 插入后删除相互抵消
+        let c = op(json!([{"p": ["b"], "od": 1}]));
+        assert_eq!(
+            json!([{"p": ["a", 0], "li": "x"}, {"p": ["n"], "na": 3}]),
+            ab.compose(&c).unwrap().to_value()
+        );
+        let text = op(json!([{"p": ["s"], "t": "text-otjs", "o": ["a"]}]))
+            .compose(&op(json!([{"p": ["s"], "t": "text-otjs", "o": [1, "b"]}])))
+            .unwrap();
+        assert_eq!(
+            json!([{"p": ["s"], "t": "text-otjs", "o": ["ab"]}]),
+            text.to_value()
+        );
+    }
+
+    #[test]
+    fn test_transform() {
+        let doc = json!({"list": [0, 1, 2, 3], "obj": {"k": "v"}, "s": "abc", "n": 0});
+        let cases = [
+            // 在同一位置插入：左侧在前
+            (
+                json!([{"p": ["list", 1], "li": "a"}]),
+                json!([{"p": ["list", 1], "li": "b"}]),
+            ),
+            // 删除同一个值
+            (
+                json!([{"p": ["list", 1], "ld": 1}]),
+                json!([{"p": ["list", 1], "ld": 1}]),
+            ),
+            // 替换与删除
+            (
+                json!([{"p": ["list", 1], "ld": 1, "li": "x"}]),
+                json!([{"p": ["list", 1], "ld": 1}]),
+            ),
+            // 移动与删除
+            (
+                json!([{"p": ["list", 0], "lm": 3}]),
+                json!([{"p": ["list", 2], "ld": 2}]),
+            ),
+            // 移动同一个值
+            (
+                json!([{"p": ["list", 0], "lm": 3}]),
+                json!([{"p": ["list", 0], "lm": 1}]),
+            ),
+            // 在同一个键插入
+            (
+                json!([{"p": ["obj", "x"], "oi": 1}]),
+                json!([{"p": ["obj", "x"], "oi": 2}]),
+            ),
+            // 删除对象与修改其内部
+            (
+                json!([{"p": ["obj"], "od": {"k": "v"}}]),
+                json!([{"p": ["obj", "k"], "od": "v", "oi": 1}]),
+            ),
+            // 字符串与数字
+            (
+                json!([{"p": ["s"], "t": "text-otjs", "o": [1, "x", 2]}, {"p": ["n"], "na": 1}]),
+                json!([{"p": ["s"], "t": "text-otjs", "o": [1, "y", -2]}, {"p": ["n"], "na": 2}]),
+            ),
+        ];
+        for (a, b) in cases.iter() {
+            check_transform(&doc, &op(a.clone()), &op(b.clone()));
+        }
+
+        // 删除的值被修改后，删除操作记录的值随之更新，保持可逆
+        let a = op(json!([{"p": ["obj"], "od": {"k": "v"}}]));
+        let b = op(json!([{"p": ["obj", "k"], "od": "v"}]));
+        let (a_prime, b_prime) = a.transform(&b).unwrap();
+        assert_eq!(json!([{"p": ["obj"], "od": {}}]), a_prime.to_value());
+        assert!(b_prime.is_noop());
+
+        let a = op(json!([{"p": ["list", 1], "li": "a"}]));
+        let b = op(json!([{"p": ["list", 1], "li": "b"}]));
+        let (a_prime, b_prime) = a.transform_with_side(&b, Side::Right).unwrap();
+        assert_eq!(json!([{"p": ["list", 2], "li": "a"}]), a_prime.to_value());
+        assert_eq!(json!([{"p": ["list", 1], "li": "b"}]), b_prime.to_value());
+    }
+
+    #[test]
+    fn test_string_insert_delete() {
+        let doc = json!({"s": "a中😄b"});
+        assert_eq!(
+            json!({"s": "a中x😄"}),
+            op(json!([{"p": ["s", 2], "si": "x"}, {"p": ["s", 4], "sd": "b"}]))
+                .apply(&doc)
+                .unwrap()
+        );
+        // 被删除的文本与文档不符
+        assert_eq!(
+            OperationError::JsonValueMismatch,
+            op(json!([{"p": ["s", 1], "sd": "x"}]))
+                .apply(&doc)
+                .unwrap_err()
+        );
+        assert_eq!(
+            OperationError::JsonPathInvalid,
+            op(json!([{"p": ["s", 5], "si": "x"}]))
+                .apply(&doc)
+                .unwrap_err()
+        );
+        // 以 UTF-16 码元计算位置
+        assert_eq!(
+            json!({"s": "a中😄xb"}),
+            Json0Operation::<Utf16>::from_value(&json!([{"p": ["s", 4], "si": "x"}]))
+                .unwrap()
+                .apply(&doc)
+                .unwrap()
+        );
+
+        let a = op(json!([{"p": ["s", 1], "sd": "中😄"}]));
+        assert_eq!(
+            json!([{"p": ["s", 1], "si": "中😄"}]),
+            a.invert(&doc).unwrap().to_value()
+        );
+        // 插入后删除相同的文本相互抵消
+        assert!(a.compose(&a.invert(&doc).unwrap()).unwrap().is_noop());
+
+        let cases = [
+            // 在删除的文本中间插入：删除被拆分为两部分
+            (
+                json!([{"p": ["s", 1], "sd": "中😄"}]),
+                json!([{"p": ["s", 2], "si": "xy"}]),
+            ),
+            // 删除重叠的文本
+            (
+                json!([{"p": ["s", 0], "sd": "a中"}]),
+                json!([{"p": ["s", 1], "sd": "中😄b"}]),
+            ),
+            // 在同一位置插入
+            (
+                json!([{"p": ["s", 1], "si": "x"}]),
+                json!([{"p": ["s", 1], "si": "y"}]),
+            ),
+            // 与 TextOperation 子类型编辑同一个字符串
+            (
+                json!([{"p": ["s", 1], "sd": "中😄"}, {"p": ["s", 0], "si": "z"}]),
+                json!([{"p": ["s"], "t": "text-otjs", "o": [2, -1, "y", 1]}]),
+            ),
+            // 删除包含该字符串的值
+            (
+                json!([{"p": ["s", 1], "si": "x"}]),
+                json!([{"p": ["s"], "od": "a中😄b"}]),
+            ),
+        ];
+        for (a, b) in cases.iter() {
+            check_transform(&doc, &op(a.clone()), &op(b.clone()));
+            check_transform(&doc, &op(b.clone()), &op(a.clone()));
+        }
+
+        let a = op(json!([{"p": ["s", 1], "sd": "中😄"}]));
+        let b = op(json!([{"p": ["s", 2], "si": "xy"}]));
+        let (a_prime, _) = a.transform(&b).unwrap();
+        assert_eq!(
+            json!([{"p": ["s", 1], "sd": "中"}, {"p": ["s", 3], "sd": "😄"}]),
+            a_prime.to_value()
+        );
+        let b = op(json!([{"p": ["s"], "t": "text-otjs", "o": [2, "x", 2]}]));
+        let (a_prime, b_prime) = a.transform(&b).unwrap();
+        assert_eq!(
+            json!([{"p": ["s", 1], "sd": "中"}, {"p": ["s", 2], "sd": "😄"}]),
+            a_prime.to_value()
+        );
+        assert_eq!(
+            json!([{"p": ["s"], "t": "text-otjs", "o": [1, "x", 1]}]),
+            b_prime.to_value()
+        );
+    }
+
+    const KEYS: [&str; 3] = ["a", "b", "c"];
+
+    fn random_value(depth: usize) -> Value {
+        let mut rng = rand::thread_rng();
+        match rng.gen_range(0..if depth == 0 { 3 } else { 5 }) {
+            0 => json!(rng.gen_range(-5..5)),
+            1 => json!(["ab", "x", "", "中😄"][rng.gen_range(0..4)]),
+            2 => Value::Null,
+            3 => Value::Array(
+                (0..rng.gen_range(0..4))
+                    .map(|_| random_value(depth - 1))
+                    .collect(),
+            ),
+            _ => Value::Object(
+                KEYS.iter()
+                    .filter(|_| rng.gen_bool(0.6))
+                    .map(|k| (k.to_string(), random_value(depth - 1)))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// 在文档中随机选取一个值，生成一个作用于该值的原子操作
+    fn random_component(doc: &Value) -> Option<Json0Component> {
+        let mut rng = rand::thread_rng();
+        let mut path = vec![];
+        let mut value = doc;
+        loop {
+            let children: Vec<(PathSegment, &Value)> = match value {
+                Value::Array(list) => list
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| (i.into(), v))
+                    .collect(),
+                Value::Object(map) => map.iter().map(|(k, v)| (k.as_str().into(), v)).collect(),
+                _ => vec![],
+            };
+            if children.is_empty() || rng.gen_bool(0.4) {
+                break;
+            }
+            let (segment, child) = children[rng.gen_range(0..children.len())].clone();
+            path.push(segment);
+            value = child;
+        }
+        let action = match value {
+            Value::Number(_) => Json0Action::NumberAdd(rng.gen_range(-3..4).into()),
+            Value::String(s) => {
+                let chars: Vec<char> = s.chars().collect();
+                let offset = rng.gen_range(0..=chars.len());
+                match rng.gen_range(0..3) {
+                    0 => {
+                        path.push(offset.into());
+                        Json0Action::StringInsert(["y", "", "😄"][rng.gen_range(0..3)].to_string())
+                    }
+                    1 => {
+                        path.push(offset.into());
+                        let end = rng.gen_range(offset..=chars.len());
+                        Json0Action::StringDelete(chars[offset..end].iter().collect())
+                    }
+                    _ => {
+                        let mut target: String = s.chars().filter(|_| rng.gen_bool(0.7)).collect();
+                        target.insert_str(0, ["y", "", "😄"][rng.gen_range(0..3)]);
+                        Json0Action::Text(TextOperation::diff(s, &target))
+                    }
+                }
+            }
+            Value::Array(list) => {
+                let index = rng.gen_range(0..=list.len());
+                path.push(index.into());
+                match rng.gen_range(0..4) {
+                    0 if index < list.len() => Json0Action::ListDelete(list[index].clone()),
+                    1 if index < list.len() => {
+                        Json0Action::ListReplace(list[index].clone(), random_value(1))
+                    }
+                    2 if index < list.len() => Json0Action::ListMove(rng.gen_range(0..list.len())),
+                    _ => Json0Action::ListInsert(random_value(1)),
+                }
+            }
+            Value::Object(map) => {
+                let key = KEYS[rng.gen_range(0..KEYS.len())];
+                path.push(key.into());
+                match (map.get(key), rng.gen_bool(0.5)) {
+                    (Some(v), true) => Json0Action::ObjectDelete(v.clone()),
+                    (Some(v), false) => Json0Action::ObjectReplace(v.clone(), random_value(1)),
+                    (None, _) => Json0Action::ObjectInsert(random_value(1)),
+                }
+            }
+            _ => return None,
+        };
+        return Some(Json0Component::new(path, action));
+    }
+
+    fn random_operation(doc: &Value, n: usize) -> Json0Operation {
+        let mut ops = Json0Operation::new();
+        let mut doc = doc.clone();
+        for _ in 0..n {
+            if let Some(c) = random_component(&doc) {
+                let c = Json0Operation::from_components(vec![c]).unwrap();
+                doc = c.apply(&doc).unwrap();
+                ops = ops.compose(&c).unwrap();
+            }
+        }
+        return ops;
+    }
+
+    #[test]
+    fn test_random() {
+        for _ in 0..500 {
+            let doc = json!({"a": random_value(2), "b": [random_value(2), random_value(2)]});
+            let a = random_operation(&doc, 3);
+            let b = random_operation(&doc, 3);
+            // compose 与依次应用的结果一致
+            let after_a = a.apply(&doc).unwrap();
+            let c = random_operation(&after_a, 3);
+            assert_eq!(
+                c.apply(&after_a).unwrap(),
+                a.compose(&c).unwrap().apply(&doc).unwrap()
+            );
+            // invert
+            assert_eq!(doc, a.invert(&doc).unwrap().apply(&after_a).unwrap());
+            // wire format
+            assert_eq!(a, Json0Operation::from_value(&a.to_value()).unwrap());
+            // transform
+            check_transform(&doc, &a, &b);
+        }
+    }
+}
@@ -0,0 +1,487 @@
+//! # OT 类型
+//!
+//! 参考 [ShareDB 的 ottypes](https://github.com/ottypes/docs)，将一种文档类型的 OT 算法
+//! （apply、compose、transform、invert、normalize）抽象为 [`OtType`] trait，
+//! 服务端、客户端、撤销管理等机制只需针对该 trait 实现一次，即可用于任意文档类型。
+//!
+//! 通过 [`TypeRegistry`] 可以按名称注册、查找类型，此时快照与操作以 `dyn Any` 传递。
+//!
+//! # Example
+//! ```
+//! use ot_rs::core::{Side, TextOperation};
+//! use ot_rs::types::OtType;
+//!
+//! fn converge<T: OtType>(snapshot: &T::Snapshot, a: &T::Op, b: &T::Op) -> T::Snapshot {
+//!     let (a_prime, b_prime) = T::transform_pair(a, b, Side::Left).unwrap();
+//!     let left = T::apply(&T::apply(snapshot, a).unwrap(), &b_prime).unwrap();
+//!     let right = T::apply(&T::apply(snapshot, b).unwrap(), &a_prime).unwrap();
+//!     assert!(left == right);
+//!     return left;
+//! }
+//!
+//! let mut a = TextOperation::new();
+//! a.insert("x").retain(2);
+//! let mut b = TextOperation::new();
+//! b.retain(2).insert("y");
+//! assert_eq!("xaby", converge::<TextOperation>(&"ab".to_string(), &a, &b));
+//! ```
+
+#[cfg(feature = "json")]
+pub mod json0;
+#[cfg(feature = "rich-text")]
+pub mod rich_text;
+
+use crate::core::{
+    Bytes, Chars, LengthUnit, Meta, OperationError, Side, TextOperation, Utf16, WrappedOperation,
+};
+use std::any::Any;
+use std::collections::HashMap;
+
+/// 一种文档类型的 OT 算法
+pub trait OtType {
+    /// 类型的名称，用于在 [`TypeRegistry`] 中注册
+    const NAME: &'static str;
+
+    /// 文档快照的类型
+    type Snapshot: Clone + PartialEq;
+    /// 操作的类型
+    type Op: Clone;
+
+    /// 创建一个空文档
+    fn create() -> Self::Snapshot;
+
+    /// 将操作应用到快照上，返回新的快照
+    fn apply(snapshot: &Self::Snapshot, op: &Self::Op) -> Result<Self::Snapshot, OperationError>;
+
+    /// 合并连续的两个操作，满足 `apply(apply(s, a), b) = apply(s, compose(a, b))`
+    fn compose(a: &Self::Op, b: &Self::Op) -> Result<Self::Op, OperationError>;
+
+    /// 将 `op` 针对基于同一版本的 `other` 进行转换，得到可以在 `other` 之后应用的操作。
+    /// 双方在同一位置插入时，由 `side` 决定 `op` 的插入是否在前，参见 [`Side`]
+    fn transform(op: &Self::Op, other: &Self::Op, side: Side) -> Result<Self::Op, OperationError>;
+
+    /// 生成操作基于快照 `snapshot` 的逆操作
+    fn invert(snapshot: &Self::Snapshot, op: &Self::Op) -> Result<Self::Op, OperationError>;
+
+    /// 将操作规范化（例如合并相邻的原子操作）
+    fn normalize(op: Self::Op) -> Self::Op;
+
+    /// 操作是否不产生任何影响
+    fn is_noop(_op: &Self::Op) -> bool {
+        false
+    }
+
+    /// 同时转换两个基于同一版本的操作，返回 `(a', b')`，满足 `apply(apply(s, a), b') = apply(apply(s, b), a')`
+    fn transform_pair(
+        a: &Self::Op,
+        b: &Self::Op,
+        side: Side,
+    ) -> Result<(Self::Op, Self::Op), OperationError> {
+        let a_prime = Self::transform(a, b, side)?;
+        let b_prime = Self::transform(b, a, side.opposite())?;
+        return Ok((a_prime, b_prime));
+    }
+}
+
+/// 各长度单位下，本模块中的类型作为 [`OtType`] 时的名称。
+///
+/// 以不同单位计算长度的操作互不兼容，因此名称互不相同，以免在 [`TypeRegistry`] 中相互替换：
+/// 以 [`Chars`] 为单位时使用不带后缀的名称，其余单位在名称后追加后缀，
+/// 例如 `TextOperation<Utf16>` 的名称为 `text-utf16`，`WrappedOperation<M, Bytes>` 的名称为 `text-wrapped-utf8`。
+///
+/// 自定义的长度单位需要实现该 trait 才能用于这些类型的 `OtType`；
+/// 与其他实现交换数据时若需要使用约定的名称（例如 ShareDB 的 `json0`），可以通过 [`TypeRegistry::register_as`] 指定。
+/// # Example
+/// ```
+/// use ot_rs::core::{Chars, Utf16};
+/// use ot_rs::types::UnitTypeNames;
+/// assert_eq!("text", Chars::TEXT);
+/// assert_eq!("text-utf16", Utf16::TEXT);
+/// assert_eq!("text-wrapped-utf16", Utf16::TEXT_WRAPPED);
+/// ```
+pub trait UnitTypeNames: LengthUnit {
+    /// [`TextOperation`] 的名称
+    const TEXT: &'static str;
+    /// [`WrappedOperation`] 的名称
+    const TEXT_WRAPPED: &'static str;
+    /// `RichTextOperation` 的名称
+    const RICH_TEXT: &'static str;
+    /// `Json0Operation` 的名称
+    const JSON0: &'static str;
+}
+
+macro_rules! impl_unit_type_names {
+    ($unit:ty, $suffix:literal) => {
+        impl UnitTypeNames for $unit {
+            const TEXT: &'static str = concat!("text", $suffix);
+            const TEXT_WRAPPED: &'static str = concat!("text-wrapped", $suffix);
+            const RICH_TEXT: &'static str = concat!("rich-text", $suffix);
+            const JSON0: &'static str = concat!("json0", $suffix);
+        }
+    };
+}
+
+impl_unit_type_names!(Chars, "");
+impl_unit_type_names!(Utf16, "-utf16");
+impl_unit_type_names!(Bytes, "-utf8");
+#[cfg(feature = "graphemes")]
+impl_unit_type_names!(crate::core::Graphemes, "-graphemes");
+
+impl<U: UnitTypeNames> OtType for TextOperation<U> {
+    const NAME: &'static str = U::TEXT;
+
+    type Snapshot = String;
+    type Op = TextOperation<U>;
+
+    fn create() -> String {
+        String::new()
+    }
+
+    fn apply(snapshot: &String, op: &TextOperation<U>) -> Result<String, OperationError> {
+        op.apply(snapshot.as_str())
+    }
+
+    fn compose(
+        a: &TextOperation<U>,
+        b: &TextOperation<U>,
+    ) -> Result<TextOperation<U>, OperationError> {
+        a.compose(b)
+    }
+
+    fn transform(
+        op: &TextOperation<U>,
+        other: &TextOperation<U>,
+        side: Side,
+    ) -> Result<TextOperation<U>, OperationError> {
+        return Ok(op.transform_with_side(other, side)?.0);
+    }
+
+    fn transform_pair(
+        a: &TextOperation<U>,
+        b: &TextOperation<U>,
+        side: Side,
+    ) -> Result<(TextOperation<U>, TextOperation<U>), OperationError> {
+        a.transform_with_side(b, side)
+    }
+
+    fn invert(
+        snapshot: &String,
+        op: &TextOperation<U>,
+    ) -> Result<TextOperation<U>, OperationError> {
+        op.invert(snapshot.as_str())
+    }
+
+    fn normalize(op: TextOperation<U>) -> TextOperation<U> {
+        // 通过 retain/insert/delete 构造的操作总是规范的
+        op
+    }
+
+    fn is_noop(op: &TextOperation<U>) -> bool {
+        op.is_noop()
+    }
+}
+
+impl<M: Meta, U: UnitTypeNames> OtType for WrappedOperation<M, U> {
+    /// 以不同的元数据注册多个 `WrappedOperation` 时，需要通过 `register_as` 指定名称
+    const NAME: &'static str = U::TEXT_WRAPPED;
+
+    type Snapshot = String;
+    type Op = WrappedOperation<M, U>;
+
+    fn create() -> String {
+        String::new()
+    }
+
+    fn apply(snapshot: &String, op: &WrappedOperation<M, U>) -> Result<String, OperationError> {
+        op.apply(snapshot.as_str())
+    }
+
+    fn compose(
+        a: &WrappedOperation<M, U>,
+        b: &WrappedOperation<M, U>,
+    ) -> Result<WrappedOperation<M, U>, OperationError> {
+        a.compose(b)
+    }
+
+    fn transform(
+        op: &WrappedOperation<M, U>,
+        other: &WrappedOperation<M, U>,
+        side: Side,
+    ) -> Result<WrappedOperation<M, U>, OperationError> {
+        return Ok(op.transform_with_side(other, side)?.0);
+    }
+
+    fn transform_pair(
+        a: &WrappedOperation<M, U>,
+        b: &WrappedOperation<M, U>,
+        side: Side,
+    ) -> Result<(WrappedOperation<M, U>, WrappedOperation<M, U>), OperationError> {
+        a.transform_with_side(b, side)
+    }
+
+    fn invert(
+        snapshot: &String,
+        op: &WrappedOperation<M, U>,
+    ) -> Result<WrappedOperation<M, U>, OperationError> {
+        op.invert(snapshot.as_str())
+    }
+
+    fn normalize(op: WrappedOperation<M, U>) -> WrappedOperation<M, U> {
+        op
+    }
+
+    fn is_noop(op: &WrappedOperation<M, U>) -> bool {
+        op.is_noop()
+    }
+}
+
+/// 类型擦除后的 [`OtType`]，快照与操作以 `dyn Any` 传递；
+/// 快照或操作的实际类型与该类型不一致时返回 `TypeMismatch`
+pub trait DynOtType {
+    /// 类型的名称
+    fn name(&self) -> &str;
+
+    /// 创建一个空文档
+    fn create(&self) -> Box<dyn Any>;
+
+    /// 参见 [`OtType::apply`]
+    fn apply(&self, snapshot: &dyn Any, op: &dyn Any) -> Result<Box<dyn Any>, OperationError>;
+
+    /// 参见 [`OtType::compose`]
+    fn compose(&self, a: &dyn Any, b: &dyn Any) -> Result<Box<dyn Any>, OperationError>;
+
+    /// 参见 [`OtType::transform`]
+    fn transform(
+        &self,
+        op: &dyn Any,
+        other: &dyn Any,
+        side: Side,
+    ) -> Result<Box<dyn Any>, OperationError>;
+
+    /// 参见 [`OtType::invert`]
+    fn invert(&self, snapshot: &dyn Any, op: &dyn Any) -> Result<Box<dyn Any>, OperationError>;
+}
+
+/// 将 `OtType` 包装为 `DynOtType`
+struct Erased<T> {
+    name: String,
+    ty: std::marker::PhantomData<T>,
+}
+
+fn downcast<T: 'static>(value: &dyn Any) -> Result<&T, OperationError> {
+    value
+        .downcast_ref::<T>()
+        .ok_or(OperationError::TypeMismatch)
+}
+
+impl<T> DynOtType for Erased<T>
+where
+    T: OtType,
+    T::Snapshot: 'static,
+    T::Op: 'static,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn create(&self) -> Box<dyn Any> {
+        Box::new(T::create())
+    }
+
+    fn apply(&self, snapshot: &dyn Any, op: &dyn Any) -> Result<Box<dyn Any>, OperationError> {
+        return Ok(Box::new(T::apply(downcast(snapshot)?, downcast(op)?)?));
+    }
+
+    fn compose(&self, a: &dyn Any, b: &dyn Any) -> Result<Box<dyn Any>, OperationError> {
+        return Ok(Box::new(T::compose(downcast(a)?, downcast(b)?)?));
+    }
+
+    fn transform(
+        &self,
+        op: &dyn Any,
+        other: &dyn Any,
+        side: Side,
+    ) -> Result<Box<dyn Any>, OperationError> {
+        return Ok(Box::new(T::transform(
+            downcast(op)?,
+            downcast(other)?,
+            side,
+        )?));
+    }
+
+    fn invert(&self, snapshot: &dyn Any, op: &dyn Any) -> Result<Box<dyn Any>, OperationError> {
+        return Ok(Box::new(T::invert(downcast(snapshot)?, downcast(op)?)?));
+    }
+}
+
+/// 按名称注册、查找 OT 类型
+/// # Example
+/// ```
+/// use ot_rs::core::TextOperation;
+/// use ot_rs::types::TypeRegistry;
+/// let mut registry = TypeRegistry::new();
+/// registry.register::<TextOperation>();
+/// let text = registry.get("text").unwrap();
+///
+/// let mut ops = TextOperation::new();
+/// ops.insert("hi");
+/// let snapshot = text.apply(text.create().as_ref(), &ops).unwrap();
+/// assert_eq!(Some(&"hi".to_string()), snapshot.downcast_ref::<String>());
+/// ```
+#[derive(Default)]
+pub struct TypeRegistry {
+    types: HashMap<String, Box<dyn DynOtType>>,
+}
+
+impl TypeRegistry {
+    /// 构造一个空的注册表
+    pub fn new() -> TypeRegistry {
+        return TypeRegistry::default();
+    }
+
+    /// 以 `T::NAME` 为名称注册一个类型，同名的类型将被替换
+    pub fn register<T>(&mut self) -> &mut TypeRegistry
+    where
+        T: OtType + 'static,
+        T::Snapshot: 'static,
+        T::Op: 'static,
+    {
+        return self.register_as::<T>(T::NAME);
+    }
+
+    /// 以给定的名称注册一个类型（例如以 `text-js` 注册 `TextOperation<Utf16>`），同名的类型将被替换
+    pub fn register_as<T>(&mut self, name: &str) -> &mut TypeRegistry
+    where
+        T: OtType + 'static,
+        T::Snapshot: 'static,
+        T::Op: 'static,
+    {
+        let erased = Erased::<T> {
+            name: name.to_string(),
+            ty: std::marker::PhantomData,
+        };
+        self.types.insert(name.to_string(), Box::new(erased));
+        return self;
+    }
+
+    /// 按名称查找类型
+    pub fn get(&self, name: &str) -> Option<&dyn DynOtType> {
+        self.types.get(name).map(|t| t.as_ref())
+    }
+
+    /// 全部已注册的类型的名称
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.types.keys().map(|k| k.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{OtType, TypeRegistry};
+    use crate::core::test_util::random_string;
+    use crate::core::{OperationError, Side, TextOperation, Utf16, WrappedOperation};
+
+    /// 对任意 OtType 检查 transform、compose、invert 的性质
+    fn check_type<T: OtType>(snapshot: &T::Snapshot, a: &T::Op, b: &T::Op) {
+        for &side in &[Side::Left, Side::Right] {
+            let (a_prime, b_prime) = T::transform_pair(a, b, side).unwrap();
+            assert!(
+                T::apply(&T::apply(snapshot, a).unwrap(), &b_prime).unwrap()
+                    == T::apply(&T::apply(snapshot, b).unwrap(), &a_prime).unwrap()
+            );
+            let ab = T::compose(a, &b_prime).unwrap();
+            assert!(
+                T::apply(snapshot, &ab).unwrap()
+                    == T::apply(&T::apply(snapshot, a).unwrap(), &b_prime).unwrap()
+            );
+        }
+        let after = T::apply(snapshot, a).unwrap();
+        let inverse = T::invert(snapshot, a).unwrap();
+        assert!(T::apply(&after, &inverse).unwrap() == *snapshot);
+    }
+
+    #[test]
+    fn test_text_type() {
+        for _ in 0..100 {
+            let base = random_string(20);
+            let a: TextOperation = TextOperation::diff(&base, &random_string(20));
+            let b: TextOperation = TextOperation::diff(&base, &random_string(20));
+            check_type::<TextOperation>(&base, &a, &b);
+            let a = TextOperation::<Utf16>::diff(&base, &random_string(20));
+            let b = TextOperation::<Utf16>::diff(&base, &random_string(20));
+            check_type::<TextOperation<Utf16>>(&base, &a, &b);
+        }
+    }
+
+    #[test]
+    fn test_registry() {
+        let mut registry = TypeRegistry::new();
+        // 不同长度单位的 TextOperation 名称不同，不会相互替换
+        registry
+            .register::<TextOperation>()
+            .register::<TextOperation<Utf16>>()
+            .register_as::<TextOperation<Utf16>>("text-js");
+        let mut names: Vec<_> = registry.names().collect();
+        names.sort_unstable();
+        assert_eq!(vec!["text", "text-js", "text-utf16"], names);
+        assert!(registry.get("json0").is_none());
+
+        // WrappedOperation 不会替换 TextOperation，不同单位的 WrappedOperation 也不会相互替换
+        registry
+            .register::<WrappedOperation<()>>()
+            .register::<WrappedOperation<(), Utf16>>();
+        assert_eq!("text", registry.get("text").unwrap().name());
+        assert_eq!("text-wrapped", registry.get("text-wrapped").unwrap().name());
+        assert_eq!(
+            "text-wrapped-utf16",
+            registry.get("text-wrapped-utf16").unwrap().name()
+        );
+        #[cfg(feature = "json")]
+        {
+            use super::json0::Json0Operation;
+            registry
+                .register::<Json0Operation>()
+                .register::<Json0Operation<Utf16>>();
+            for name in ["json0", "json0-utf16"] {
+                assert_eq!(name, registry.get(name).unwrap().name());
+            }
+        }
+        #[cfg(feature = "rich-text")]
+        {
+            use super::rich_text::RichTextOperation;
+            registry
+                .register::<RichTextOperation>()
+                .register::<RichTextOperation<Utf16>>();
+            for name in ["rich-text", "rich-text-utf16"] {
+                assert_eq!(name, registry.get(name).unwrap().name());
+            }
+        }
+
+        let text = registry.get("text-utf16").unwrap();
+        assert_eq!("text-utf16", text.name());
+        let mut ops = TextOperation::<Utf16>::with_unit();
+        ops.insert("😄");
+        let snapshot = text.apply(&"".to_string(), &ops).unwrap();
+        assert_eq!(Some(&"😄".to_string()), snapshot.downcast_ref());
+        let inverse = text.invert(&"".to_string(), &ops).unwrap();
+        let composed = text.compose(&ops, inverse.as_ref()).unwrap();
+        assert!(composed
+            .downcast_ref::<TextOperation<Utf16>>()
+            .unwrap()
+            .is_noop());
+        let transformed = text.transform(&ops, &ops, Side::Right).unwrap();
+        assert_eq!(
+            Some(&"😄😄".to_string()),
+            text.apply(snapshot.as_ref(), transformed.as_ref())
+                .unwrap()
+                .downcast_ref()
+        );
+        // 以 Chars 为单位的操作不能交给 Utf16 的类型
+        assert_eq!(
+            OperationError::TypeMismatch,
+            text.apply(&"".to_string(), &TextOperation::new())
+                .unwrap_err()
+        );
+    }
+}
@@ -0,0 +1,1049 @@
+//! # 富文本 OT 类型（兼容 Quill Delta）
+//!
+//! 实现上参考了 [quill-delta](https://github.com/slab/delta/blob/main/src/Delta.ts)（需要开启 `rich-text` feature）。
+//!
+//! 与 `TextOperation` 不同，[`RichTextOperation`] 的 `Retain` 与 `Insert` 可以携带属性（例如 `bold`、`link`、`header`）：
+//! - `Insert(text, attributes)`：插入带有属性的文本
+//! - `InsertEmbed(embed, attributes)`：插入带有属性的嵌入对象（例如图片、@提及、公式），
+//!   嵌入对象是一个 JSON 对象（例如 `{"image": "https://..."}`），无论长度单位是什么都恰好占 1 个长度单位，且不可分割
+//! - `Retain(n, attributes)`：保留 n 个长度单位，并将 `attributes` 合并到这些内容的属性上，值为 `null` 的属性将被移除
+//! - `Delete(n)`：删除 n 个长度单位
+//!
+//! 与 Quill Delta 一致，操作末尾隐含保留文档剩余的全部内容，因此操作不记录 `base_length`；
+//! 文档本身也是一个只包含 `Insert` 与 `InsertEmbed` 的 `RichTextOperation`，即由带有属性的文本片段与嵌入对象组成。
+//!
+//! 注意：Quill 以 UTF-16 码元计算长度，与 Quill 交换数据时应使用 `RichTextOperation<Utf16>`。
+//!
+//! # Example
+//! ```
+//! use ot_rs::core::Side;
+//! use ot_rs::types::rich_text::RichTextOperation;
+//! use serde_json::json;
+//!
+//! let doc: RichTextOperation = serde_json::from_value(json!({"ops": [{"insert": "Hello world\n"}]})).unwrap();
+//! // 用户 a 将 "Hello" 加粗，用户 b 将 "world" 改为斜体并在末尾插入 "!"
+//! let a: RichTextOperation = serde_json::from_value(json!([{"retain": 5, "attributes": {"bold": true}}])).unwrap();
+//! let b: RichTextOperation = serde_json::from_value(json!([
+//!     {"retain": 6},
+//!     {"retain": 5, "attributes": {"italic": true}},
+//!     {"insert": "!"},
+//! ])).unwrap();
+//!
+//! let (a_prime, b_prime) = a.transform_with_side(&b, Side::Left).unwrap();
+//! let left = doc.apply(&a).unwrap().apply(&b_prime).unwrap();
+//! let right = doc.apply(&b).unwrap().apply(&a_prime).unwrap();
+//! assert_eq!(left, right);
+//! assert_eq!(
+//!     json!({"ops": [
+//!         {"insert": "Hello", "attributes": {"bold": true}},
+//!         {"insert": " "},
+//!         {"insert": "world", "attributes": {"italic": true}},
+//!         {"insert": "!\n"},
+//!     ]}),
+//!     left.to_value()
+//! );
+//! ```
+
+use crate::core::{Chars, LengthUnit, OperationError, Side};
+use crate::types::{OtType, UnitTypeNames};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::marker::PhantomData;
+
+/// 属性表，例如 `{"bold": true, "link": "https://..."}`；为空表示没有属性
+pub type Attributes = Map<String, Value>;
+
+/// 富文本的原子操作
+#[derive(Debug, Clone, PartialEq)]
+pub enum RichTextComponent {
+    /// 保留 n 个长度单位，并修改其属性
+    Retain(usize, Attributes),
+    /// 插入带有属性的文本
+    Insert(String, Attributes),
+    /// 插入带有属性的嵌入对象，占 1 个长度单位
+    InsertEmbed(Value, Attributes),
+    /// 删除 n 个长度单位
+    Delete(usize),
+}
+
+impl RichTextComponent {
+    fn len<U: LengthUnit>(&self) -> usize {
+        match self {
+            &Self::Retain(n, _) | &Self::Delete(n) => n,
+            Self::Insert(text, _) => U::len(text),
+            Self::InsertEmbed(..) => 1,
+        }
+    }
+
+    fn is_insert(&self) -> bool {
+        matches!(self, Self::Insert(..) | Self::InsertEmbed(..))
+    }
+
+    fn attributes(&self) -> Option<&Attributes> {
+        match self {
+            Self::Retain(_, attributes)
+            | Self::Insert(_, attributes)
+            | Self::InsertEmbed(_, attributes) => Some(attributes),
+            Self::Delete(_) => None,
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        let mut map = Map::new();
+        match self {
+            &Self::Retain(n, _) => map.insert("retain".to_string(), Value::from(n)),
+            Self::Insert(text, _) => map.insert("insert".to_string(), Value::from(text.as_str())),
+            Self::InsertEmbed(embed, _) => map.insert("insert".to_string(), embed.clone()),
+            &Self::Delete(n) => map.insert("delete".to_string(), Value::from(n)),
+        };
+        if let Some(attributes) = self.attributes().filter(|a| !a.is_empty()) {
+            map.insert("attributes".to_string(), Value::Object(attributes.clone()));
+        }
+        return Value::Object(map);
+    }
+
+    fn from_value(value: &Value) -> Result<RichTextComponent, OperationError> {
+        let map = value.as_object().ok_or(OperationError::DeltaInvalid)?;
+        let attributes = match map.get("attributes") {
+            None | Some(Value::Null) => Attributes::new(),
+            Some(Value::Object(attributes)) => attributes.clone(),
+            Some(_) => return Err(OperationError::DeltaInvalid),
+        };
+        let length = |n: &Value| {
+            n.as_u64()
+                .map(|n| n as usize)
+                .ok_or(OperationError::DeltaInvalid)
+        };
+        let component = match (map.get("insert"), map.get("retain"), map.get("delete")) {
+            (Some(Value::String(text)), None, None) => Self::Insert(text.clone(), attributes),
+            (Some(embed @ Value::Object(_)), None, None) => {
+                Self::InsertEmbed(embed.clone(), attributes)
+            }
+            (None, Some(n), None) => Self::Retain(length(n)?, attributes),
+            (None, None, Some(n)) if attributes.is_empty() => Self::Delete(length(n)?),
+            _ => return Err(OperationError::DeltaInvalid),
+        };
+        return Ok(component);
+    }
+}
+
+/// 富文本上的操作，参见[模块文档](self)
+#[derive(Debug, Clone, PartialEq)]
+pub struct RichTextOperation<U: LengthUnit = Chars> {
+    ops: Vec<RichTextComponent>,
+    unit: PhantomData<U>,
+}
+
+impl<U: LengthUnit> Default for RichTextOperation<U> {
+    fn default() -> Self {
+        return RichTextOperation {
+            ops: vec![],
+            unit: PhantomData,
+        };
+    }
+}
+
+impl RichTextOperation {
+    /// 构造一个空操作
+    pub fn new() -> RichTextOperation {
+        return RichTextOperation::default();
+    }
+}
+
+impl<U: LengthUnit> RichTextOperation<U> {
+    /// 构造一个空操作，并指定长度单位
+    pub fn with_unit() -> RichTextOperation<U> {
+        return RichTextOperation::default();
+    }
+
+    /// 由原子操作列表构造一个操作，相邻的可合并的原子操作将被合并，末尾不带属性的 `Retain` 将被移除
+    pub fn from_components<I: IntoIterator<Item = RichTextComponent>>(
+        components: I,
+    ) -> RichTextOperation<U> {
+        let mut operation = RichTextOperation::with_unit();
+        for component in components {
+            operation.push(component);
+        }
+        operation.chop();
+        return operation;
+    }
+
+    /// 原子操作列表
+    pub fn ops(&self) -> &[RichTextComponent] {
+        &self.ops
+    }
+
+    /// 操作是否不产生任何影响
+    pub fn is_noop(&self) -> bool {
+        self.ops
+            .iter()
+            .all(|c| matches!(c, RichTextComponent::Retain(_, a) if a.is_empty()))
+    }
+
+    /// 操作所要求的文档的最小长度，即 `Retain` 与 `Delete` 的长度之和
+    pub fn base_length(&self) -> usize {
+        self.ops
+            .iter()
+            .filter(|c| !c.is_insert())
+            .map(|c| c.len::<U>())
+            .sum()
+    }
+
+    /// 操作是否只包含 `Insert`，即是否可以作为一个文档
+    pub fn is_document(&self) -> bool {
+        self.ops.iter().all(|c| c.is_insert())
+    }
+
+    /// 全部插入的文本（忽略属性与嵌入对象）
+    /// # Example
+    /// ```
+    /// use ot_rs::types::rich_text::RichTextOperation;
+    /// use serde_json::json;
+    /// let mut doc = RichTextOperation::new();
+    /// doc.insert("Hello ").insert_with("world", json!({"bold": true}).as_object().unwrap().clone());
+    /// assert_eq!("Hello world", doc.text());
+    /// ```
+    pub fn text(&self) -> String {
+        self.ops
+            .iter()
+            .filter_map(|c| match c {
+                RichTextComponent::Insert(text, _) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// 保留 n 个长度单位
+    pub fn retain(&mut self, n: usize) -> &mut RichTextOperation<U> {
+        self.push(RichTextComponent::Retain(n, Attributes::new()));
+        return self;
+    }
+
+    /// 保留 n 个长度单位，并修改其属性，值为 `null` 的属性将被移除
+    pub fn retain_with(&mut self, n: usize, attributes: Attributes) -> &mut RichTextOperation<U> {
+        self.push(RichTextComponent::Retain(n, attributes));
+        return self;
+    }
+
+    /// 插入文本
+    pub fn insert<T: Into<String>>(&mut self, text: T) -> &mut RichTextOperation<U> {
+        self.push(RichTextComponent::Insert(text.into(), Attributes::new()));
+        return self;
+    }
+
+    /// 插入带有属性的文本
+    pub fn insert_with<T: Into<String>>(
+        &mut self,
+        text: T,
+        attributes: Attributes,
+    ) -> &mut RichTextOperation<U> {
+        self.push(RichTextComponent::Insert(text.into(), attributes));
+        return self;
+    }
+
+    /// 插入带有属性的嵌入对象
+    /// # Example
+    /// ```
+    /// use ot_rs::types::rich_text::{Attributes, RichTextOperation};
+    /// use serde_json::json;
+    /// let mut doc = RichTextOperation::new();
+    /// doc.insert("ab");
+    /// let mut ops = RichTextOperation::new();
+    /// ops.retain(1).insert_embed(json!({"image": "cat.png"}), Attributes::new());
+    /// let doc = doc.apply(&ops).unwrap();
+    /// assert_eq!(
+    ///     json!({"ops": [{"insert": "a"}, {"insert": {"image": "cat.png"}}, {"insert": "b"}]}),
+    ///     doc.to_value()
+    /// );
+    /// assert_eq!(3, doc.length());
+    /// assert_eq!("ab", doc.text());
+    /// ```
+    pub fn insert_embed(
+        &mut self,
+        embed: Value,
+        attributes: Attributes,
+    ) -> &mut RichTextOperation<U> {
+        self.push(RichTextComponent::InsertEmbed(embed, attributes));
+        return self;
+    }
+
+    /// 删除 n 个长度单位
+    pub fn delete(&mut self, n: usize) -> &mut RichTextOperation<U> {
+        self.push(RichTextComponent::Delete(n));
+        return self;
+    }
+
+    /// 追加一个原子操作：空的原子操作将被忽略，相邻且属性相同的原子操作将被合并，
+    /// 紧跟在 `Delete` 之后的 `Insert` 将被移动到 `Delete` 之前
+    fn push(&mut self, component: RichTextComponent) {
+        use RichTextComponent::*;
+        if component.len::<U>() == 0 {
+            return;
+        }
+        let mut index = self.ops.len();
+        match (self.ops.last_mut(), &component) {
+            (Some(Delete(n)), &Delete(m)) => {
+                *n += m;
+                return;
+            }
+            (Some(Delete(_)), Insert(..)) | (Some(Delete(_)), InsertEmbed(..)) => index -= 1,
+            _ => {}
+        }
+        if index > 0 {
+            match (&mut self.ops[index - 1], &component) {
+                (Insert(text, attributes), Insert(more, a)) if attributes == a => {
+                    text.push_str(more);
+                    return;
+                }
+                (Retain(n, attributes), &Retain(m, ref a)) if attributes == a => {
+                    *n += m;
+                    return;
+                }
+                _ => {}
+            }
+        }
+        self.ops.insert(index, component);
+    }
+
+    /// 移除末尾不带属性的 `Retain`
+    fn chop(&mut self) {
+        if let Some(RichTextComponent::Retain(_, attributes)) = self.ops.last() {
+            if attributes.is_empty() {
+                self.ops.pop();
+            }
+        }
+    }
+
+    /// 转换为 Quill Delta 格式，即 `{"ops": [...]}`
+    pub fn to_value(&self) -> Value {
+        let mut map = Map::new();
+        map.insert(
+            "ops".to_string(),
+            Value::Array(self.ops.iter().map(|c| c.to_value()).collect()),
+        );
+        return Value::Object(map);
+    }
+
+    /// 从 Quill Delta 格式解析，`{"ops": [...]}` 与 `[...]` 均被接受
+    pub fn from_value(value: &Value) -> Result<RichTextOperation<U>, OperationError> {
+        let ops = match value {
+            Value::Array(ops) => ops,
+            Value::Object(map) => map
+                .get("ops")
+                .and_then(|ops| ops.as_array())
+                .ok_or(OperationError::DeltaInvalid)?,
+            _ => return Err(OperationError::DeltaInvalid),
+        };
+        let components = ops
+            .iter()
+            .map(RichTextComponent::from_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        return Ok(RichTextOperation::from_components(components));
+    }
+
+    /// 将操作应用到文档 `doc`（只包含 `Insert` 的操作）上
+    /// # Example
+    /// ```
+    /// use ot_rs::core::OperationError;
+    /// use ot_rs::types::rich_text::RichTextOperation;
+    /// use serde_json::json;
+    /// let mut doc = RichTextOperation::new();
+    /// doc.insert("abc");
+    /// let mut ops = RichTextOperation::new();
+    /// ops.retain_with(1, json!({"bold": true}).as_object().unwrap().clone()).delete(1);
+    /// assert_eq!(
+    ///     json!({"ops": [{"insert": "a", "attributes": {"bold": true}}, {"insert": "c"}]}),
+    ///     doc.apply(&ops).unwrap().to_value()
+    /// );
+    /// ops.retain(2);
+    /// assert_eq!(Err(OperationError::OperationMoreLeftString), doc.apply(&ops));
+    /// ```
+    pub fn apply(
+        &self,
+        operation: &RichTextOperation<U>,
+    ) -> Result<RichTextOperation<U>, OperationError> {
+        if !self.is_document() {
+            return Err(OperationError::DeltaInvalid);
+        }
+        if operation.base_length() > self.length() {
+            return Err(OperationError::OperationMoreLeftString);
+        }
+        return self.compose(operation);
+    }
+
+    /// 全部原子操作的长度之和，对于文档即文档的长度
+    pub fn length(&self) -> usize {
+        self.ops.iter().map(|c| c.len::<U>()).sum()
+    }
+
+    /// 合并连续的两个操作
+    /// # Example
+    /// ```
+    /// use ot_rs::types::rich_text::RichTextOperation;
+    /// use serde_json::json;
+    /// let bold = json!({"bold": true}).as_object().unwrap().clone();
+    /// let mut a = RichTextOperation::new();
+    /// a.insert("ab");
+    /// let mut b = RichTextOperation::new();
+    /// b.retain_with(1, bold).delete(1);
+    /// assert_eq!(
+    ///     json!({"ops": [{"insert": "a", "attributes": {"bold": true}}]}),
+    ///     a.compose(&b).unwrap().to_value()
+    /// );
+    /// ```
+    pub fn compose(
+        &self,
+        operation2: &RichTextOperation<U>,
+    ) -> Result<RichTextOperation<U>, OperationError> {
+        use RichTextComponent::*;
+        let mut this = Cursor::<U>::new(&self.ops);
+        let mut other = Cursor::<U>::new(&operation2.ops);
+        let mut composed = RichTextOperation::with_unit();
+        while this.has_next() || other.has_next() {
+            if other.peek_is_insert() {
+                composed.push(other.next(usize::MAX)?);
+            } else if this.peek_is_delete() {
+                composed.push(this.next(usize::MAX)?);
+            } else {
+                let length = this.peek_len().min(other.peek_len());
+                let this_op = this.next(length)?;
+                let other_op = other.next(length)?;
+                match (this_op, other_op) {
+                    (Retain(_, a), Retain(_, b)) => {
+                        composed.push(Retain(length, compose_attributes(&a, &b, true)));
+                    }
+                    (Insert(text, a), Retain(_, b)) => {
+                        composed.push(Insert(text, compose_attributes(&a, &b, false)));
+                    }
+                    (InsertEmbed(embed, a), Retain(_, b)) => {
+                        composed.push(InsertEmbed(embed, compose_attributes(&a, &b, false)));
+                    }
+                    (Retain(..), Delete(_)) => composed.push(Delete(length)),
+                    // 删除刚刚插入的内容，两者相互抵消
+                    _ => {}
+                }
+            }
+        }
+        composed.chop();
+        return Ok(composed);
+    }
+
+    /// 以 Quill 的语义将 `operation2` 针对 `self` 进行转换：`priority` 为 `true` 时 `self` 优先
+    fn transform_other(
+        &self,
+        operation2: &RichTextOperation<U>,
+        priority: bool,
+    ) -> Result<RichTextOperation<U>, OperationError> {
+        use RichTextComponent::*;
+        let mut this = Cursor::<U>::new(&self.ops);
+        let mut other = Cursor::<U>::new(&operation2.ops);
+        let mut transformed = RichTextOperation::with_unit();
+        while this.has_next() || other.has_next() {
+            if this.peek_is_insert() && (priority || !other.peek_is_insert()) {
+                let inserted = this.next(usize::MAX)?;
+                transformed.retain(inserted.len::<U>());
+            } else if other.peek_is_insert() {
+                transformed.push(other.next(usize::MAX)?);
+            } else {
+                let length = this.peek_len().min(other.peek_len());
+                let this_op = this.next(length)?;
+                let other_op = other.next(length)?;
+                match (this_op, other_op) {
+                    // self 的删除使得 operation2 的删除或保留失去意义
+                    (Delete(_), _) => {}
+                    (_, Delete(_)) => transformed.push(Delete(length)),
+                    (Retain(_, a), Retain(_, b)) => {
+                        transformed.retain_with(length, transform_attributes(&a, &b, priority));
+                    }
+                    _ => unreachable!("inserts have been consumed"),
+                }
+            }
+        }
+        transformed.chop();
+        return Ok(transformed);
+    }
+
+    /// 转换基于同一文档的两个操作，返回 `(a', b')`。
+    /// 双方在同一位置插入、或同时修改同一段内容的同一个属性时，由 `side` 决定哪一方优先：
+    /// `Side::Left` 时 `self` 的插入在前，且 `self` 设置的属性获胜
+    /// # Example
+    /// ```
+    /// use ot_rs::core::Side;
+    /// use ot_rs::types::rich_text::RichTextOperation;
+    /// use serde_json::json;
+    /// let color = |c: &str| json!({"color": c}).as_object().unwrap().clone();
+    /// let mut a = RichTextOperation::new();
+    /// a.retain_with(2, color("red"));
+    /// let mut b = RichTextOperation::new();
+    /// b.retain_with(2, color("blue"));
+    /// let (a_prime, b_prime) = a.transform_with_side(&b, Side::Left).unwrap();
+    /// assert_eq!(a, a_prime);
+    /// assert!(b_prime.is_noop());
+    /// let (a_prime, b_prime) = a.transform_with_side(&b, Side::Right).unwrap();
+    /// assert!(a_prime.is_noop());
+    /// assert_eq!(b, b_prime);
+    /// ```
+    pub fn transform_with_side(
+        &self,
+        operation2: &RichTextOperation<U>,
+        side: Side,
+    ) -> Result<(RichTextOperation<U>, RichTextOperation<U>), OperationError> {
+        let a_prime = operation2.transform_other(self, side == Side::Right)?;
+        let b_prime = self.transform_other(operation2, side == Side::Left)?;
+        return Ok((a_prime, b_prime));
+    }
+
+    /// 同 `transform_with_side(operation2, Side::Left)`
+    pub fn transform(
+        &self,
+        operation2: &RichTextOperation<U>,
+    ) -> Result<(RichTextOperation<U>, RichTextOperation<U>), OperationError> {
+        self.transform_with_side(operation2, Side::Left)
+    }
+
+    /// 将文档中的一个位置（例如光标）转换为该操作应用后的文档中对应的位置，`is_own_op` 的含义同 `TextOperation::transform_index`
+    /// # Example
+    /// ```
+    /// use ot_rs::types::rich_text::RichTextOperation;
+    /// let mut ops = RichTextOperation::new();
+    /// ops.retain(2).insert("xyz").delete(2);
+    /// assert_eq!(2, ops.transform_index(2, false));
+    /// assert_eq!(5, ops.transform_index(2, true));
+    /// assert_eq!(5, ops.transform_index(3, false));
+    /// assert_eq!(6, ops.transform_index(5, false));
+    /// ```
+    pub fn transform_index(&self, index: usize, is_own_op: bool) -> usize {
+        let mut index = index;
+        let mut offset = 0;
+        for component in &self.ops {
+            if offset > index {
+                break;
+            }
+            let length = component.len::<U>();
+            match component {
+                RichTextComponent::Delete(_) => {
+                    index -= length.min(index - offset);
+                    continue;
+                }
+                RichTextComponent::Insert(..) | RichTextComponent::InsertEmbed(..)
+                    if offset < index || is_own_op =>
+                {
+                    index += length
+                }
+                _ => {}
+            }
+            offset += length;
+        }
+        return index;
+    }
+
+    /// 基于文档 `base` 生成逆操作，被删除的内容与被修改的属性取自 `base`
+    /// # Example
+    /// ```
+    /// use ot_rs::types::rich_text::RichTextOperation;
+    /// use serde_json::json;
+    /// let doc: RichTextOperation = RichTextOperation::from_value(&json!([
+    ///     {"insert": "ab", "attributes": {"bold": true}},
+    ///     {"insert": "c"},
+    /// ])).unwrap();
+    /// let ops: RichTextOperation = RichTextOperation::from_value(&json!([
+    ///     {"retain": 1, "attributes": {"bold": null, "italic": true}},
+    ///     {"delete": 2},
+    /// ])).unwrap();
+    /// let inverse = ops.invert(&doc).unwrap();
+    /// assert_eq!(
+    ///     json!({"ops": [
+    ///         {"retain": 1, "attributes": {"bold": true, "italic": null}},
+    ///         {"insert": "b", "attributes": {"bold": true}},
+    ///         {"insert": "c"},
+    ///     ]}),
+    ///     inverse.to_value()
+    /// );
+    /// assert_eq!(doc, doc.apply(&ops).unwrap().apply(&inverse).unwrap());
+    /// ```
+    pub fn invert(
+        &self,
+        base: &RichTextOperation<U>,
+    ) -> Result<RichTextOperation<U>, OperationError> {
+        use RichTextComponent::*;
+        if !base.is_document() {
+            return Err(OperationError::DeltaInvalid);
+        }
+        let mut base_cursor = Cursor::<U>::new(&base.ops);
+        let mut inverted = RichTextOperation::with_unit();
+        for component in &self.ops {
+            match component {
+                Insert(..) | InsertEmbed(..) => {
+                    inverted.delete(component.len::<U>());
+                }
+                Retain(n, attributes) if attributes.is_empty() => {
+                    inverted.retain(*n);
+                    base_cursor.skip(*n)?;
+                }
+                &Retain(n, ref attributes) => {
+                    for slice in base_cursor.take(n)? {
+                        if let Insert(_, base_attributes) | InsertEmbed(_, base_attributes) = &slice
+                        {
+                            inverted.retain_with(
+                                slice.len::<U>(),
+                                invert_attributes(attributes, base_attributes),
+                            );
+                        }
+                    }
+                }
+                &Delete(n) => {
+                    for slice in base_cursor.take(n)? {
+                        inverted.push(slice);
+                    }
+                }
+            }
+        }
+        inverted.chop();
+        return Ok(inverted);
+    }
+}
+
+/// 依次遍历原子操作，可以从原子操作的中间切分；遍历结束后视为无限长的 `Retain`
+struct Cursor<'a, U> {
+    ops: &'a [RichTextComponent],
+    index: usize,
+    offset: usize,
+    unit: PhantomData<U>,
+}
+
+impl<'a, U: LengthUnit> Cursor<'a, U> {
+    fn new(ops: &'a [RichTextComponent]) -> Cursor<'a, U> {
+        return Cursor {
+            ops,
+            index: 0,
+            offset: 0,
+            unit: PhantomData,
+        };
+    }
+
+    fn has_next(&self) -> bool {
+        self.index < self.ops.len()
+    }
+
+    fn peek_len(&self) -> usize {
+        match self.ops.get(self.index) {
+            Some(component) => component.len::<U>() - self.offset,
+            None => usize::MAX,
+        }
+    }
+
+    fn peek_is_insert(&self) -> bool {
+        matches!(self.ops.get(self.index), Some(c) if c.is_insert())
+    }
+
+    fn peek_is_delete(&self) -> bool {
+        matches!(self.ops.get(self.index), Some(RichTextComponent::Delete(_)))
+    }
+
+    /// 取出当前原子操作的前（至多）n 个长度单位
+    fn next(&mut self, n: usize) -> Result<RichTextComponent, OperationError> {
+        let component = match self.ops.get(self.index) {
+            Some(component) => component,
+            None => return Ok(RichTextComponent::Retain(n, Attributes::new())),
+        };
+        let offset = self.offset;
+        let length = component.len::<U>();
+        let n = n.min(length - offset);
+        if offset + n == length {
+            self.index += 1;
+            self.offset = 0;
+        } else {
+            self.offset += n;
+        }
+        let next = match component {
+            RichTextComponent::Retain(_, attributes) => {
+                RichTextComponent::Retain(n, attributes.clone())
+            }
+            RichTextComponent::Insert(text, attributes) => {
+                let rest = U::split_at(text, offset)?.1;
+                RichTextComponent::Insert(U::split_at(rest, n)?.0.to_string(), attributes.clone())
+            }
+            // 嵌入对象只占 1 个长度单位，不会被切分
+            RichTextComponent::InsertEmbed(embed, attributes) => {
+                RichTextComponent::InsertEmbed(embed.clone(), attributes.clone())
+            }
+            RichTextComponent::Delete(_) => RichTextComponent::Delete(n),
+        };
+        return Ok(next);
+    }
+
+    /// 取出 n 个长度单位的原子操作，不足时返回 `OperationMoreLeftString`
+    fn take(&mut self, n: usize) -> Result<Vec<RichTextComponent>, OperationError> {
+        let mut components = vec![];
+        let mut left = n;
+        while left > 0 {
+            if !self.has_next() {
+                return Err(OperationError::OperationMoreLeftString);
+            }
+            let component = self.next(left)?;
+            left -= component.len::<U>();
+            components.push(component);
+        }
+        return Ok(components);
+    }
+
+    fn skip(&mut self, n: usize) -> Result<(), OperationError> {
+        self.take(n)?;
+        return Ok(());
+    }
+}
+
+/// 将属性修改 `b` 合并到 `a` 上，`keep_null` 为 `false` 时移除值为 `null` 的属性
+fn compose_attributes(a: &Attributes, b: &Attributes, keep_null: bool) -> Attributes {
+    let mut attributes: Attributes = b
+        .iter()
+        .filter(|(_, v)| keep_null || !v.is_null())
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    for (key, value) in a {
+        if !b.contains_key(key) {
+            attributes.insert(key.clone(), value.clone());
+        }
+    }
+    return attributes;
+}
+
+/// 生成属性修改 `attributes` 基于原有属性 `base` 的逆
+fn invert_attributes(attributes: &Attributes, base: &Attributes) -> Attributes {
+    let mut inverted = Attributes::new();
+    for (key, value) in base {
+        if attributes.contains_key(key) && attributes.get(key) != Some(value) {
+            inverted.insert(key.clone(), value.clone());
+        }
+    }
+    for key in attributes.keys() {
+        if !base.contains_key(key) && !attributes[key].is_null() {
+            inverted.insert(key.clone(), Value::Null);
+        }
+    }
+    return inverted;
+}
+
+/// 将属性修改 `b` 针对同时发生的属性修改 `a` 进行转换：`priority` 为 `true` 时 `a` 获胜
+fn transform_attributes(a: &Attributes, b: &Attributes, priority: bool) -> Attributes {
+    if !priority {
+        return b.clone();
+    }
+    return b
+        .iter()
+        .filter(|(k, _)| !a.contains_key(*k))
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+}
+
+impl<U: LengthUnit> Serialize for RichTextOperation<U> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_value().serialize(serializer)
+    }
+}
+
+impl<'de, U: LengthUnit> Deserialize<'de> for RichTextOperation<U> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        RichTextOperation::from_value(&value).map_err(serde::de::Error::custom)
+    }
+}
+
+impl<U: UnitTypeNames> OtType for RichTextOperation<U> {
+    const NAME: &'static str = U::RICH_TEXT;
+
+    type Snapshot = RichTextOperation<U>;
+    type Op = RichTextOperation<U>;
+
+    fn create() -> RichTextOperation<U> {
+        RichTextOperation::with_unit()
+    }
+
+    fn apply(
+        snapshot: &RichTextOperation<U>,
+        op: &RichTextOperation<U>,
+    ) -> Result<RichTextOperation<U>, OperationError> {
+        snapshot.apply(op)
+    }
+
+    fn compose(
+        a: &RichTextOperation<U>,
+        b: &RichTextOperation<U>,
+    ) -> Result<RichTextOperation<U>, OperationError> {
+        a.compose(b)
+    }
+
+    fn transform(
+        op: &RichTextOperation<U>,
+        other: &RichTextOperation<U>,
+        side: Side,
+    ) -> Result<RichTextOperation<U>, OperationError> {
+        other.transform_other(op, side == Side::Right)
+    }
+
+    fn transform_pair(
+        a: &RichTextOperation<U>,
+        b: &RichTextOperation<U>,
+        side: Side,
+    ) -> Result<(RichTextOperation<U>, RichTextOperation<U>), OperationError> {
+        a.transform_with_side(b, side)
+    }
+
+    fn invert(
+        snapshot: &RichTextOperation<U>,
+        op: &RichTextOperation<U>,
+    ) -> Result<RichTextOperation<U>, OperationError> {
+        op.invert(snapshot)
+    }
+
+    fn normalize(op: RichTextOperation<U>) -> RichTextOperation<U> {
+        // 通过 retain/insert/delete 构造的操作总是规范的
+        op
+    }
+
+    fn is_noop(op: &RichTextOperation<U>) -> bool {
+        op.is_noop()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Attributes, RichTextComponent, RichTextOperation};
+    use crate::core::test_util::random_string;
+    use crate::core::{Bytes, Chars, OperationError, Side, Utf16};
+    use rand::{self, Rng};
+    use serde_json::{json, Value};
+
+    fn random_attributes(allow_null: bool) -> Attributes {
+        let mut rng = rand::thread_rng();
+        let mut attributes = Attributes::new();
+        if rng.gen_bool(0.5) {
+            let values = [json!(true), json!("red"), json!(1), Value::Null];
+            for key in ["bold", "color", "header"] {
+                if rng.gen_bool(0.4) {
+                    let value = values[rng.gen_range(0..if allow_null { 4 } else { 3 })].clone();
+                    attributes.insert(key.to_string(), value);
+                }
+            }
+        }
+        return attributes;
+    }
+
+    fn random_text() -> String {
+        return random_string(rand::thread_rng().gen_range(1..4));
+    }
+
+    /// 随机插入文本或嵌入对象
+    fn random_insert(ops: &mut RichTextOperation) {
+        let mut rng = rand::thread_rng();
+        if rng.gen_bool(0.2) {
+            let embed =
+                [json!({"image": "a.png"}), json!({"formula": "x^2"})][rng.gen_range(0..2)].clone();
+            ops.insert_embed(embed, random_attributes(false));
+        } else {
+            ops.insert_with(random_text(), random_attributes(false));
+        }
+    }
+
+    fn random_document() -> RichTextOperation {
+        let mut doc = RichTextOperation::new();
+        for _ in 0..rand::thread_rng().gen_range(0..6) {
+            random_insert(&mut doc);
+        }
+        return doc;
+    }
+
+    fn random_operation(doc: &RichTextOperation) -> RichTextOperation {
+        let mut rng = rand::thread_rng();
+        let length = doc.length();
+        let mut ops = RichTextOperation::new();
+        let mut i = 0;
+        while i < length {
+            let n = rng.gen_range(1..=(length - i).min(4));
+            match rng.gen_range(0..4) {
+                0 => random_insert(&mut ops),
+                1 => {
+                    ops.delete(n);
+                    i += n;
+                }
+                2 => {
+                    ops.retain_with(n, random_attributes(true));
+                    i += n;
+                }
+                _ => {
+                    ops.retain(n);
+                    i += n;
+                }
+            }
+        }
+        if rng.gen_bool(0.3) {
+            random_insert(&mut ops);
+        }
+        return ops;
+    }
+
+    #[test]
+    fn test_push() {
+        let bold = json!({"bold": true}).as_object().unwrap().clone();
+        let mut ops = RichTextOperation::new();
+        ops.retain(1)
+            .retain(2)
+            .delete(1)
+            .insert("a")
+            .insert("b")
+            .delete(1)
+            .insert_with("c", bold.clone())
+            .retain(0)
+            .insert("");
+        assert_eq!(
+            &[
+                RichTextComponent::Retain(3, Attributes::new()),
+                RichTextComponent::Insert("ab".to_string(), Attributes::new()),
+                RichTextComponent::Insert("c".to_string(), bold),
+                RichTextComponent::Delete(2),
+            ],
+            ops.ops()
+        );
+        assert_eq!(5, ops.base_length());
+        assert!(!ops.is_document());
+        let mut noop = RichTextOperation::new();
+        noop.retain(3);
+        assert!(noop.is_noop());
+        assert!(
+            RichTextOperation::<Chars>::from_components(noop.ops().to_vec())
+                .ops()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_wire_format() {
+        let value = json!({"ops": [
+            {"retain": 1, "attributes": {"bold": null}},
+            {"insert": "a", "attributes": {"link": "https://example.com"}},
+            {"delete": 2},
+        ]});
+        let ops: RichTextOperation = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(value, serde_json::to_value(&ops).unwrap());
+        assert_eq!(ops, RichTextOperation::from_value(&value["ops"]).unwrap());
+        for invalid in [
+            json!([{"insert": 1}]),
+            json!([{"retain": -1}]),
+            json!([{"delete": 1, "attributes": {"bold": true}}]),
+            json!([{"retain": 1, "delete": 1}]),
+            json!([{"retain": 1, "attributes": 1}]),
+            json!({"op": []}),
+        ] {
+            assert_eq!(
+                Err(OperationError::DeltaInvalid),
+                RichTextOperation::<Utf16>::from_value(&invalid)
+            );
+        }
+    }
+
+    #[test]
+    fn test_utf16() {
+        let doc = RichTextOperation::<Utf16>::from_value(&json!([{"insert": "😄a"}])).unwrap();
+        let ops = RichTextOperation::<Utf16>::from_value(&json!([
+            {"retain": 2, "attributes": {"bold": true}},
+            {"insert": "b"},
+        ]))
+        .unwrap();
+        assert_eq!(
+            json!({"ops": [{"insert": "😄", "attributes": {"bold": true}}, {"insert": "ba"}]}),
+            doc.apply(&ops).unwrap().to_value()
+        );
+        let mut bad = RichTextOperation::<Utf16>::with_unit();
+        bad.retain_with(1, json!({"bold": true}).as_object().unwrap().clone());
+        assert_eq!(Err(OperationError::NotOnUnitBoundary), doc.apply(&bad));
+    }
+
+    #[test]
+    fn test_embed() {
+        let image = json!({"image": "cat.png"});
+        let bold = json!({"bold": true}).as_object().unwrap().clone();
+        // 无论长度单位是什么，嵌入对象都只占 1 个长度单位
+        let doc = RichTextOperation::<Bytes>::from_value(&json!([
+            {"insert": "中"},
+            {"insert": {"image": "cat.png"}},
+            {"insert": "文"},
+        ]))
+        .unwrap();
+        assert_eq!(7, doc.length());
+        let mut ops = RichTextOperation::<Bytes>::with_unit();
+        ops.retain(3).retain_with(1, bold.clone()).insert("!");
+        let after = doc.apply(&ops).unwrap();
+        assert_eq!(
+            &RichTextComponent::InsertEmbed(image.clone(), bold.clone()),
+            &after.ops()[1]
+        );
+        assert_eq!(doc, after.apply(&ops.invert(&doc).unwrap()).unwrap());
+
+        // 相邻的相同嵌入对象不会被合并
+        let mut two = RichTextOperation::new();
+        two.insert_embed(image.clone(), Attributes::new())
+            .insert_embed(image.clone(), Attributes::new());
+        assert_eq!(2, two.ops().len());
+
+        // 删除嵌入对象与修改其属性同时发生
+        let mut a = RichTextOperation::new();
+        a.delete(1);
+        let mut b = RichTextOperation::new();
+        b.retain_with(1, bold).insert("x");
+        let (a_prime, b_prime) = a.transform(&b).unwrap();
+        let left = two.apply(&a).unwrap().apply(&b_prime).unwrap();
+        assert_eq!(left, two.apply(&b).unwrap().apply(&a_prime).unwrap());
+        assert_eq!(
+            json!({"ops": [{"insert": "x"}, {"insert": {"image": "cat.png"}}]}),
+            left.to_value()
+        );
+        assert_eq!(
+            Err(OperationError::DeltaInvalid),
+            RichTextOperation::<Chars>::from_value(&json!([{"insert": ["image"]}]))
+        );
+    }
+
+    #[test]
+    fn test_random() {
+        for _ in 0..500 {
+            let doc = random_document();
+            let a = random_operation(&doc);
+            let b = random_operation(&doc);
+            let after_a = doc.apply(&a).unwrap();
+            assert!(after_a.is_document());
+
+            // compose
+            let c = random_operation(&after_a);
+            assert_eq!(
+                after_a.apply(&c).unwrap(),
+                doc.apply(&a.compose(&c).unwrap()).unwrap()
+            );
+
+            // transform
+            for &side in &[Side::Left, Side::Right] {
+                let (a_prime, b_prime) = a.transform_with_side(&b, side).unwrap();
+                assert_eq!(
+                    after_a.apply(&b_prime).unwrap(),
+                    doc.apply(&b).unwrap().apply(&a_prime).unwrap()
+                );
+            }
+
+            // invert
+            let inverse = a.invert(&doc).unwrap();
+            assert_eq!(doc, after_a.apply(&inverse).unwrap());
+
+            // transform_index 与 transform 一致：在该位置插入一个字符，转换后的插入位置即为转换后的下标
+            let length = doc.length();
+            let index = rand::thread_rng().gen_range(0..=length);
+            let mut insert = RichTextOperation::new();
+            insert.retain(index).insert("|");
+            let (_, insert_prime) = a.transform_with_side(&insert, Side::Right).unwrap();
+            let expected = match insert_prime.ops().first() {
+                Some(RichTextComponent::Retain(n, _)) => *n,
+                _ => 0,
+            };
+            assert_eq!(expected, a.transform_index(index, false));
+        }
+    }
+}
@@ -0,0 +1,716 @@
+//! # 撤销 / 重做
+//!
+//! 实现上参考了 [ot.js 的 UndoManager](https://github.com/Operational-Transformation/ot.js/blob/master/lib/undo-manager.js)。
+//!
+//! 撤销栈中保存的是本地操作的逆操作，撤销时弹出并应用到文档上，同时将其逆操作压入重做栈，重做同理。
+//! 连续的击键（例如一口气输入的一句话，或按住退格键的删除）会通过
+//! [`should_be_composed_with_inverted`](crate::core::TextOperation::should_be_composed_with_inverted)
+//! 判断后合并（compose）为撤销栈上的一项，从而一次撤销。
+//!
+//! 与 ot.js 通过回调通知调用方不同，[`UndoManager::perform_undo`] 与 [`UndoManager::perform_redo`]
+//! 直接返回需要应用到文档的操作，由调用方负责应用（以及作为本地操作发送给服务端）。
+//! 除相邻性之外，还可以通过 [`GroupingPolicy`] 按时间间隔、单词与换行边界以及每组的最大长度控制合并。
+//!
+//! 协同编辑时，收到的远端操作需要通过 [`UndoManager::transform`] 转换两个栈，使撤销只回退本地用户的修改。
+//!
+//! 栈中的每一项是一个 [`WrappedOperation`]，元数据（例如 [`SelectionMeta`](crate::core::SelectionMeta)）
+//! 随之一起被 invert、compose 与 transform，参见 [`UndoManager::add_wrapped`]。
+//!
+//! # Example
+//! ```
+//! use ot_rs::core::TextOperation;
+//! use ot_rs::undo::UndoManager;
+//!
+//! let mut undo: UndoManager = UndoManager::new();
+//! let mut doc = String::new();
+//! // 连续输入 "ab"，被合并为一项
+//! for (i, c) in ["a", "b"].iter().enumerate() {
+//!     let mut ops = TextOperation::new();
+//!     ops.retain(i).insert(*c);
+//!     undo.add(&ops, &doc).unwrap();
+//!     doc = ops.apply(doc.as_str()).unwrap();
+//! }
+//! assert_eq!(1, undo.undo_len());
+//!
+//! let ops = undo.perform_undo(&doc).unwrap().unwrap();
+//! doc = ops.apply(doc.as_str()).unwrap();
+//! assert_eq!("", doc);
+//! assert!(!undo.can_undo());
+//!
+//! let ops = undo.perform_redo(&doc).unwrap().unwrap();
+//! doc = ops.apply(doc.as_str()).unwrap();
+//! assert_eq!("ab", doc);
+//! ```
+
+use crate::core::{
+    Chars, Component, LengthUnit, Meta, OperationError, TextOperation, WrappedOperation,
+};
+
+/// 撤销栈的默认最大长度
+pub const DEFAULT_MAX_ITEMS: usize = 50;
+
+/// 撤销分组策略，决定满足相邻性（`should_be_composed_with_inverted`）的本地操作是否还应被合并为一组。
+///
+/// 默认不附加任何限制，即只按相邻性合并。
+/// # Example
+/// ```
+/// use ot_rs::core::TextOperation;
+/// use ot_rs::undo::{GroupingPolicy, UndoManager};
+/// let mut undo: UndoManager = UndoManager::new();
+/// undo.set_grouping_policy(GroupingPolicy {
+///     break_on_word: true,
+///     ..GroupingPolicy::default()
+/// });
+/// let mut doc = String::new();
+/// for c in "ab cd".chars() {
+///     let mut ops = TextOperation::new();
+///     ops.retain(doc.chars().count()).insert(c.to_string());
+///     undo.add(&ops, &doc).unwrap();
+///     doc = ops.apply(doc.as_str()).unwrap();
+/// }
+/// // "ab " 与 "cd" 各为一组
+/// assert_eq!(2, undo.undo_len());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct GroupingPolicy {
+    /// 与上一次修改的最大时间间隔（毫秒），超过时开始新的一组；
+    /// 只对通过 [`UndoManager::add_with_timestamp`] 记录的相邻两次修改生效
+    pub max_interval: Option<u64>,
+    /// 每组插入 / 删除内容的最大长度（以 `U` 为单位），超过时开始新的一组
+    pub max_group_size: Option<usize>,
+    /// 在单词边界处开始新的一组：紧跟在空白字符之后的非空白字符开始新的一组，即每个单词与其后的空白为一组
+    pub break_on_word: bool,
+    /// 插入 / 删除的内容包含换行符时开始新的一组
+    pub break_on_newline: bool,
+}
+
+/// 撤销 / 重做管理器，参见[模块文档](self)。`M` 为随每一项保存的元数据，默认不保存元数据
+#[derive(Debug, Clone, PartialEq)]
+pub struct UndoManager<U: LengthUnit = Chars, M: Meta = ()> {
+    max_items: usize,
+    undo_stack: Vec<WrappedOperation<M, U>>,
+    redo_stack: Vec<WrappedOperation<M, U>>,
+    /// 上一次撤销 / 重做之后，下一个本地操作不与栈顶合并
+    dont_compose: bool,
+    policy: GroupingPolicy,
+    /// 撤销栈顶这一组插入 / 删除内容的长度
+    group_size: usize,
+    /// 上一次修改插入 / 删除内容的最后一个字符
+    last_char: Option<char>,
+    /// 上一次修改的时间戳
+    last_timestamp: Option<u64>,
+}
+
+impl<U: LengthUnit, M: Meta> UndoManager<U, M> {
+    /// 构造一个撤销栈最大长度为 [`DEFAULT_MAX_ITEMS`] 的管理器
+    pub fn new() -> UndoManager<U, M> {
+        return UndoManager::with_max_items(DEFAULT_MAX_ITEMS);
+    }
+
+    /// 构造一个撤销栈最大长度为 `max_items` 的管理器，超出时丢弃最早的一项
+    /// # Example
+    /// ```
+    /// use ot_rs::core::TextOperation;
+    /// use ot_rs::undo::UndoManager;
+    /// let mut undo: UndoManager = UndoManager::with_max_items(1);
+    /// let mut a = TextOperation::new();
+    /// a.insert("a");
+    /// let mut b = TextOperation::new();
+    /// b.delete(1);
+    /// undo.add(&a, "").unwrap();
+    /// undo.add(&b, "a").unwrap();
+    /// assert_eq!(1, undo.undo_len());
+    /// ```
+    pub fn with_max_items(max_items: usize) -> UndoManager<U, M> {
+        return UndoManager {
+            max_items,
+            undo_stack: vec![],
+            redo_stack: vec![],
+            dont_compose: false,
+            policy: GroupingPolicy::default(),
+            group_size: 0,
+            last_char: None,
+            last_timestamp: None,
+        };
+    }
+
+    /// 当前的撤销分组策略
+    pub fn grouping_policy(&self) -> &GroupingPolicy {
+        &self.policy
+    }
+
+    /// 设置撤销分组策略，只影响之后记录的操作
+    pub fn set_grouping_policy(&mut self, policy: GroupingPolicy) {
+        self.policy = policy;
+    }
+
+    /// 记录一个应用到文档 `base` 上的本地操作 `operation`，并清空重做栈。
+    ///
+    /// 若其逆操作与撤销栈顶满足 `should_be_composed_with_inverted`，则合并为一项；
+    /// 紧跟在撤销 / 重做之后的操作，以及不满足 [`GroupingPolicy`] 的操作不会被合并。空操作不会被记录。
+    /// 元数据为 `M::default()`
+    pub fn add(&mut self, operation: &TextOperation<U>, base: &str) -> Result<(), OperationError>
+    where
+        M: Default,
+    {
+        return self.record(operation, M::default(), base, None);
+    }
+
+    /// 同 [`add`](Self::add)，并记录修改发生的时间戳 `timestamp`（毫秒），
+    /// 用于 [`GroupingPolicy::max_interval`] 的判断
+    /// # Example
+    /// ```
+    /// use ot_rs::core::TextOperation;
+    /// use ot_rs::undo::{GroupingPolicy, UndoManager};
+    /// let mut undo: UndoManager = UndoManager::new();
+    /// undo.set_grouping_policy(GroupingPolicy {
+    ///     max_interval: Some(1000),
+    ///     ..GroupingPolicy::default()
+    /// });
+    /// let mut doc = String::new();
+    /// for (i, timestamp) in [0, 500, 2000].iter().enumerate() {
+    ///     let mut ops = TextOperation::new();
+    ///     ops.retain(i).insert("a");
+    ///     undo.add_with_timestamp(&ops, &doc, *timestamp).unwrap();
+    ///     doc = ops.apply(doc.as_str()).unwrap();
+    /// }
+    /// assert_eq!(2, undo.undo_len());
+    /// ```
+    pub fn add_with_timestamp(
+        &mut self,
+        operation: &TextOperation<U>,
+        base: &str,
+        timestamp: u64,
+    ) -> Result<(), OperationError>
+    where
+        M: Default,
+    {
+        return self.record(operation, M::default(), base, Some(timestamp));
+    }
+
+    /// 同 [`add`](Self::add)，并随该项保存操作的元数据。撤销栈中保存的是逆操作，其元数据为 [`Meta::invert`] 的结果，
+    /// 例如对于 [`SelectionMeta`](crate::core::SelectionMeta)，撤销返回的操作的 `meta.after` 即为原操作之前的选择
+    /// # Example
+    /// ```
+    /// use ot_rs::core::{Selection, SelectionMeta, TextOperation, WrappedOperation};
+    /// use ot_rs::undo::UndoManager;
+    /// let mut undo: UndoManager<_, SelectionMeta> = UndoManager::new();
+    /// let mut ops = TextOperation::new();
+    /// ops.retain(2).insert("c");
+    /// let meta = SelectionMeta::new(Selection::cursor(2), Selection::cursor(3));
+    /// undo.add_wrapped(&WrappedOperation::new(ops, meta), "ab").unwrap();
+    ///
+    /// let undone = undo.perform_undo("abc").unwrap().unwrap();
+    /// assert_eq!("ab", undone.apply("abc").unwrap());
+    /// // 撤销后恢复原操作之前的选择
+    /// assert_eq!(Selection::cursor(2), undone.meta.after);
+    /// ```
+    pub fn add_wrapped(
+        &mut self,
+        operation: &WrappedOperation<M, U>,
+        base: &str,
+    ) -> Result<(), OperationError> {
+        return self.record(&operation.operation, operation.meta.clone(), base, None);
+    }
+
+    /// 同 [`add_wrapped`](Self::add_wrapped)，并记录修改发生的时间戳 `timestamp`（毫秒）
+    pub fn add_wrapped_with_timestamp(
+        &mut self,
+        operation: &WrappedOperation<M, U>,
+        base: &str,
+        timestamp: u64,
+    ) -> Result<(), OperationError> {
+        return self.record(
+            &operation.operation,
+            operation.meta.clone(),
+            base,
+            Some(timestamp),
+        );
+    }
+
+    fn record(
+        &mut self,
+        operation: &TextOperation<U>,
+        meta: M,
+        base: &str,
+        timestamp: Option<u64>,
+    ) -> Result<(), OperationError> {
+        if operation.is_noop() {
+            return Ok(());
+        }
+        let inverse = WrappedOperation::new(operation.invert(base)?, meta.invert());
+        // 插入或删除的内容，即原操作与逆操作中插入的字符串
+        let text: String = operation
+            .ops()
+            .chain(inverse.operation.ops())
+            .filter_map(|op| match op {
+                Component::Insert(str) => Some(str.as_str()),
+                _ => None,
+            })
+            .collect();
+        let size = U::len(&text);
+        let compose = !self.dont_compose && self.should_group(&text, size, timestamp);
+        match self.undo_stack.last_mut() {
+            Some(last)
+                if compose
+                    && inverse
+                        .operation
+                        .should_be_composed_with_inverted(&last.operation) =>
+            {
+                *last = inverse.compose(last)?;
+                self.group_size += size;
+            }
+            _ => {
+                self.undo_stack.push(inverse);
+                if self.undo_stack.len() > self.max_items {
+                    self.undo_stack.remove(0);
+                }
+                self.group_size = size;
+            }
+        }
+        self.last_char = text.chars().last();
+        self.last_timestamp = timestamp;
+        self.dont_compose = false;
+        self.redo_stack.clear();
+        return Ok(());
+    }
+
+    /// 根据分组策略判断插入 / 删除了 `text` 的修改是否可以并入上一组
+    fn should_group(&self, text: &str, size: usize, timestamp: Option<u64>) -> bool {
+        let policy = &self.policy;
+        if let (Some(max), Some(now), Some(last)) =
+            (policy.max_interval, timestamp, self.last_timestamp)
+        {
+            if now.saturating_sub(last) > max {
+                return false;
+            }
+        }
+        if let Some(max) = policy.max_group_size {
+            if self.group_size + size > max {
+                return false;
+            }
+        }
+        if policy.break_on_newline && text.contains('\n') {
+            return false;
+        }
+        if policy.break_on_word {
+            if let (Some(last), Some(first)) = (self.last_char, text.chars().next()) {
+                if last.is_whitespace() && !first.is_whitespace() {
+                    return false;
+                }
+            }
+        }
+        return true;
+    }
+
+    /// 弹出撤销栈顶，返回需要应用到当前文档 `document` 上的操作及其元数据，其逆操作压入重做栈。
+    /// 撤销栈为空时返回 `None`；出错时两个栈保持不变
+    pub fn perform_undo(
+        &mut self,
+        document: &str,
+    ) -> Result<Option<WrappedOperation<M, U>>, OperationError> {
+        let operation = match self.undo_stack.last() {
+            Some(operation) => operation,
+            None => return Ok(None),
+        };
+        let inverse = operation.invert(document)?;
+        self.redo_stack.push(inverse);
+        self.dont_compose = true;
+        return Ok(self.undo_stack.pop());
+    }
+
+    /// 弹出重做栈顶，返回需要应用到当前文档 `document` 上的操作及其元数据，其逆操作压入撤销栈。
+    /// 重做栈为空时返回 `None`；出错时两个栈保持不变
+    pub fn perform_redo(
+        &mut self,
+        document: &str,
+    ) -> Result<Option<WrappedOperation<M, U>>, OperationError> {
+        let operation = match self.redo_stack.last() {
+            Some(operation) => operation,
+            None => return Ok(None),
+        };
+        let inverse = operation.invert(document)?;
+        self.undo_stack.push(inverse);
+        self.dont_compose = true;
+        return Ok(self.redo_stack.pop());
+    }
+
+    /// 协同编辑时，收到一个应用到当前文档上的远端操作 `operation` 后，将撤销栈与重做栈中的每一项针对其进行转换，
+    /// 使得撤销只回退本地用户的修改，且能应用到最新的文档上。转换后成为空操作的项将被丢弃，
+    /// 元数据通过 [`Meta::transform`] 转换。出错时两个栈保持不变
+    /// # Example
+    /// ```
+    /// use ot_rs::core::TextOperation;
+    /// use ot_rs::undo::UndoManager;
+    /// let mut undo: UndoManager = UndoManager::new();
+    /// let mut local = TextOperation::new();
+    /// local.retain(2).insert("c");
+    /// undo.add(&local, "ab").unwrap();
+    /// let mut remote = TextOperation::new();
+    /// remote.insert("x").retain(3);
+    /// undo.transform(&remote).unwrap();
+    ///
+    /// let ops = undo.perform_undo("xabc").unwrap().unwrap();
+    /// assert_eq!("xab", ops.apply("xabc").unwrap());
+    /// ```
+    pub fn transform(&mut self, operation: &TextOperation<U>) -> Result<(), OperationError> {
+        let undo_stack = UndoManager::transform_stack(&self.undo_stack, operation)?;
+        let redo_stack = UndoManager::transform_stack(&self.redo_stack, operation)?;
+        self.undo_stack = undo_stack;
+        self.redo_stack = redo_stack;
+        return Ok(());
+    }
+
+    /// 栈顶基于当前文档，其下每一项基于应用了上一项之后的文档，因此从栈顶开始依次转换，并将远端操作随之转换
+    fn transform_stack(
+        stack: &[WrappedOperation<M, U>],
+        operation: &TextOperation<U>,
+    ) -> Result<Vec<WrappedOperation<M, U>>, OperationError> {
+        let mut operation = operation.clone();
+        let mut result = Vec::with_capacity(stack.len());
+        for entry in stack.iter().rev() {
+            let (entry_prime, operation_prime) = entry.operation.transform(&operation)?;
+            if !entry_prime.is_noop() {
+                result.push(WrappedOperation::new(
+                    entry_prime,
+                    entry.meta.transform(&operation),
+                ));
+            }
+            operation = operation_prime;
+        }
+        result.reverse();
+        return Ok(result);
+    }
+
+    /// 是否可以撤销
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    /// 是否可以重做
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// 撤销栈的长度
+    pub fn undo_len(&self) -> usize {
+        self.undo_stack.len()
+    }
+
+    /// 重做栈的长度
+    pub fn redo_len(&self) -> usize {
+        self.redo_stack.len()
+    }
+
+    /// 清空撤销栈与重做栈
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.dont_compose = false;
+        self.group_size = 0;
+        self.last_char = None;
+        self.last_timestamp = None;
+    }
+}
+
+impl<U: LengthUnit, M: Meta> Default for UndoManager<U, M> {
+    fn default() -> Self {
+        return UndoManager::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{GroupingPolicy, UndoManager};
+    use crate::core::{Chars, Selection, SelectionMeta, TextOperation, WrappedOperation};
+    use rand::{self, Rng};
+
+    fn apply(undo: &mut UndoManager, doc: &mut String, ops: &TextOperation) {
+        undo.add(ops, doc).unwrap();
+        *doc = ops.apply(doc.as_str()).unwrap();
+    }
+
+    #[test]
+    fn test_grouping() {
+        let mut undo: UndoManager = UndoManager::new();
+        let mut doc = "xy".to_string();
+        // 连续输入
+        for (i, c) in ["a", "b", "c"].iter().enumerate() {
+            let mut ops = TextOperation::new();
+            ops.retain(1 + i).insert(*c).retain(1);
+            apply(&mut undo, &mut doc, &ops);
+        }
+        assert_eq!("xabcy", doc);
+        assert_eq!(1, undo.undo_len());
+        // 连续退格
+        for i in 0..2 {
+            let mut ops = TextOperation::new();
+            ops.retain(3 - i).delete(1).retain(1);
+            apply(&mut undo, &mut doc, &ops);
+        }
+        assert_eq!("xay", doc);
+        assert_eq!(2, undo.undo_len());
+
+        let ops = undo.perform_undo(&doc).unwrap().unwrap();
+        doc = ops.apply(doc.as_str()).unwrap();
+        assert_eq!("xabcy", doc);
+        // 撤销之后的操作不与栈顶合并，并清空重做栈
+        let mut ops = TextOperation::new();
+        ops.retain(4).insert("d").retain(1);
+        apply(&mut undo, &mut doc, &ops);
+        assert_eq!(2, undo.undo_len());
+        assert!(!undo.can_redo());
+
+        while let Some(ops) = undo.perform_undo(&doc).unwrap() {
+            doc = ops.apply(doc.as_str()).unwrap();
+        }
+        assert_eq!("xy", doc);
+        assert_eq!(None, undo.perform_undo(&doc).unwrap());
+        assert_eq!(2, undo.redo_len());
+
+        // 出错时栈保持不变
+        assert!(undo.perform_redo("").is_err());
+        assert_eq!(2, undo.redo_len());
+    }
+
+    fn random_operation(rng: &mut impl Rng, doc: &str) -> TextOperation {
+        let mut target = String::new();
+        for c in doc.chars() {
+            if rng.gen_bool(0.9) {
+                target.push(c);
+            }
+            if rng.gen_bool(0.1) {
+                target.push(rng.gen_range('a'..='e'));
+            }
+        }
+        return TextOperation::diff(doc, &target);
+    }
+
+    fn type_text(undo: &mut UndoManager, doc: &mut String, text: &str, timestamp: &mut u64) {
+        for c in text.chars() {
+            let mut ops = TextOperation::new();
+            ops.retain(doc.chars().count()).insert(c.to_string());
+            undo.add_with_timestamp(&ops, doc, *timestamp).unwrap();
+            *doc = ops.apply(doc.as_str()).unwrap();
+            *timestamp += 100;
+        }
+    }
+
+    #[test]
+    fn test_grouping_policy() {
+        let text = "hello world\nfoo bar";
+        let cases = vec![
+            (GroupingPolicy::default(), 1),
+            (
+                GroupingPolicy {
+                    break_on_word: true,
+                    ..GroupingPolicy::default()
+                },
+                4,
+            ),
+            (
+                GroupingPolicy {
+                    break_on_newline: true,
+                    ..GroupingPolicy::default()
+                },
+                2,
+            ),
+            (
+                GroupingPolicy {
+                    break_on_word: true,
+                    break_on_newline: true,
+                    ..GroupingPolicy::default()
+                },
+                5,
+            ),
+            (
+                GroupingPolicy {
+                    max_group_size: Some(5),
+                    ..GroupingPolicy::default()
+                },
+                4,
+            ),
+            (
+                GroupingPolicy {
+                    max_interval: Some(99),
+                    ..GroupingPolicy::default()
+                },
+                text.chars().count(),
+            ),
+        ];
+        for (policy, groups) in cases {
+            let mut undo: UndoManager = UndoManager::new();
+            undo.set_grouping_policy(policy.clone());
+            let mut doc = String::new();
+            let mut timestamp = 0;
+            type_text(&mut undo, &mut doc, text, &mut timestamp);
+            assert_eq!(groups, undo.undo_len(), "{:?}", policy);
+        }
+
+        // 按时间间隔分组，之后的退格按单词分组
+        let mut undo: UndoManager = UndoManager::new();
+        undo.set_grouping_policy(GroupingPolicy {
+            max_interval: Some(1000),
+            break_on_word: true,
+            ..GroupingPolicy::default()
+        });
+        let mut doc = String::new();
+        let mut timestamp = 0;
+        type_text(&mut undo, &mut doc, "ab", &mut timestamp);
+        timestamp += 1000;
+        type_text(&mut undo, &mut doc, "cd ef", &mut timestamp);
+        assert_eq!(3, undo.undo_len());
+        for _ in 0..4 {
+            let len = doc.chars().count();
+            let mut ops = TextOperation::new();
+            ops.retain(len - 1).delete(1);
+            undo.add(&ops, &doc).unwrap();
+            doc = ops.apply(doc.as_str()).unwrap();
+        }
+        assert_eq!("abc", doc);
+        // "ef " 与 "d" 两组
+        assert_eq!(5, undo.undo_len());
+        let ops = undo.perform_undo(&doc).unwrap().unwrap();
+        assert_eq!("abcd", ops.apply(doc.as_str()).unwrap());
+    }
+
+    #[test]
+    fn test_selection_meta() {
+        let mut undo: UndoManager<Chars, SelectionMeta> = UndoManager::new();
+        let mut doc = String::new();
+        for (i, c) in "abc".chars().enumerate() {
+            let mut ops = TextOperation::new();
+            ops.retain(i).insert(c.to_string());
+            let meta = SelectionMeta::new(Selection::cursor(i), Selection::cursor(i + 1));
+            undo.add_wrapped(&WrappedOperation::new(ops.clone(), meta), &doc)
+                .unwrap();
+            doc = ops.apply(doc.as_str()).unwrap();
+        }
+        assert_eq!(1, undo.undo_len());
+        // 远端在开头插入，选择随之转换
+        let mut remote = TextOperation::new();
+        remote.insert("X").retain(3);
+        undo.transform(&remote).unwrap();
+        doc = remote.apply(doc.as_str()).unwrap();
+
+        let undone = undo.perform_undo(&doc).unwrap().unwrap();
+        doc = undone.apply(doc.as_str()).unwrap();
+        assert_eq!("X", doc);
+        // 撤销恢复第一次输入之前的选择
+        assert_eq!(Selection::cursor(1), undone.meta.after);
+        assert_eq!(Selection::cursor(4), undone.meta.before);
+
+        let redone = undo.perform_redo(&doc).unwrap().unwrap();
+        doc = redone.apply(doc.as_str()).unwrap();
+        assert_eq!("Xabc", doc);
+        assert_eq!(Selection::cursor(4), redone.meta.after);
+    }
+
+    #[test]
+    fn test_random() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..200 {
+            let mut undo: UndoManager = UndoManager::with_max_items(1000);
+            undo.set_grouping_policy(GroupingPolicy {
+                max_interval: None,
+                max_group_size: Some(rng.gen_range(1..10)),
+                break_on_word: rng.gen(),
+                break_on_newline: rng.gen(),
+            });
+            let original = "hello world".to_string();
+            let mut doc = original.clone();
+            for _ in 0..rng.gen_range(1..20) {
+                if rng.gen_bool(0.3) {
+                    if let Some(ops) = undo.perform_undo(&doc).unwrap() {
+                        doc = ops.apply(doc.as_str()).unwrap();
+                    }
+                } else if rng.gen_bool(0.2) {
+                    if let Some(ops) = undo.perform_redo(&doc).unwrap() {
+                        doc = ops.apply(doc.as_str()).unwrap();
+                    }
+                } else {
+                    let ops = random_operation(&mut rng, &doc);
+                    apply(&mut undo, &mut doc, &ops);
+                }
+            }
+            // 重做全部后再撤销全部，回到原始文档
+            while let Some(ops) = undo.perform_redo(&doc).unwrap() {
+                doc = ops.apply(doc.as_str()).unwrap();
+            }
+            let last = doc.clone();
+            while let Some(ops) = undo.perform_undo(&doc).unwrap() {
+                doc = ops.apply(doc.as_str()).unwrap();
+            }
+            assert_eq!(original, doc);
+            while let Some(ops) = undo.perform_redo(&doc).unwrap() {
+                doc = ops.apply(doc.as_str()).unwrap();
+            }
+            assert_eq!(last, doc);
+        }
+    }
+
+    fn random_insert(rng: &mut impl Rng, doc: &str, c: char) -> TextOperation {
+        let len = doc.chars().count();
+        let index = rng.gen_range(0..=len);
+        let mut ops = TextOperation::new();
+        ops.retain(index).insert(c.to_string()).retain(len - index);
+        return ops;
+    }
+
+    #[test]
+    fn test_transform() {
+        let mut undo: UndoManager = UndoManager::new();
+        let mut doc = "xy".to_string();
+        let mut ops = TextOperation::new();
+        ops.retain(2).insert("abc");
+        apply(&mut undo, &mut doc, &ops);
+        // 远端删除了本地插入的内容，撤销栈中对应的项被丢弃
+        let mut remote = TextOperation::new();
+        remote.retain(1).delete(4);
+        undo.transform(&remote).unwrap();
+        doc = remote.apply(doc.as_str()).unwrap();
+        assert_eq!("x", doc);
+        assert!(!undo.can_undo());
+        // 基础长度不匹配时，栈保持不变
+        let mut ops = TextOperation::new();
+        ops.retain(1).insert("z");
+        apply(&mut undo, &mut doc, &ops);
+        assert!(undo.transform(&remote).is_err());
+        assert_eq!(1, undo.undo_len());
+    }
+
+    #[test]
+    fn test_random_transform() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..200 {
+            // 本地插入大写字母，远端插入小写字母，撤销全部后只剩下原始文档与远端的修改
+            let mut undo: UndoManager = UndoManager::with_max_items(1000);
+            let mut doc = "hello world".to_string();
+            for _ in 0..rng.gen_range(1..30) {
+                let r = rng.gen_range(0..10);
+                if r < 4 {
+                    let c = rng.gen_range('A'..='E');
+                    let ops = random_insert(&mut rng, &doc, c);
+                    apply(&mut undo, &mut doc, &ops);
+                } else if r < 8 {
+                    let c = rng.gen_range('a'..='e');
+                    let remote = random_insert(&mut rng, &doc, c);
+                    undo.transform(&remote).unwrap();
+                    doc = remote.apply(doc.as_str()).unwrap();
+                } else if r < 9 {
+                    if let Some(ops) = undo.perform_undo(&doc).unwrap() {
+                        doc = ops.apply(doc.as_str()).unwrap();
+                    }
+                } else if let Some(ops) = undo.perform_redo(&doc).unwrap() {
+                    doc = ops.apply(doc.as_str()).unwrap();
+                }
+            }
+            let expected: String = doc.chars().filter(|c| !c.is_uppercase()).collect();
+            while let Some(ops) = undo.perform_undo(&doc).unwrap() {
+                doc = ops.apply(doc.as_str()).unwrap();
+            }
+            assert_eq!(expected, doc);
+        }
+    }
+}